@@ -0,0 +1,526 @@
+use crate::result::{OpenCliError, Result};
+use bzip2::read::MultiBzDecoder;
+use flate2::read::GzDecoder;
+use lzma_rust2::XzReader;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use tar::{Archive, EntryType};
+use zip::ZipArchive;
+
+const COPY_BUFFER_SIZE: usize = 64 * 1024;
+/// Longest symlink target read from a zip entry
+const MAX_LINK_TARGET_LEN: u64 = 4096;
+
+const S_IFMT: u32 = 0o170000;
+const S_IFREG: u32 = 0o100000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFLNK: u32 = 0o120000;
+
+const SUPPORTED_FORMATS: &str = "zip, tar, tar.gz, tar.xz, tar.bz2";
+
+/// File name suffixes and the matching format.
+const FORMAT_SUFFIXES: &[(&str, ArchiveFormat)] = &[
+    (".zip", ArchiveFormat::Zip),
+    (".tar", ArchiveFormat::Tar),
+    (".tar.gz", ArchiveFormat::TarGz),
+    (".tgz", ArchiveFormat::TarGz),
+    (".tar.xz", ArchiveFormat::TarXz),
+    (".txz", ArchiveFormat::TarXz),
+    (".tar.bz2", ArchiveFormat::TarBz2),
+    (".tbz2", ArchiveFormat::TarBz2),
+    (".tbz", ArchiveFormat::TarBz2),
+];
+
+/// Archive formats that are recognized but cannot be extracted.
+const UNSUPPORTED_SUFFIXES: &[(&str, &str)] = &[(".rar", "RAR"), (".7z", "7-Zip")];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+    TarXz,
+    TarBz2,
+}
+
+/// Files written by an extraction, plus every entry name for layout detection.
+#[derive(Debug, Default)]
+pub struct ExtractedArchive {
+    /// Extracted file on disk and its path inside the archive
+    pub files: Vec<(PathBuf, String)>,
+    /// Names of all entries, including directories
+    pub entries: Vec<String>,
+}
+
+/// An entry that was refused because extracting it would be unsafe.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RejectedEntry {
+    pub name: String,
+    pub reason: String,
+}
+
+/// A symlink or hard link whose target is materialized after all entries are read.
+struct PendingLink {
+    name: String,
+    path: PathBuf,
+    target: PathBuf,
+}
+
+/// State collected while walking the entries of one archive.
+struct Extraction<'a> {
+    dest_dir: &'a Path,
+    extracted: ExtractedArchive,
+    written: HashSet<PathBuf>,
+    links: Vec<PendingLink>,
+    rejected: Vec<RejectedEntry>,
+}
+
+/** Streaming archive extractor shared by compiler and package installs
+ *
+ * # Behaviour
+ * - Entries are copied to disk through a fixed-size buffer, so memory use
+ *   does not grow with the size of the archive or of single entries
+ * - Compressed tar archives (gzip, xz, bzip2) are decompressed on the fly
+ *   while reading
+ * - Runs on the blocking thread pool to keep the async runtime responsive
+ *
+ * # Safety
+ * - Entries with absolute paths, drive prefixes or `..` components are
+ *   rejected, so nothing is written outside the destination directory
+ * - Device files, FIFOs and sockets are rejected
+ * - Symlinks and hard links are never created on disk; a link to a regular
+ *   file inside the archive is extracted as a copy of that file, any other
+ *   link is rejected
+ * - If any entry is rejected the files already written are removed and the
+ *   extraction fails with the name of every rejected entry
+ *
+ * # Example
+ * ```ignore
+ * let extracted = ArchiveExtractor::new(&temp_dir)
+ *     .extract(Path::new("sscanf.zip"), ArchiveFormat::Zip)
+ *     .await?;
+ * ```
+ */
+pub struct ArchiveExtractor {
+    dest_dir: PathBuf,
+}
+
+impl ArchiveFormat {
+    /** Detects the archive format from a file name
+     *
+     * # Returns
+     * - `Ok(Some(format))` for archives that can be extracted
+     * - `Ok(None)` for files that are not archives
+     * - An error for archives in a format that cannot be extracted (rar, 7z)
+     */
+    pub fn from_file_name(file_name: &str) -> Result<Option<Self>> {
+        let lower = file_name.to_lowercase();
+
+        if let Some((_, format)) = FORMAT_SUFFIXES
+            .iter()
+            .find(|(suffix, _)| lower.ends_with(suffix))
+        {
+            return Ok(Some(*format));
+        }
+
+        match UNSUPPORTED_SUFFIXES
+            .iter()
+            .find(|(suffix, _)| lower.ends_with(suffix))
+        {
+            Some((_, kind)) => Err(OpenCliError::Process(
+                format!(
+                    "Unsupported archive format: {} is a {} archive, which cannot be extracted (supported: {})",
+                    file_name, kind, SUPPORTED_FORMATS
+                )
+                .into(),
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Parses the `method` of a compiler platform entry in `compilers.toml`,
+    /// which names a format by its extension (`zip`, `tgz`, `tar.xz`, ...).
+    pub fn from_method(method: &str) -> Result<Self> {
+        let suffix = format!(".{}", method.trim_start_matches('.').to_lowercase());
+
+        FORMAT_SUFFIXES
+            .iter()
+            .find(|(known, _)| *known == suffix)
+            .map(|(_, format)| *format)
+            .ok_or_else(|| {
+                OpenCliError::Config(
+                    format!(
+                        "Unsupported extraction method: {} (supported: {})",
+                        method, SUPPORTED_FORMATS
+                    )
+                    .into(),
+                )
+            })
+    }
+}
+
+impl ArchiveExtractor {
+    pub fn new(dest_dir: &Path) -> Self {
+        Self {
+            dest_dir: dest_dir.to_path_buf(),
+        }
+    }
+
+    pub async fn extract(
+        &self,
+        archive_path: &Path,
+        format: ArchiveFormat,
+    ) -> Result<ExtractedArchive> {
+        let archive_path = archive_path.to_path_buf();
+        let dest_dir = self.dest_dir.clone();
+
+        tokio::task::spawn_blocking(move || {
+            std::fs::create_dir_all(&dest_dir)?;
+            let file = File::open(&archive_path)?;
+            let mut extraction = Extraction::new(&dest_dir);
+
+            match format {
+                ArchiveFormat::Zip => Self::extract_zip(file, &mut extraction)?,
+                ArchiveFormat::Tar => Self::extract_tar(BufReader::new(file), &mut extraction)?,
+                ArchiveFormat::TarGz => {
+                    Self::extract_tar(GzDecoder::new(BufReader::new(file)), &mut extraction)?
+                }
+                ArchiveFormat::TarXz => {
+                    Self::extract_tar(XzReader::new(BufReader::new(file), true), &mut extraction)?
+                }
+                ArchiveFormat::TarBz2 => {
+                    Self::extract_tar(MultiBzDecoder::new(BufReader::new(file)), &mut extraction)?
+                }
+            }
+
+            extraction.finish(&archive_path)
+        })
+        .await
+        .map_err(|e| OpenCliError::Process(format!("Extraction task failed: {}", e).into()))?
+    }
+
+    fn extract_zip(file: File, extraction: &mut Extraction) -> Result<()> {
+        let mut archive = ZipArchive::new(BufReader::new(file))
+            .map_err(|e| OpenCliError::Process(format!("Invalid ZIP archive: {}", e).into()))?;
+
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i).map_err(|e| {
+                OpenCliError::Process(format!("ZIP extraction error: {}", e).into())
+            })?;
+
+            let name = entry.name().to_string();
+            let file_type = entry.unix_mode().map(|mode| mode & S_IFMT).unwrap_or(0);
+
+            let Some(relative) = extraction.check_path(&name) else {
+                continue;
+            };
+
+            if entry.is_dir() || file_type == S_IFDIR {
+                extraction.create_dir(&name, &relative)?;
+                continue;
+            }
+
+            match file_type {
+                0 | S_IFREG => {}
+                S_IFLNK => {
+                    let mut target = String::new();
+                    (&mut entry)
+                        .take(MAX_LINK_TARGET_LEN)
+                        .read_to_string(&mut target)
+                        .map_err(|e| {
+                            OpenCliError::Process(
+                                format!("ZIP read error in {}: {}", name, e).into(),
+                            )
+                        })?;
+                    extraction.add_link(&name, &relative, Path::new(&target), true);
+                    continue;
+                }
+                _ => {
+                    extraction.reject(&name, "device or special file");
+                    continue;
+                }
+            }
+
+            let mode = entry.unix_mode();
+            extraction.write_file(&name, relative, &mut entry, mode)?;
+        }
+
+        Ok(())
+    }
+
+    fn extract_tar<R: Read>(reader: R, extraction: &mut Extraction) -> Result<()> {
+        let mut archive = Archive::new(reader);
+
+        for entry in archive
+            .entries()
+            .map_err(|e| OpenCliError::Process(format!("TAR extraction error: {}", e).into()))?
+        {
+            let mut entry = entry
+                .map_err(|e| OpenCliError::Process(format!("TAR entry error: {}", e).into()))?;
+
+            let name = String::from_utf8_lossy(&entry.path_bytes()).to_string();
+            let entry_type = entry.header().entry_type();
+
+            if matches!(
+                entry_type,
+                EntryType::Char | EntryType::Block | EntryType::Fifo
+            ) {
+                extraction.reject(&name, "device or special file");
+                continue;
+            }
+
+            let Some(relative) = extraction.check_path(&name) else {
+                continue;
+            };
+
+            if entry_type.is_dir() {
+                extraction.create_dir(&name, &relative)?;
+                continue;
+            }
+
+            if entry_type.is_symlink() || entry_type.is_hard_link() {
+                let target = entry
+                    .link_name_bytes()
+                    .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+                    .unwrap_or_default();
+                extraction.add_link(
+                    &name,
+                    &relative,
+                    Path::new(&target),
+                    entry_type.is_symlink(),
+                );
+                continue;
+            }
+
+            if !entry_type.is_file() {
+                continue;
+            }
+
+            let mode = entry.header().mode().ok();
+            extraction.write_file(&name, relative, &mut entry, mode)?;
+        }
+
+        Ok(())
+    }
+
+    /// Copies one entry to disk in fixed-size chunks.
+    fn write_entry(
+        reader: &mut impl Read,
+        output_path: &Path,
+        mode: Option<u32>,
+    ) -> std::io::Result<u64> {
+        if let Some(parent) = output_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut output = BufWriter::with_capacity(COPY_BUFFER_SIZE, File::create(output_path)?);
+        let written = std::io::copy(reader, &mut output)?;
+        output.flush()?;
+
+        #[cfg(unix)]
+        if mode.is_some_and(|mode| mode & 0o111 != 0) {
+            use std::os::unix::fs::PermissionsExt;
+            // Only the executable bit is carried over, never setuid or world-writable
+            std::fs::set_permissions(output_path, std::fs::Permissions::from_mode(0o755))?;
+        }
+        #[cfg(not(unix))]
+        let _ = mode;
+
+        Ok(written)
+    }
+}
+
+impl<'a> Extraction<'a> {
+    fn new(dest_dir: &'a Path) -> Self {
+        Self {
+            dest_dir,
+            extracted: ExtractedArchive::default(),
+            written: HashSet::new(),
+            links: Vec::new(),
+            rejected: Vec::new(),
+        }
+    }
+
+    fn reject(&mut self, name: &str, reason: &str) {
+        log::warn!("Rejected archive entry {}: {}", name, reason);
+        self.rejected.push(RejectedEntry {
+            name: name.to_string(),
+            reason: reason.to_string(),
+        });
+    }
+
+    /// Records the entry name and returns its sanitized relative path, or
+    /// `None` when the entry was rejected or names the archive root.
+    fn check_path(&mut self, name: &str) -> Option<PathBuf> {
+        match sanitize_entry_path(name) {
+            Ok(relative) if relative.as_os_str().is_empty() => None,
+            Ok(relative) => {
+                self.extracted.entries.push(name.to_string());
+                Some(relative)
+            }
+            Err(reason) => {
+                self.reject(name, reason);
+                None
+            }
+        }
+    }
+
+    fn create_dir(&mut self, name: &str, relative: &Path) -> Result<()> {
+        std::fs::create_dir_all(self.dest_dir.join(relative))
+            .map_err(|e| OpenCliError::Process(format!("Failed to create {}: {}", name, e).into()))
+    }
+
+    fn write_file(
+        &mut self,
+        name: &str,
+        relative: PathBuf,
+        reader: &mut impl Read,
+        mode: Option<u32>,
+    ) -> Result<()> {
+        let output_path = self.dest_dir.join(&relative);
+        ArchiveExtractor::write_entry(reader, &output_path, mode)
+            .map_err(|e| OpenCliError::Process(format!("Read error in {}: {}", name, e).into()))?;
+
+        self.written.insert(relative);
+        self.extracted.files.push((output_path, name.to_string()));
+        Ok(())
+    }
+
+    /// Queues a link for materialization once its target has been extracted.
+    fn add_link(&mut self, name: &str, relative: &Path, target: &Path, symbolic: bool) {
+        // Symlink targets are relative to the link, hard link targets to the root
+        let base = if symbolic {
+            relative.parent().unwrap_or(Path::new(""))
+        } else {
+            Path::new("")
+        };
+
+        match resolve_link_target(base, target) {
+            Ok(target) => self.links.push(PendingLink {
+                name: name.to_string(),
+                path: relative.to_path_buf(),
+                target,
+            }),
+            Err(reason) => self.reject(name, reason),
+        }
+    }
+
+    /// Copies link targets into place, then fails if anything was rejected.
+    fn finish(mut self, archive_path: &Path) -> Result<ExtractedArchive> {
+        for link in std::mem::take(&mut self.links) {
+            if !self.written.contains(&link.target) {
+                self.reject(
+                    &link.name,
+                    "link target is not a regular file in the archive",
+                );
+                continue;
+            }
+
+            let output_path = self.dest_dir.join(&link.path);
+            if let Some(parent) = output_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::copy(self.dest_dir.join(&link.target), &output_path)?;
+
+            self.written.insert(link.path);
+            self.extracted.files.push((output_path, link.name));
+        }
+
+        if self.rejected.is_empty() {
+            return Ok(self.extracted);
+        }
+
+        for (path, _) in &self.extracted.files {
+            let _ = std::fs::remove_file(path);
+        }
+
+        let details: Vec<String> = self
+            .rejected
+            .iter()
+            .map(|entry| format!("  - {}: {}", entry.name, entry.reason))
+            .collect();
+
+        Err(OpenCliError::Process(
+            format!(
+                "Refusing to extract {}: {} unsafe entr{}\n{}",
+                archive_path.display(),
+                self.rejected.len(),
+                if self.rejected.len() == 1 { "y" } else { "ies" },
+                details.join("\n")
+            )
+            .into(),
+        ))
+    }
+}
+
+/** Turns an archive entry name into a path relative to the extraction root
+ *
+ * # Rules
+ * - Both `/` and `\\` separate components, so Windows-style names cannot
+ *   sneak a `..` past the check
+ * - Absolute paths and drive prefixes (`C:`) are rejected
+ * - `..` components are rejected; `.` and empty components are dropped
+ *
+ * # Returns
+ * - The relative path (empty for the archive root), or the rejection reason
+ */
+fn sanitize_entry_path(name: &str) -> std::result::Result<PathBuf, &'static str> {
+    if name.contains('\0') {
+        return Err("entry name contains a NUL byte");
+    }
+
+    if name.starts_with('/') || name.starts_with('\\') {
+        return Err("absolute path");
+    }
+
+    let mut path = PathBuf::new();
+    for (index, component) in name.split(['/', '\\']).enumerate() {
+        match component {
+            "" | "." => {}
+            ".." => return Err("path traversal outside the extraction directory"),
+            _ if index == 0 && component.contains(':') => {
+                return Err("absolute path (drive prefix)")
+            }
+            _ => path.push(component),
+        }
+    }
+
+    // Defence in depth against platform-specific prefixes
+    if path
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_)))
+    {
+        return Err("absolute path");
+    }
+
+    Ok(path)
+}
+
+/// Resolves a link target against `base`, refusing anything outside the root.
+fn resolve_link_target(base: &Path, target: &Path) -> std::result::Result<PathBuf, &'static str> {
+    let target = target.to_string_lossy();
+    if target.is_empty() {
+        return Err("link without a target");
+    }
+    if target.starts_with('/') || target.starts_with('\\') {
+        return Err("link to an absolute path");
+    }
+
+    let mut resolved: Vec<&str> = base.iter().filter_map(|c| c.to_str()).collect();
+    for (index, component) in target.split(['/', '\\']).enumerate() {
+        match component {
+            "" | "." => {}
+            ".." => {
+                if resolved.pop().is_none() {
+                    return Err("link target escapes the extraction directory");
+                }
+            }
+            _ if index == 0 && component.contains(':') => return Err("link to an absolute path"),
+            _ => resolved.push(component),
+        }
+    }
+
+    Ok(resolved.iter().collect())
+}
@@ -1,14 +1,19 @@
+use crate::network::NetworkConfig;
+use crate::package::{PackageManifest, PackageSource};
 use crate::result::{OpenCliError, Result};
 use serde::{Deserialize, Serialize};
 use smol_str::SmolStr;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use tokio::fs;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BuildConfig {
     pub build: Build,
-    pub packages: Option<HashMap<SmolStr, PackageSpec>>,
+    pub toolchain: Option<ToolchainConfig>,
+    pub network: Option<NetworkConfig>,
+    pub package: Option<PackageManifest>,
+    pub packages: Option<BTreeMap<SmolStr, PackageSpec>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,6 +23,7 @@ pub struct Build {
     pub compiler_version: String,
     pub includes: Option<BuildIncludes>,
     pub args: Option<BuildArgs>,
+    pub matrix: Option<BuildMatrix>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,6 +36,35 @@ pub struct BuildArgs {
     pub args: Vec<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildMatrix {
+    pub compiler_versions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolchainConfig {
+    #[serde(default)]
+    pub location: ToolchainLocation,
+    #[serde(default)]
+    pub kind: ToolchainKind,
+    pub wrapper: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolchainKind {
+    #[default]
+    Pawncc,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolchainLocation {
+    #[default]
+    User,
+    Project,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum PackageSpec {
@@ -38,15 +73,31 @@ pub enum PackageSpec {
         version: SmolStr,
         target: Option<PackageTarget>,
     },
+    /// Fetched from a git host, a local path or a URL instead of GitHub releases
+    Source {
+        #[serde(flatten)]
+        source: PackageSource,
+        target: Option<PackageTarget>,
+    },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum PackageTarget {
     Components,
     Plugins,
 }
 
+impl PackageTarget {
+    /// Name used in `opencli.toml` and for the server folder.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PackageTarget::Components => "components",
+            PackageTarget::Plugins => "plugins",
+        }
+    }
+}
+
 impl Default for BuildConfig {
     fn default() -> Self {
         Self {
@@ -66,7 +117,11 @@ impl Default for BuildConfig {
                         "-Z+".to_string(),
                     ],
                 }),
+                matrix: None,
             },
+            toolchain: None,
+            network: None,
+            package: None,
             packages: None,
         }
     }
@@ -111,7 +166,7 @@ impl BuildConfig {
 
     pub fn add_package(&mut self, name: SmolStr, spec: PackageSpec) {
         if self.packages.is_none() {
-            self.packages = Some(HashMap::new());
+            self.packages = Some(BTreeMap::new());
         }
         self.packages.as_mut().unwrap().insert(name, spec);
     }
@@ -124,10 +179,33 @@ impl BuildConfig {
         }
     }
 
-    pub fn get_packages(&self) -> Option<&HashMap<SmolStr, PackageSpec>> {
+    pub fn get_packages(&self) -> Option<&BTreeMap<SmolStr, PackageSpec>> {
         self.packages.as_ref()
     }
 
+    pub fn toolchain_location(&self) -> ToolchainLocation {
+        self.toolchain
+            .as_ref()
+            .map(|t| t.location)
+            .unwrap_or_default()
+    }
+
+    pub fn matrix_compiler_versions(&self) -> Vec<String> {
+        self.build
+            .matrix
+            .as_ref()
+            .map(|m| m.compiler_versions.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn compiler_args(&self) -> &[String] {
+        self.build
+            .args
+            .as_ref()
+            .map(|a| a.args.as_slice())
+            .unwrap_or_default()
+    }
+
     pub fn get_include_paths(&self) -> Vec<PathBuf> {
         self.build
             .includes
@@ -137,18 +215,38 @@ impl BuildConfig {
     }
 }
 
+impl ToolchainLocation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ToolchainLocation::User => "user",
+            ToolchainLocation::Project => "project",
+        }
+    }
+}
+
 impl PackageSpec {
+    /// Version constraint, `*` for packages that are not GitHub releases.
     pub fn version(&self) -> &str {
         match self {
             PackageSpec::Simple(version) => version,
             PackageSpec::Detailed { version, .. } => version,
+            PackageSpec::Source { .. } => "*",
         }
     }
 
     pub fn target(&self) -> Option<&PackageTarget> {
         match self {
             PackageSpec::Simple(_) => None,
-            PackageSpec::Detailed { target, .. } => target.as_ref(),
+            PackageSpec::Detailed { target, .. } | PackageSpec::Source { target, .. } => {
+                target.as_ref()
+            }
+        }
+    }
+
+    pub fn source(&self) -> Option<&PackageSource> {
+        match self {
+            PackageSpec::Source { source, .. } => Some(source),
+            _ => None,
         }
     }
 
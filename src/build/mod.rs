@@ -1,3 +1,4 @@
 pub mod config;
+pub mod sampctl;
 
 pub use config::*;
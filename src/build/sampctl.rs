@@ -0,0 +1,357 @@
+use crate::build::{
+    BuildArgs, BuildConfig, BuildIncludes, BuildMatrix, PackageSpec, PackageTarget,
+};
+use crate::package::manifest::{is_toolchain_provided, parse_sampctl_dependency};
+use crate::package::PackageManifest;
+use crate::result::{OpenCliError, Result};
+use serde::Deserialize;
+use smol_str::SmolStr;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// sampctl project files, in the order sampctl itself looks for them.
+pub const SAMPCTL_FILES: [&str; 2] = ["pawn.json", "pawn.yaml"];
+
+/** A sampctl project, as declared in `pawn.json` or `pawn.yaml`
+ *
+ * # Notes
+ * - Only the fields with an `opencli.toml` equivalent are modelled; every
+ *   other field is kept in `other` so the import can report it
+ */
+#[derive(Debug, Default, Deserialize)]
+pub struct SampctlProject {
+    pub user: Option<String>,
+    pub repo: Option<String>,
+    pub entry: Option<PathBuf>,
+    pub output: Option<PathBuf>,
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    #[serde(default)]
+    pub dev_dependencies: Vec<String>,
+    pub include_path: Option<PathBuf>,
+    #[serde(default)]
+    pub builds: Vec<SampctlBuild>,
+    pub build: Option<SampctlBuild>,
+    pub runtime: Option<SampctlRuntime>,
+    #[serde(default)]
+    pub runtimes: Vec<SampctlRuntime>,
+    #[serde(flatten)]
+    pub other: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SampctlBuild {
+    pub name: Option<String>,
+    /// A string, or a number when YAML parsed an unquoted `3.10` as `3.1`
+    pub version: Option<serde_json::Value>,
+    pub args: Option<Vec<String>>,
+    #[serde(default)]
+    pub includes: Vec<PathBuf>,
+    #[serde(default)]
+    pub constants: BTreeMap<String, serde_json::Value>,
+    pub input: Option<PathBuf>,
+    pub output: Option<PathBuf>,
+    #[serde(flatten)]
+    pub other: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SampctlRuntime {
+    pub name: Option<String>,
+    #[serde(default)]
+    pub plugins: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub components: Vec<serde_json::Value>,
+    #[serde(flatten)]
+    pub other: BTreeMap<String, serde_json::Value>,
+}
+
+/// Result of converting a sampctl project.
+#[derive(Debug)]
+pub struct SampctlImport {
+    pub config: BuildConfig,
+    /// Settings that have no `opencli.toml` equivalent, one line each
+    pub unmapped: Vec<String>,
+}
+
+impl SampctlProject {
+    /// Finds `pawn.json` or `pawn.yaml` in `dir`.
+    pub fn find(dir: &Path) -> Option<PathBuf> {
+        SAMPCTL_FILES
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file())
+    }
+
+    pub async fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).await?;
+
+        let is_yaml = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml"));
+
+        if is_yaml {
+            Self::from_yaml(&content)
+        } else {
+            Self::from_json(&content)
+        }
+    }
+
+    pub fn from_json(content: &str) -> Result<Self> {
+        Ok(serde_json::from_str(content)?)
+    }
+
+    pub fn from_yaml(content: &str) -> Result<Self> {
+        serde_yaml_ng::from_str(content)
+            .map_err(|e| OpenCliError::Config(format!("Invalid pawn.yaml: {}", e).into()))
+    }
+
+    /** Converts the project into an `opencli.toml` configuration
+     *
+     * # Mapping
+     * - `entry` and `output` (or the first build's `input`/`output`) become
+     *   `[build]` files
+     * - The first build provides the compiler version, arguments, include
+     *   paths and constants (`NAME=value` arguments); the compiler versions
+     *   of further builds go to `[build.matrix]`
+     * - `dependencies` become `[packages]`, runtime `plugins` and
+     *   `components` become packages with the matching `target`
+     * - Everything else is listed in [`SampctlImport::unmapped`]
+     */
+    pub fn to_opencli(&self) -> SampctlImport {
+        let mut unmapped = Vec::new();
+        let mut config = BuildConfig::default();
+
+        let builds: Vec<&SampctlBuild> = self.build.iter().chain(&self.builds).collect();
+        let first_build = builds.first().copied();
+
+        let entry = first_build
+            .and_then(|build| build.input.clone())
+            .or_else(|| self.entry.clone());
+        match entry {
+            Some(entry) => config.build.entry_file = entry,
+            None => unmapped.push(format!(
+                "entry: not set, using {}",
+                config.build.entry_file.display()
+            )),
+        }
+
+        config.build.output_file = first_build
+            .and_then(|build| build.output.clone())
+            .or_else(|| self.output.clone())
+            .unwrap_or_else(|| config.build.entry_file.with_extension("amx"));
+
+        let mut include_paths = config.get_include_paths();
+        if let Some(include_path) = &self.include_path {
+            if include_path != Path::new(".") && !include_paths.contains(include_path) {
+                include_paths.push(include_path.clone());
+            }
+        }
+
+        if let Some(build) = first_build {
+            self.map_build(build, &mut config, &mut include_paths, &mut unmapped);
+        }
+
+        config.build.includes = Some(BuildIncludes {
+            paths: include_paths,
+        });
+
+        let mut matrix = Vec::new();
+        for (index, build) in builds.iter().enumerate().skip(1) {
+            let label = build_label(build, index);
+            match build.version_string().as_deref().map(compiler_version) {
+                Some(version) if version != config.build.compiler_version => {
+                    unmapped.push(format!(
+                        "{}: only the first build is imported, compiler {} is added to [build.matrix]",
+                        label, version
+                    ));
+                    if !matrix.contains(&version) {
+                        matrix.push(version);
+                    }
+                }
+                _ => unmapped.push(format!("{}: only the first build is imported", label)),
+            }
+        }
+        if !matrix.is_empty() {
+            matrix.insert(0, config.build.compiler_version.clone());
+            config.build.matrix = Some(BuildMatrix {
+                compiler_versions: matrix,
+            });
+        }
+
+        for dependency in &self.dependencies {
+            add_dependency(&mut config, dependency, None, "dependency", &mut unmapped);
+        }
+        for dependency in &self.dev_dependencies {
+            unmapped.push(format!(
+                "dev dependency {}: opencli has no development-only packages",
+                dependency
+            ));
+        }
+
+        let single_runtime = self.runtime.is_some() && self.runtimes.is_empty();
+        for (index, runtime) in self.runtime.iter().chain(&self.runtimes).enumerate() {
+            let label = match &runtime.name {
+                Some(name) => format!("runtime \"{}\"", name),
+                None if single_runtime => "runtime".to_string(),
+                None => format!("runtime {}", index + 1),
+            };
+
+            for (entries, target, kind) in [
+                (&runtime.plugins, PackageTarget::Plugins, "plugin"),
+                (&runtime.components, PackageTarget::Components, "component"),
+            ] {
+                for entry in entries {
+                    match entry.as_str() {
+                        Some(name) if name.contains('/') => add_dependency(
+                            &mut config,
+                            name,
+                            Some(target.clone()),
+                            &format!("{} {}", label, kind),
+                            &mut unmapped,
+                        ),
+                        _ => unmapped.push(format!(
+                            "{} {} {}: not a GitHub repository, copy the binary into the server manually",
+                            label, kind, entry
+                        )),
+                    }
+                }
+            }
+
+            if !runtime.other.is_empty() {
+                unmapped.push(format!(
+                    "{} settings ({}): server settings belong in config.json",
+                    label,
+                    runtime.other.keys().cloned().collect::<Vec<_>>().join(", ")
+                ));
+            }
+        }
+
+        if let (Some(user), Some(repo)) = (&self.user, &self.repo) {
+            config.package = Some(PackageManifest {
+                name: Some(format!("{}/{}", user, repo).into()),
+                dependencies: None,
+            });
+        }
+
+        for key in self.other.keys() {
+            unmapped.push(format!("{}: no opencli.toml equivalent", key));
+        }
+
+        SampctlImport { config, unmapped }
+    }
+
+    fn map_build(
+        &self,
+        build: &SampctlBuild,
+        config: &mut BuildConfig,
+        include_paths: &mut Vec<PathBuf>,
+        unmapped: &mut Vec<String>,
+    ) {
+        let label = build_label(build, 0);
+
+        if let Some(version) = build.version_string() {
+            if !build.version.as_ref().is_some_and(|v| v.is_string()) {
+                unmapped.push(format!(
+                    "{} version {}: written as a number, check it (quote versions like \"3.10\")",
+                    label, version
+                ));
+            }
+            config.build.compiler_version = compiler_version(&version);
+        }
+
+        let mut args = build
+            .args
+            .clone()
+            .unwrap_or_else(|| config.compiler_args().to_vec());
+        for (name, value) in &build.constants {
+            match value {
+                serde_json::Value::String(value) => args.push(format!("{}={}", name, value)),
+                serde_json::Value::Null => args.push(name.clone()),
+                value => args.push(format!("{}={}", name, value)),
+            }
+        }
+        config.build.args = Some(BuildArgs { args });
+
+        for include in &build.includes {
+            if !include_paths.contains(include) {
+                include_paths.push(include.clone());
+            }
+        }
+
+        for key in build.other.keys() {
+            unmapped.push(format!("{} {}: no opencli.toml equivalent", label, key));
+        }
+    }
+}
+
+impl SampctlBuild {
+    fn version_string(&self) -> Option<String> {
+        self.version.as_ref().map(|version| match version {
+            serde_json::Value::String(version) => version.clone(),
+            other => other.to_string(),
+        })
+    }
+}
+
+fn add_dependency(
+    config: &mut BuildConfig,
+    dependency: &str,
+    target: Option<PackageTarget>,
+    kind: &str,
+    unmapped: &mut Vec<String>,
+) {
+    let (repo, spec) = match parse_sampctl_dependency(dependency) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            unmapped.push(format!("{} {}: {}", kind, dependency, reason(&e)));
+            return;
+        }
+    };
+
+    if is_toolchain_provided(&repo) {
+        unmapped.push(format!(
+            "{} {}: provided by the open.mp compiler (qawno/include)",
+            kind, dependency
+        ));
+        return;
+    }
+
+    let spec = match spec {
+        PackageSpec::Source { source, .. } => PackageSpec::Source { source, target },
+        spec => {
+            // `=tag` is written as the plain tag, which pins the same version
+            let version: SmolStr = spec.version().trim_start_matches('=').into();
+            match target {
+                Some(target) => PackageSpec::new_detailed(version, Some(target)),
+                None => PackageSpec::new_simple(version),
+            }
+        }
+    };
+    config.add_package(repo, spec);
+}
+
+fn reason(error: &OpenCliError) -> String {
+    match error {
+        OpenCliError::Config(message) => message.to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn build_label(build: &SampctlBuild, index: usize) -> String {
+    build.name.as_ref().map_or_else(
+        || format!("build {}", index + 1),
+        |name| format!("build \"{}\"", name),
+    )
+}
+
+/// sampctl writes compiler versions without the `v` prefix of release tags.
+fn compiler_version(version: &str) -> String {
+    if version.starts_with('v') {
+        version.to_string()
+    } else {
+        format!("v{}", version)
+    }
+}
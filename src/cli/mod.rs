@@ -1,6 +1,7 @@
 pub mod parser;
 
 use crate::commands::CommandExecutor;
+use crate::network::NetworkSettings;
 use crate::result::Result;
 use clap::Parser;
 
@@ -14,6 +15,13 @@ use clap::Parser;
     help_template = "{before-help}{name} v{version}\nAuthor: {author}\n\n{about-with-newline}\n{usage-heading} {usage}\n\n{all-args}{after-help}"
 )]
 pub struct Cli {
+    #[arg(
+        long,
+        global = true,
+        help = "Use only cached compilers and packages, never access the network (also OPENCLI_OFFLINE)"
+    )]
+    offline: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -39,6 +47,14 @@ pub enum Commands {
 
         #[arg(long, help = "Update compiler configuration from remote")]
         update_config: bool,
+
+        #[arg(
+            long,
+            value_delimiter = ',',
+            num_args = 0..,
+            help = "Build with each compiler version (comma separated, defaults to [build.matrix])"
+        )]
+        compiler_matrix: Option<Vec<String>>,
     },
 
     #[command(about = "Setup project with default opencli.toml")]
@@ -58,6 +74,24 @@ pub enum Commands {
         #[command(subcommand)]
         action: PackageAction,
     },
+
+    #[command(about = "Manage GitHub credentials")]
+    Auth {
+        #[command(subcommand)]
+        action: AuthAction,
+    },
+
+    #[command(about = "Manage the package store shared by all projects")]
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+
+    #[command(about = "Import a project from another tool")]
+    Import {
+        #[command(subcommand)]
+        source: ImportSource,
+    },
 }
 
 #[derive(Parser)]
@@ -76,11 +110,34 @@ pub enum InstallComponent {
 pub enum PackageAction {
     #[command(about = "Install packages")]
     Install {
-        #[arg(help = "Package to install (owner/repo or owner/repo=version)")]
+        #[arg(
+            help = "Package to install (owner/repo, owner/repo=version, owner/repo@branch or owner/repo#commit)"
+        )]
         package: Option<String>,
 
         #[arg(long, help = "Target folder (components or plugins)")]
         target: Option<String>,
+
+        #[arg(
+            long,
+            conflicts_with = "package",
+            help = "Install exactly what opencli.lock records, failing if it is out of sync"
+        )]
+        locked: bool,
+
+        #[arg(
+            long,
+            conflicts_with = "package",
+            help = "Like --locked, and download nothing but the locked artifacts"
+        )]
+        frozen: bool,
+
+        #[arg(
+            short,
+            long,
+            help = "Packages to download and extract at once (default: 4)"
+        )]
+        jobs: Option<usize>,
     },
 
     #[command(about = "Remove package")]
@@ -95,6 +152,12 @@ pub enum PackageAction {
     #[command(about = "Check package integrity")]
     Check,
 
+    #[command(about = "Show installed packages with newer releases")]
+    Outdated {
+        #[arg(long, help = "Print the report as JSON")]
+        json: bool,
+    },
+
     #[command(about = "Update package")]
     Update {
         #[arg(help = "Package to update (owner/repo)")]
@@ -102,9 +165,64 @@ pub enum PackageAction {
 
         #[arg(long, help = "Update all packages")]
         all: bool,
+
+        #[arg(
+            short,
+            long,
+            help = "Packages to download and extract at once (default: 4)"
+        )]
+        jobs: Option<usize>,
+    },
+}
+
+#[derive(Parser)]
+pub enum CacheAction {
+    #[command(about = "Remove stored packages that no known opencli.lock references")]
+    Gc {
+        #[arg(long, help = "Only list what would be removed")]
+        dry_run: bool,
     },
 }
 
+#[derive(Parser)]
+pub enum ImportSource {
+    #[command(about = "Convert a sampctl pawn.json or pawn.yaml into opencli.toml")]
+    Sampctl {
+        #[arg(help = "pawn.json/pawn.yaml or its directory (default: current directory)")]
+        path: Option<String>,
+
+        #[arg(long, help = "Force overwrite existing opencli.toml")]
+        force: bool,
+    },
+}
+
+#[derive(Parser)]
+pub enum AuthAction {
+    #[command(about = "Store a GitHub token for a host")]
+    Login {
+        #[arg(
+            long,
+            help = "Host to log in to (default: host of the configured GitHub API)"
+        )]
+        host: Option<String>,
+
+        #[arg(long, help = "Read the token from standard input instead of prompting")]
+        with_token: bool,
+    },
+
+    #[command(about = "Remove the stored token for a host")]
+    Logout {
+        #[arg(
+            long,
+            help = "Host to log out from (default: host of the configured GitHub API)"
+        )]
+        host: Option<String>,
+    },
+
+    #[command(about = "Show stored credentials and verify them")]
+    Status,
+}
+
 impl Default for Cli {
     fn default() -> Self {
         Self::parse()
@@ -117,6 +235,18 @@ impl Cli {
     }
 
     pub async fn execute(self) -> Result<()> {
+        if self.offline {
+            NetworkSettings::set_offline(true);
+        }
+
+        if let Commands::Build {
+            config: Some(config),
+            ..
+        } = &self.command
+        {
+            NetworkSettings::set_project_config(config);
+        }
+
         let mut executor = CommandExecutor::new();
 
         match self.command {
@@ -126,9 +256,16 @@ impl Cli {
                 verbose,
                 force_download,
                 update_config,
+                compiler_matrix,
             } => {
                 executor
-                    .build_project(config, verbose, force_download, update_config)
+                    .build_project(
+                        config,
+                        verbose,
+                        force_download,
+                        update_config,
+                        compiler_matrix,
+                    )
                     .await
             }
             Commands::Setup { force } => executor.setup_project(force).await,
@@ -138,6 +275,11 @@ impl Cli {
                 }
             },
             Commands::Package { action } => executor.handle_package_action(action).await,
+            Commands::Auth { action } => executor.handle_auth_action(action).await,
+            Commands::Cache { action } => executor.handle_cache_action(action).await,
+            Commands::Import { source } => match source {
+                ImportSource::Sampctl { path, force } => executor.import_sampctl(path, force).await,
+            },
         }
     }
 }
@@ -0,0 +1,198 @@
+use crate::credentials::{mask_token, register_secret, CredentialStore, HostCredentials};
+use crate::network::NetworkSettings;
+use crate::result::{OpenCliError, Result};
+use reqwest::header::{ACCEPT, AUTHORIZATION};
+use reqwest::StatusCode;
+use std::io::Read;
+
+/// Account details returned by the API for a token.
+struct TokenInfo {
+    login: String,
+    scopes: Option<String>,
+}
+
+/** Stores a GitHub token for `host` after checking it against the API
+ *
+ * # Arguments
+ * * `host` - Host to store the token for, defaults to the configured API host
+ * * `with_token` - Read the token from stdin (for scripts) instead of prompting
+ */
+pub async fn login(host: Option<&str>, with_token: bool) -> Result<()> {
+    let settings = NetworkSettings::current();
+    let host = resolve_host(host);
+
+    let token = if with_token {
+        let mut input = String::new();
+        std::io::stdin().read_to_string(&mut input)?;
+        input
+    } else {
+        println!(
+            "Create a token at https://{}/settings/tokens (the `repo` scope is needed for private repositories)",
+            host
+        );
+        rpassword::prompt_password(format!("Paste a GitHub token for {}: ", host))?
+    };
+    let token = token.trim().to_string();
+
+    if token.is_empty() {
+        return Err(OpenCliError::Config("No token provided".into()));
+    }
+    register_secret(&token);
+
+    NetworkSettings::ensure_online(&format!("Token verification for {}", host))?;
+    let info = verify_token(settings, &host, &token).await?;
+
+    let mut store = CredentialStore::load()?;
+    store.set(
+        &host,
+        HostCredentials {
+            token,
+            user: Some(info.login.clone()),
+        },
+    );
+    store.save()?;
+
+    log::info!("Stored token for {} ({})", host, info.login);
+    println!("Logged in to {} as {}", host, info.login);
+    if let Some(scopes) = info.scopes.filter(|s| !s.is_empty()) {
+        println!("Token scopes: {}", scopes);
+    }
+    println!("Token saved to {}", store.path().display());
+
+    if host == settings.github_host() && std::env::var("GITHUB_TOKEN").is_ok() {
+        println!("Note: GITHUB_TOKEN is set and takes precedence over the stored token");
+    }
+
+    Ok(())
+}
+
+pub fn logout(host: Option<&str>) -> Result<()> {
+    let host = resolve_host(host);
+    let mut store = CredentialStore::load()?;
+
+    if store.remove(&host).is_none() {
+        println!("Not logged in to {}", host);
+        return Ok(());
+    }
+
+    store.save()?;
+    log::info!("Removed stored token for {}", host);
+    println!("Logged out of {}", host);
+
+    if host == NetworkSettings::current().github_host() && std::env::var("GITHUB_TOKEN").is_ok() {
+        println!("Note: GITHUB_TOKEN is still set and will be used for API requests");
+    }
+
+    Ok(())
+}
+
+/// Lists stored tokens and the environment override, verifying each one online.
+pub async fn status() -> Result<()> {
+    let settings = NetworkSettings::current();
+    let store = CredentialStore::load()?;
+    let api_host = settings.github_host();
+    let env_token = std::env::var("GITHUB_TOKEN")
+        .ok()
+        .filter(|token| !token.is_empty());
+
+    if env_token.is_none() && store.hosts().next().is_none() {
+        println!("Not logged in to any host. Run `opencli auth login` to add a token.");
+        return Ok(());
+    }
+
+    if let Some(token) = &env_token {
+        register_secret(token);
+        println!("GITHUB_TOKEN");
+        println!("  Token: {}", mask_token(token));
+        println!("  Used for: {} (overrides the stored token)", api_host);
+        print_verification(settings, &api_host, token).await;
+    }
+
+    for (host, credentials) in store.hosts() {
+        let active = host == &api_host && env_token.is_none();
+        println!("{}{}", host, if active { " (active)" } else { "" });
+        if let Some(user) = &credentials.user {
+            println!("  User: {}", user);
+        }
+        println!("  Token: {}", mask_token(&credentials.token));
+        print_verification(settings, host, &credentials.token).await;
+    }
+
+    println!();
+    println!("Credentials file: {}", store.path().display());
+
+    Ok(())
+}
+
+async fn print_verification(settings: &NetworkSettings, host: &str, token: &str) {
+    if NetworkSettings::is_offline() {
+        println!("  Status: not verified (offline)");
+        return;
+    }
+
+    match verify_token(settings, host, token).await {
+        Ok(info) => println!("  Status: valid, authenticated as {}", info.login),
+        Err(e) => println!("  Status: {}", e),
+    }
+}
+
+async fn verify_token(settings: &NetworkSettings, host: &str, token: &str) -> Result<TokenInfo> {
+    let url = format!("{}/user", settings.api_url_for_host(host));
+
+    let response = settings
+        .http_client()?
+        .get(&url)
+        .header(AUTHORIZATION, format!("Bearer {}", token))
+        .header(ACCEPT, "application/vnd.github+json")
+        .send()
+        .await
+        .map_err(|e| OpenCliError::Server(format!("Could not reach {}: {}", host, e).into()))?;
+
+    let status = response.status();
+    if status == StatusCode::UNAUTHORIZED {
+        return Err(OpenCliError::Config(
+            format!("{} rejected the token (401 Unauthorized)", host).into(),
+        ));
+    }
+    if !status.is_success() {
+        return Err(OpenCliError::Server(
+            format!("Could not verify the token with {}: HTTP {}", url, status).into(),
+        ));
+    }
+
+    let scopes = response
+        .headers()
+        .get("x-oauth-scopes")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+
+    let body = response.text().await.map_err(|e| {
+        OpenCliError::Server(format!("Invalid response from {}: {}", url, e).into())
+    })?;
+    let user: serde_json::Value = serde_json::from_str(&body)?;
+    let login = user
+        .get("login")
+        .and_then(|login| login.as_str())
+        .unwrap_or("unknown user")
+        .to_string();
+
+    Ok(TokenInfo { login, scopes })
+}
+
+/// Normalizes `--host` (accepting URLs) or falls back to the configured API host.
+fn resolve_host(host: Option<&str>) -> String {
+    let Some(host) = host else {
+        return NetworkSettings::current().github_host();
+    };
+
+    let host = host
+        .trim()
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .trim_end_matches('/');
+
+    match host {
+        "api.github.com" => "github.com".to_string(),
+        _ => host.to_string(),
+    }
+}
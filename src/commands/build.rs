@@ -1,17 +1,17 @@
 use crate::build::BuildConfig;
-use crate::compiler::CompilerManager;
+use crate::compiler::{load_toolchain, validate_compiler_version, Invocation, Toolchain};
 use crate::result::{OpenCliError, Result};
 use indicatif::{ProgressBar, ProgressStyle};
 use std::path::Path;
 use std::process::Stdio;
 use std::time::Instant;
-use tokio::process::Command;
 
 pub async fn execute(
     config_path: Option<&str>,
     verbose: bool,
     force_download: bool,
     update_config: bool,
+    compiler_matrix: Option<Vec<String>>,
 ) -> Result<()> {
     let mut cmd = BuildCommand::new();
     cmd.execute(
@@ -19,6 +19,7 @@ pub async fn execute(
         verbose,
         force_download,
         update_config,
+        compiler_matrix,
     )
     .await
 }
@@ -37,6 +38,7 @@ impl BuildCommand {
         verbose: bool,
         force_download: bool,
         update_config: bool,
+        compiler_matrix: Option<Vec<String>>,
     ) -> Result<()> {
         println!("Building project...");
 
@@ -56,12 +58,36 @@ impl BuildCommand {
             config.build.entry_file.display()
         );
 
+        if let Some(requested) = compiler_matrix {
+            build_spinner.finish_and_clear();
+
+            let versions = if requested.is_empty() {
+                config.matrix_compiler_versions()
+            } else {
+                requested
+            };
+
+            if versions.is_empty() {
+                return Err(OpenCliError::Config(
+                    "No compiler versions given. Pass --compiler-matrix v3.10.10,v3.10.11 or add a [build.matrix] section".into(),
+                ));
+            }
+
+            return self
+                .execute_matrix(&config, &versions, force_download, update_config)
+                .await;
+        }
+
         if verbose {
             build_spinner.finish_and_clear();
             println!("Build configuration:");
             println!("  Entry file: {}", config.build.entry_file.display());
             println!("  Output file: {}", config.build.output_file.display());
             println!("  Compiler version: {}", config.build.compiler_version);
+            println!(
+                "  Toolchain location: {}",
+                config.toolchain_location().as_str()
+            );
 
             build_spinner.set_message("Preparing compiler...");
             build_spinner.enable_steady_tick(std::time::Duration::from_millis(100));
@@ -69,25 +95,36 @@ impl BuildCommand {
             build_spinner.set_message("Preparing compiler...");
         }
 
-        let mut compiler_manager = if update_config {
-            CompilerManager::new_with_update().await?
-        } else {
-            CompilerManager::new().await?
-        };
+        let mut toolchain = load_toolchain(&config, update_config).await?;
+
+        toolchain.validate_args(&config.build.compiler_version, config.compiler_args())?;
 
-        let compiler_path = compiler_manager
-            .get_compiler_path(&config.build.compiler_version, force_download)
+        let compiler_path = toolchain
+            .install(&config.build.compiler_version, force_download)
             .await?;
 
         if verbose {
             build_spinner.finish_and_clear();
-            println!("Using compiler: {}", compiler_path.display());
+            let detected = toolchain
+                .detect_version(&compiler_path)
+                .await
+                .ok()
+                .flatten()
+                .unwrap_or_else(|| "unknown version".to_string());
+            println!(
+                "Using compiler: {} ({} {})",
+                compiler_path.display(),
+                toolchain.name(),
+                detected
+            );
         }
 
         build_spinner.set_message("Compiling project...");
         log::info!("Using compiler: {}", compiler_path.display());
 
-        let result = self.compile_project(&config, &compiler_path, verbose).await;
+        let result = self
+            .compile_project(&config, toolchain.as_ref(), &compiler_path, verbose)
+            .await;
         build_spinner.finish_and_clear();
 
         result
@@ -112,6 +149,7 @@ impl BuildCommand {
     async fn compile_project(
         &self,
         config: &BuildConfig,
+        toolchain: &dyn Toolchain,
         compiler_path: &Path,
         verbose: bool,
     ) -> Result<()> {
@@ -132,52 +170,12 @@ impl BuildCommand {
         }
 
         let compile_start = Instant::now();
-        let mut cmd = Command::new(compiler_path);
-        cmd.current_dir(&current_dir);
-
-        if let Some(compiler_dir) = Path::new(compiler_path).parent() {
-            let mut ld_path = compiler_dir.to_string_lossy().to_string();
-            if let Ok(existing_ld) = std::env::var("LD_LIBRARY_PATH") {
-                ld_path = format!("{}:{}", ld_path, existing_ld);
-            }
-            cmd.env("LD_LIBRARY_PATH", ld_path);
-            log::debug!("Set LD_LIBRARY_PATH to: {}", compiler_dir.display());
-        }
-
-        let output_arg = format!("-o{}", config.build.output_file.display());
-        cmd.arg(&output_arg);
-
-        if let Some(includes) = &config.build.includes {
-            for include_path in &includes.paths {
-                let full_include_path = current_dir.join(include_path);
-                if full_include_path.exists() {
-                    let include_arg = format!("-i{}", full_include_path.display());
-                    cmd.arg(&include_arg);
-                }
-            }
-        }
-
-        let mut has_debug_flags = false;
-        let mut processed_args = Vec::new();
-
-        if let Some(args) = &config.build.args {
-            for arg in &args.args {
-                if arg == "-d2" || arg == "-d3" {
-                    has_debug_flags = true;
-                    processed_args.push(arg.clone());
-                } else if arg.starts_with("-O") && has_debug_flags {
-                    continue;
-                } else {
-                    processed_args.push(arg.clone());
-                }
-            }
-        }
-
-        for arg in processed_args {
-            cmd.arg(&arg);
-        }
-
-        cmd.arg(&config.build.entry_file);
+        let invocation = self.build_invocation(config, &current_dir, &config.build.output_file);
+        let mut cmd = toolchain.invoke(compiler_path, &invocation);
+        let has_debug_flags = config
+            .compiler_args()
+            .iter()
+            .any(|arg| arg == "-d2" || arg == "-d3");
 
         if verbose || has_debug_flags {
             cmd.stdout(Stdio::inherit());
@@ -255,6 +253,242 @@ impl BuildCommand {
 
         Ok(())
     }
+
+    fn build_invocation<'a>(
+        &self,
+        config: &'a BuildConfig,
+        current_dir: &'a Path,
+        output_file: &'a Path,
+    ) -> Invocation<'a> {
+        let include_paths = config
+            .get_include_paths()
+            .into_iter()
+            .map(|path| current_dir.join(path))
+            .filter(|path| path.exists())
+            .collect();
+
+        Invocation {
+            current_dir,
+            entry_file: &config.build.entry_file,
+            output_file,
+            include_paths,
+            args: config.compiler_args(),
+        }
+    }
+
+    async fn execute_matrix(
+        &self,
+        config: &BuildConfig,
+        versions: &[String],
+        force_download: bool,
+        update_config: bool,
+    ) -> Result<()> {
+        let current_dir = std::env::current_dir().map_err(|e| {
+            OpenCliError::Process(format!("Failed to get current directory: {}", e).into())
+        })?;
+
+        let entry_path = current_dir.join(&config.build.entry_file);
+        if !entry_path.exists() {
+            return Err(OpenCliError::NotFound(
+                format!("Entry file not found: {}", entry_path.display()).into(),
+            ));
+        }
+
+        let mut toolchain = load_toolchain(config, update_config).await?;
+
+        let output_name = config
+            .build
+            .output_file
+            .file_name()
+            .map(|n| n.to_os_string())
+            .unwrap_or_else(|| "output.amx".into());
+
+        let mut results = Vec::new();
+
+        for version in versions {
+            println!("Building with compiler {}...", version);
+            log::info!("Matrix build with compiler {}", version);
+
+            if let Err(e) = validate_compiler_version(version) {
+                results.push(MatrixResult {
+                    version: version.clone(),
+                    status: MatrixStatus::Error,
+                    duration: None,
+                    diagnostics: e.to_string(),
+                });
+                continue;
+            }
+
+            if let Err(e) = toolchain.validate_args(version, config.compiler_args()) {
+                log::error!("Compiler {} rejects build arguments: {}", version, e);
+                results.push(MatrixResult {
+                    version: version.clone(),
+                    status: MatrixStatus::Error,
+                    duration: None,
+                    diagnostics: e.to_string(),
+                });
+                continue;
+            }
+
+            let compiler_path = match toolchain.install(version, force_download).await {
+                Ok(path) => path,
+                Err(e) => {
+                    log::error!("Failed to prepare compiler {}: {}", version, e);
+                    results.push(MatrixResult {
+                        version: version.clone(),
+                        status: MatrixStatus::Error,
+                        duration: None,
+                        diagnostics: e.to_string(),
+                    });
+                    continue;
+                }
+            };
+
+            let output_file = current_dir
+                .join(".opencli")
+                .join("matrix")
+                .join(version)
+                .join(&output_name);
+            if let Some(output_dir) = output_file.parent() {
+                tokio::fs::create_dir_all(output_dir).await?;
+            }
+
+            let invocation = self.build_invocation(config, &current_dir, &output_file);
+            let mut cmd = toolchain.invoke(&compiler_path, &invocation);
+            cmd.stdout(Stdio::piped());
+            cmd.stderr(Stdio::piped());
+
+            let compile_start = Instant::now();
+            let output = match cmd.output().await {
+                Ok(output) => output,
+                Err(e) => {
+                    log::error!("Failed to execute compiler {}: {}", version, e);
+                    results.push(MatrixResult {
+                        version: version.clone(),
+                        status: MatrixStatus::Error,
+                        duration: None,
+                        diagnostics: format!("Failed to execute compiler: {}", e),
+                    });
+                    continue;
+                }
+            };
+            let duration = compile_start.elapsed();
+
+            let diagnostics = format!(
+                "{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            );
+
+            let status = if output.status.success() {
+                MatrixStatus::Pass
+            } else {
+                MatrixStatus::Fail
+            };
+
+            log::info!(
+                "Matrix build with {} finished: {} in {}",
+                version,
+                status.as_str(),
+                format_duration(duration)
+            );
+
+            results.push(MatrixResult {
+                version: version.clone(),
+                status,
+                duration: Some(duration),
+                diagnostics,
+            });
+        }
+
+        self.print_matrix_results(&results);
+
+        let failed = results
+            .iter()
+            .filter(|r| r.status != MatrixStatus::Pass)
+            .count();
+
+        if failed > 0 {
+            return Err(OpenCliError::Process(
+                format!(
+                    "Matrix build failed for {} of {} compiler versions",
+                    failed,
+                    results.len()
+                )
+                .into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn print_matrix_results(&self, results: &[MatrixResult]) {
+        let version_width = results
+            .iter()
+            .map(|r| r.version.len())
+            .max()
+            .unwrap_or(0)
+            .max("COMPILER".len());
+
+        println!();
+        println!("Compiler matrix results:");
+        println!(
+            "  {:<width$}  {:<6}  TIME",
+            "COMPILER",
+            "RESULT",
+            width = version_width
+        );
+        for result in results {
+            let time_str = result
+                .duration
+                .map(format_duration)
+                .unwrap_or_else(|| "-".to_string());
+            println!(
+                "  {:<width$}  {:<6}  {}",
+                result.version,
+                result.status.as_str(),
+                time_str,
+                width = version_width
+            );
+        }
+
+        for result in results {
+            let diagnostics = result.diagnostics.trim();
+            if diagnostics.is_empty() {
+                continue;
+            }
+
+            println!();
+            println!("Diagnostics for {}:", result.version);
+            for line in diagnostics.lines() {
+                println!("  {}", line);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MatrixStatus {
+    Pass,
+    Fail,
+    Error,
+}
+
+impl MatrixStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MatrixStatus::Pass => "PASS",
+            MatrixStatus::Fail => "FAIL",
+            MatrixStatus::Error => "ERROR",
+        }
+    }
+}
+
+struct MatrixResult {
+    version: String,
+    status: MatrixStatus,
+    duration: Option<std::time::Duration>,
+    diagnostics: String,
 }
 
 fn format_duration(duration: std::time::Duration) -> String {
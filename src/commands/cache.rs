@@ -0,0 +1,25 @@
+use crate::package::PackageStore;
+use crate::result::Result;
+
+/// Removes store entries that none of the lock files seen by installs reference.
+pub async fn gc(dry_run: bool) -> Result<()> {
+    let store = PackageStore::new()?;
+    let report = store.gc(dry_run).await?;
+
+    let verb = if dry_run { "Would remove" } else { "Removed" };
+    for entry in &report.removed {
+        println!("{} {}", verb, entry);
+    }
+
+    println!(
+        "{} {} store entries ({:.1} MiB); {} still used by {} lock files in {}",
+        verb,
+        report.removed.len(),
+        report.freed_bytes as f64 / (1024.0 * 1024.0),
+        report.kept,
+        report.lock_files,
+        store.dir().display()
+    );
+
+    Ok(())
+}
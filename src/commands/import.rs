@@ -0,0 +1,76 @@
+use crate::build::sampctl::{SampctlProject, SAMPCTL_FILES};
+use crate::result::{OpenCliError, Result};
+use std::path::{Path, PathBuf};
+
+/** Converts a sampctl project into `opencli.toml`
+ *
+ * # Arguments
+ * * `path` - `pawn.json`/`pawn.yaml`, or the directory containing it
+ * * `force` - Overwrite an existing `opencli.toml`
+ */
+pub async fn sampctl(path: Option<&str>, force: bool) -> Result<()> {
+    let path = PathBuf::from(path.unwrap_or("."));
+
+    let source = if path.is_dir() {
+        SampctlProject::find(&path).ok_or_else(|| {
+            OpenCliError::NotFound(
+                format!(
+                    "No {} found in {}",
+                    SAMPCTL_FILES.join(" or "),
+                    path.display()
+                )
+                .into(),
+            )
+        })?
+    } else {
+        path
+    };
+
+    let project_dir = source.parent().unwrap_or(Path::new("."));
+    let config_path = project_dir.join("opencli.toml");
+
+    if config_path.exists() && !force {
+        return Err(OpenCliError::Config(
+            format!(
+                "{} already exists. Use --force to overwrite.",
+                config_path.display()
+            )
+            .into(),
+        ));
+    }
+
+    log::info!("Importing sampctl project from {}", source.display());
+    let project = SampctlProject::load(&source).await?;
+    let import = project.to_opencli();
+
+    import
+        .config
+        .save_to_file(config_path.to_string_lossy().as_ref())
+        .await?;
+
+    println!(
+        "Imported {} into {}",
+        source.display(),
+        config_path.display()
+    );
+    println!("  Entry: {}", import.config.build.entry_file.display());
+    println!("  Output: {}", import.config.build.output_file.display());
+    println!("  Compiler: {}", import.config.build.compiler_version);
+
+    let packages = import.config.get_packages().map_or(0, |p| p.len());
+    println!("  Packages: {}", packages);
+
+    if !import.unmapped.is_empty() {
+        println!();
+        println!("Not imported:");
+        for item in &import.unmapped {
+            log::warn!("Not imported from {}: {}", source.display(), item);
+            println!("  - {}", item);
+        }
+    }
+
+    println!();
+    println!("Then run: opencli package install");
+
+    Ok(())
+}
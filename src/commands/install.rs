@@ -1,6 +1,8 @@
-use crate::compiler::CompilerManager;
+use crate::build::BuildConfig;
+use crate::compiler::load_toolchain;
 use crate::result::{OpenCliError, Result};
 use indicatif::{ProgressBar, ProgressStyle};
+use std::path::Path;
 
 pub async fn execute_compiler(version: Option<&str>, force: bool) -> Result<()> {
     let mut cmd = InstallCommand::new();
@@ -31,10 +33,11 @@ impl InstallCommand {
         install_spinner.set_message("Initializing compiler manager...");
         install_spinner.enable_steady_tick(std::time::Duration::from_millis(120));
 
-        let mut manager = CompilerManager::new().await?;
+        let config = self.load_project_config().await?;
+        let mut toolchain = load_toolchain(&config, false).await?;
         install_spinner.finish_and_clear();
 
-        match manager.get_compiler_path(&version, force).await {
+        match toolchain.install(&version, force).await {
             Ok(path) => {
                 println!("\nCompleted successfully!");
                 println!("Compiler installed at: {}", path.display());
@@ -56,4 +59,14 @@ impl InstallCommand {
 
         Ok(())
     }
+
+    async fn load_project_config(&self) -> Result<BuildConfig> {
+        let config_file = "opencli.toml";
+
+        if !Path::new(config_file).exists() {
+            return Ok(BuildConfig::default());
+        }
+
+        BuildConfig::from_file(config_file).await
+    }
 }
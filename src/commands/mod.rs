@@ -1,9 +1,12 @@
+pub mod auth;
 pub mod build;
+pub mod cache;
+pub mod import;
 pub mod install;
 pub mod run;
 pub mod setup;
 
-use crate::cli::PackageAction;
+use crate::cli::{AuthAction, CacheAction, PackageAction};
 use crate::result::Result;
 use smol_str::SmolStr;
 
@@ -17,6 +20,7 @@ pub enum CommandType {
         verbose: bool,
         force_download: bool,
         update_config: bool,
+        compiler_matrix: Option<Vec<SmolStr>>,
     },
     Setup {
         force: bool,
@@ -25,6 +29,10 @@ pub enum CommandType {
         version: Option<SmolStr>,
         force: bool,
     },
+    ImportSampctl {
+        path: Option<SmolStr>,
+        force: bool,
+    },
 }
 
 impl CommandType {
@@ -36,11 +44,24 @@ impl CommandType {
                 verbose,
                 force_download,
                 update_config,
-            } => build::execute(config.as_deref(), verbose, force_download, update_config).await,
+                compiler_matrix,
+            } => {
+                build::execute(
+                    config.as_deref(),
+                    verbose,
+                    force_download,
+                    update_config,
+                    compiler_matrix.map(|v| v.into_iter().map(|s| s.to_string()).collect()),
+                )
+                .await
+            }
             CommandType::Setup { force } => setup::execute(force).await,
             CommandType::InstallCompiler { version, force } => {
                 install::execute_compiler(version.as_deref(), force).await
             }
+            CommandType::ImportSampctl { path, force } => {
+                import::sampctl(path.as_deref(), force).await
+            }
         }
     }
 }
@@ -67,12 +88,14 @@ impl CommandExecutor {
         verbose: bool,
         force_download: bool,
         update_config: bool,
+        compiler_matrix: Option<Vec<String>>,
     ) -> Result<()> {
         CommandType::Build {
             config: config.map(|s| s.into()),
             verbose,
             force_download,
             update_config,
+            compiler_matrix: compiler_matrix.map(|v| v.into_iter().map(|s| s.into()).collect()),
         }
         .execute()
         .await
@@ -91,26 +114,52 @@ impl CommandExecutor {
         .await
     }
 
+    pub async fn import_sampctl(&mut self, path: Option<String>, force: bool) -> Result<()> {
+        CommandType::ImportSampctl {
+            path: path.map(|s| s.into()),
+            force,
+        }
+        .execute()
+        .await
+    }
+
+    pub async fn handle_auth_action(&mut self, action: AuthAction) -> Result<()> {
+        match action {
+            AuthAction::Login { host, with_token } => {
+                auth::login(host.as_deref(), with_token).await
+            }
+            AuthAction::Logout { host } => auth::logout(host.as_deref()),
+            AuthAction::Status => auth::status().await,
+        }
+    }
+
+    pub async fn handle_cache_action(&mut self, action: CacheAction) -> Result<()> {
+        match action {
+            CacheAction::Gc { dry_run } => cache::gc(dry_run).await,
+        }
+    }
+
     pub async fn handle_package_action(&mut self, action: PackageAction) -> Result<()> {
         use crate::build::PackageTarget;
-        use crate::package::PackageManager;
+        use crate::package::{PackageManager, PackageSource};
 
         let workspace_root = std::env::current_dir()?;
         let config_path = workspace_root.join("opencli.toml");
-        let mut manager = PackageManager::new(&workspace_root, &config_path);
+        let mut manager = PackageManager::new(&workspace_root, &config_path)?;
 
         match action {
-            PackageAction::Install { package, target } => {
-                if let Some(package_spec) = package {
-                    let (repo, version) = if let Some(pos) = package_spec.find('=') {
-                        let repo_part = &package_spec[..pos];
-                        let version_part = &package_spec[pos + 1..];
-                        let clean_version = version_part.trim_matches('"').trim_matches('\'');
-                        (repo_part, Some(clean_version))
-                    } else {
-                        (package_spec.as_str(), None)
-                    };
+            PackageAction::Install {
+                package,
+                target,
+                locked,
+                frozen,
+                jobs,
+            } => {
+                if let Some(jobs) = jobs {
+                    manager.set_jobs(jobs);
+                }
 
+                if let Some(package_spec) = package {
                     let target_type =
                         target
                             .as_deref()
@@ -120,7 +169,28 @@ impl CommandExecutor {
                                 _ => None,
                             });
 
+                    // owner/repo@branch and owner/repo#commit install a ref, not a release
+                    if let Some(pos) = package_spec.find(['@', '#']) {
+                        let source = PackageSource::Rev {
+                            rev: package_spec[pos + 1..].into(),
+                        };
+                        return manager
+                            .install_package_source(&package_spec[..pos], source, target_type)
+                            .await;
+                    }
+
+                    let (repo, version) = if let Some(pos) = package_spec.find('=') {
+                        let repo_part = &package_spec[..pos];
+                        let version_part = &package_spec[pos + 1..];
+                        let clean_version = version_part.trim_matches('"').trim_matches('\'');
+                        (repo_part, Some(clean_version))
+                    } else {
+                        (package_spec.as_str(), None)
+                    };
+
                     manager.install_package(repo, version, target_type).await
+                } else if locked || frozen {
+                    manager.install_locked_packages(frozen).await
                 } else {
                     manager.install_all_packages().await
                 }
@@ -128,7 +198,12 @@ impl CommandExecutor {
             PackageAction::Remove { package } => manager.remove_package(&package).await,
             PackageAction::List => manager.list_packages().await,
             PackageAction::Check => manager.check_packages().await,
-            PackageAction::Update { package, all } => {
+            PackageAction::Outdated { json } => manager.outdated_packages(json).await,
+            PackageAction::Update { package, all, jobs } => {
+                if let Some(jobs) = jobs {
+                    manager.set_jobs(jobs);
+                }
+
                 if all {
                     manager.install_all_packages().await
                 } else if let Some(repo) = package {
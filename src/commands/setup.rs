@@ -1,3 +1,4 @@
+use crate::network::NetworkSettings;
 use crate::result::{OpenCliError, Result};
 use std::path::Path;
 use tokio::fs;
@@ -29,7 +30,7 @@ impl SetupCommand {
             ));
         }
 
-        println!("Downloading opencli.toml from GitHub Gist...");
+        println!("Downloading opencli.toml template...");
         log::info!("Downloading opencli.toml from remote source");
 
         let content = self.download_config().await?;
@@ -52,17 +53,16 @@ impl SetupCommand {
     }
 
     async fn download_config(&self) -> Result<String> {
-        const CONFIG_URL: &str = "https://gist.githubusercontent.com/mxp96/82fd1b1b17ccb23a11fcbe40b83ceaa5/raw/opencli.toml";
+        let settings = NetworkSettings::current();
+        NetworkSettings::ensure_online("Project template opencli.toml")?;
 
-        let client = reqwest::Client::new();
-        let response = client
-            .get(CONFIG_URL)
-            .header("User-Agent", "opencli/0.1.0")
-            .send()
-            .await
-            .map_err(|e| {
-                OpenCliError::Process(format!("Failed to download config: {}", e).into())
-            })?;
+        let url = settings.rewrite_url(settings.project_template_url());
+        log::info!("Fetching project template from {}", url);
+
+        let client = settings.http_client()?;
+        let response = client.get(&url).send().await.map_err(|e| {
+            OpenCliError::Process(format!("Failed to download config: {}", e).into())
+        })?;
 
         if !response.status().is_success() {
             return Err(OpenCliError::Process(
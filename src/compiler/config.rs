@@ -1,3 +1,4 @@
+use crate::compiler::{FlagRule, FlagTable};
 use crate::result::{OpenCliError, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -8,6 +9,7 @@ pub struct CompilerConfig {
     pub darwin: Option<PlatformConfig>,
     pub linux: Option<PlatformConfig>,
     pub windows: Option<PlatformConfig>,
+    pub flags: Option<Vec<FlagRule>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +40,10 @@ impl CompilerConfig {
         Ok(())
     }
 
+    pub fn flag_table(&self) -> FlagTable {
+        FlagTable::new(self.flags.as_deref())
+    }
+
     pub fn get_platform_config(&self) -> Option<&PlatformConfig> {
         if cfg!(target_os = "windows") {
             self.windows.as_ref()
@@ -1,13 +1,11 @@
+use crate::download::HttpDownloader;
+use crate::network::NetworkSettings;
 use crate::result::{OpenCliError, Result};
-use indicatif::{ProgressBar, ProgressStyle};
 use octocrab::Octocrab;
 use once_cell::sync::Lazy;
 use regex::Regex;
-use reqwest::Client;
 use std::collections::HashMap;
 use std::path::Path;
-use tokio::fs::File;
-use tokio::io::AsyncWriteExt;
 
 static REGEX_CACHE: Lazy<HashMap<&'static str, Regex>> = Lazy::new(|| {
     let mut cache = HashMap::new();
@@ -20,35 +18,15 @@ static REGEX_CACHE: Lazy<HashMap<&'static str, Regex>> = Lazy::new(|| {
 
 pub struct CompilerDownloader {
     github: std::sync::Arc<Octocrab>,
-    client: Client,
-}
-
-impl Default for CompilerDownloader {
-    fn default() -> Self {
-        Self::new()
-    }
+    http: HttpDownloader,
 }
 
 impl CompilerDownloader {
-    pub fn new() -> Self {
-        let client = Client::new();
-
-        let github = if let Ok(token) = std::env::var("GITHUB_TOKEN") {
-            if !token.is_empty() {
-                let crab = Octocrab::builder()
-                    .personal_token(token)
-                    .build()
-                    .unwrap_or_else(|_| Octocrab::default());
-                octocrab::initialise(crab);
-                octocrab::instance()
-            } else {
-                octocrab::instance()
-            }
-        } else {
-            octocrab::instance()
-        };
-
-        Self { github, client }
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            github: NetworkSettings::current().github()?,
+            http: HttpDownloader::new()?,
+        })
     }
 
     pub async fn get_release_assets(&self, version: &str) -> Result<Vec<GitHubAsset>> {
@@ -58,6 +36,11 @@ impl CompilerDownloader {
             ("pawn-lang", "compiler")
         };
 
+        NetworkSettings::ensure_online(&format!(
+            "Release metadata for {}/{} {}",
+            owner, repo, version
+        ))?;
+
         let release = self
             .github
             .repos(owner, repo)
@@ -104,65 +87,14 @@ impl CompilerDownloader {
     }
 
     pub async fn download_asset(&self, asset: &GitHubAsset, output_path: &Path) -> Result<()> {
-        if let Some(parent) = output_path.parent() {
-            tokio::fs::create_dir_all(parent).await?;
-        }
-
-        let response = self
-            .client
-            .get(&asset.download_url)
-            .header("User-Agent", "opencli/0.1.0")
-            .send()
-            .await
-            .map_err(|e| {
-                OpenCliError::Process(format!("Failed to download asset: {}", e).into())
-            })?;
-
-        if !response.status().is_success() {
-            return Err(OpenCliError::Process(
-                format!("Download failed with status: {}", response.status()).into(),
-            ));
-        }
-
-        let total_size = response.content_length();
-
-        let pb = if let Some(size) = total_size {
-            let pb = ProgressBar::new(size);
-            pb.set_style(ProgressStyle::default_bar()
-                .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta})")
-                .unwrap()
-                .progress_chars("#>-"));
-            pb.set_message("Downloading compiler");
-            pb
-        } else {
-            let pb = ProgressBar::new_spinner();
-            pb.set_style(
-                ProgressStyle::default_spinner()
-                    .template(
-                        "{spinner:.green} [{elapsed_precise}] Downloading compiler... {bytes}",
-                    )
-                    .unwrap(),
-            );
-            pb.enable_steady_tick(std::time::Duration::from_millis(100));
-            pb
-        };
-
-        let bytes = response
-            .bytes()
-            .await
-            .map_err(|e| OpenCliError::Process(format!("Download failed: {}", e).into()))?;
-
-        if total_size.is_some() {
-            pb.set_position(bytes.len() as u64);
-        } else {
-            pb.inc(bytes.len() as u64);
-        }
-
-        let mut file = File::create(output_path).await?;
-        file.write_all(&bytes).await?;
-        file.flush().await?;
-
-        pb.finish_with_message(format!("Download complete ({} bytes)", bytes.len()));
+        self.http
+            .download(
+                &asset.download_url,
+                output_path,
+                "Downloading compiler",
+                None,
+            )
+            .await?;
         Ok(())
     }
 }
@@ -0,0 +1,160 @@
+use crate::package::version::Version;
+use crate::result::{OpenCliError, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlagRule {
+    pub flag: String,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub suggestion: Option<String>,
+}
+
+// (flag, first compiler version accepting it, suggestion)
+const BUILTIN_FLAGS: &[(&str, Option<&str>, Option<&str>)] = &[
+    ("a", None, None),
+    ("C", None, None),
+    ("c", None, None),
+    ("D", None, None),
+    ("d", None, None),
+    ("e", None, None),
+    ("H", None, None),
+    ("i", None, None),
+    ("l", None, None),
+    ("o", None, None),
+    ("O", None, None),
+    ("p", None, None),
+    ("r", None, None),
+    ("S", None, None),
+    ("s", None, None),
+    ("t", None, None),
+    ("v", None, None),
+    ("w", None, None),
+    ("XD", None, None),
+    ("X", None, None),
+    ("\\", None, None),
+    ("^", None, None),
+    (";", None, None),
+    ("(", None, None),
+    (
+        "Z",
+        Some("3.10.1"),
+        Some("older compilers always behave as if -Z+ was given, so the flag can be dropped"),
+    ),
+    (
+        "E",
+        Some("3.10.3"),
+        Some("use -w to silence individual warnings instead"),
+    ),
+];
+
+/** Table of compiler options and the compiler versions that accept them
+ *
+ * # Sources
+ * - Built-in defaults covering pawncc 3.2 and the pawn-lang 3.10 series
+ * - Optional `[[flags]]` entries in `compilers.toml`, which take precedence
+ *
+ * # Notes
+ * - Arguments that are not flags (e.g. `DEBUG=1`) are ignored
+ * - Unknown flags only produce a warning so new options keep working
+ */
+pub struct FlagTable {
+    rules: Vec<FlagRule>,
+}
+
+impl FlagTable {
+    pub fn new(overrides: Option<&[FlagRule]>) -> Self {
+        let mut rules: Vec<FlagRule> = overrides.map(|o| o.to_vec()).unwrap_or_default();
+
+        for (flag, since, suggestion) in BUILTIN_FLAGS {
+            if !rules.iter().any(|r| r.flag == *flag) {
+                rules.push(FlagRule {
+                    flag: flag.to_string(),
+                    since: since.map(|s| s.to_string()),
+                    until: None,
+                    suggestion: suggestion.map(|s| s.to_string()),
+                });
+            }
+        }
+
+        // Longest flags first so `-XD` is not mistaken for `-X`
+        rules.sort_by_key(|r| std::cmp::Reverse(r.flag.len()));
+
+        Self { rules }
+    }
+
+    pub fn validate(&self, compiler_version: &str, args: &[String]) -> Result<()> {
+        let version = match Version::parse(compiler_version) {
+            Ok(version) => version,
+            Err(_) => {
+                log::warn!(
+                    "Skipping flag validation, cannot parse compiler version: {}",
+                    compiler_version
+                );
+                return Ok(());
+            }
+        };
+
+        for arg in args {
+            let Some(body) = arg.strip_prefix('-') else {
+                continue;
+            };
+
+            let Some(rule) = self
+                .rules
+                .iter()
+                .find(|r| body.starts_with(r.flag.as_str()))
+            else {
+                println!("Warning: unknown compiler flag '{}'", arg);
+                log::warn!("Unknown compiler flag: {}", arg);
+                continue;
+            };
+
+            if let Some(since) = &rule.since {
+                if Version::parse(since).is_ok_and(|min| version < min) {
+                    return Err(self.unsupported(
+                        arg,
+                        compiler_version,
+                        rule,
+                        format!("requires compiler {} or newer", since),
+                    ));
+                }
+            }
+
+            if let Some(until) = &rule.until {
+                if Version::parse(until).is_ok_and(|max| version > max) {
+                    return Err(self.unsupported(
+                        arg,
+                        compiler_version,
+                        rule,
+                        format!("was removed after compiler {}", until),
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn unsupported(
+        &self,
+        arg: &str,
+        compiler_version: &str,
+        rule: &FlagRule,
+        reason: String,
+    ) -> OpenCliError {
+        let suggestion = rule
+            .suggestion
+            .as_deref()
+            .map(|s| format!("\nSuggestion: {}", s))
+            .unwrap_or_default();
+
+        OpenCliError::Config(
+            format!(
+                "Compiler flag '{}' is not supported by compiler_version {}: it {}. Remove it from [build.args] or change compiler_version.{}",
+                arg, compiler_version, reason, suggestion
+            )
+            .into(),
+        )
+    }
+}
@@ -1,5 +1,8 @@
+use crate::archive::{ArchiveExtractor, ArchiveFormat};
+use crate::build::ToolchainLocation;
 use crate::cache::CacheManager;
-use crate::compiler::{CompilerConfig, CompilerDownloader, PlatformConfig};
+use crate::compiler::{CompilerConfig, CompilerDownloader, CompilerSelfTest, PlatformConfig};
+use crate::network::NetworkSettings;
 use crate::result::{OpenCliError, Result};
 use crate::security::SecurityManager;
 use dirs::config_dir;
@@ -17,11 +20,12 @@ pub struct CompilerManager {
     base_dir: PathBuf,
     security: SecurityManager,
     cache: CacheManager,
+    launcher: Vec<String>,
 }
 
 impl CompilerManager {
-    pub async fn new() -> Result<Self> {
-        let base_dir = Self::get_base_directory()?;
+    pub async fn new(location: ToolchainLocation) -> Result<Self> {
+        let base_dir = Self::get_base_directory(location)?;
         let config_path = base_dir.join("compilers.toml");
 
         fs::create_dir_all(&base_dir).await?;
@@ -34,15 +38,16 @@ impl CompilerManager {
 
         Ok(Self {
             config,
-            downloader: CompilerDownloader::new(),
+            downloader: CompilerDownloader::new()?,
             base_dir: base_dir.clone(),
             security: SecurityManager::new(),
             cache: CacheManager::new(&base_dir),
+            launcher: Vec::new(),
         })
     }
 
-    pub async fn new_with_update() -> Result<Self> {
-        let base_dir = Self::get_base_directory()?;
+    pub async fn new_with_update(location: ToolchainLocation) -> Result<Self> {
+        let base_dir = Self::get_base_directory(location)?;
         let config_path = base_dir.join("compilers.toml");
 
         fs::create_dir_all(&base_dir).await?;
@@ -52,32 +57,64 @@ impl CompilerManager {
 
         Ok(Self {
             config,
-            downloader: CompilerDownloader::new(),
+            downloader: CompilerDownloader::new()?,
             base_dir: base_dir.clone(),
             security: SecurityManager::new(),
             cache: CacheManager::new(&base_dir),
+            launcher: Vec::new(),
         })
     }
 
-    fn get_base_directory() -> Result<PathBuf> {
-        let config_dir = config_dir()
-            .ok_or_else(|| OpenCliError::Config("Could not determine config directory".into()))?;
+    fn get_base_directory(location: ToolchainLocation) -> Result<PathBuf> {
+        match location {
+            ToolchainLocation::User => {
+                let config_dir = config_dir().ok_or_else(|| {
+                    OpenCliError::Config("Could not determine config directory".into())
+                })?;
+
+                Ok(config_dir.join("opencli"))
+            }
+            ToolchainLocation::Project => {
+                let project_dir = std::env::current_dir().map_err(|e| {
+                    OpenCliError::Process(format!("Failed to get current directory: {}", e).into())
+                })?;
+
+                Ok(project_dir.join(".opencli").join("toolchain"))
+            }
+        }
+    }
+
+    pub fn with_launcher(mut self, launcher: Vec<String>) -> Self {
+        self.launcher = launcher;
+        self
+    }
+
+    pub fn launcher(&self) -> &[String] {
+        &self.launcher
+    }
+
+    pub fn base_dir(&self) -> &Path {
+        &self.base_dir
+    }
 
-        Ok(config_dir.join("opencli"))
+    pub fn validate_args(&self, version: &str, args: &[String]) -> Result<()> {
+        self.config.flag_table().validate(version, args)
     }
 
     async fn download_compilers_config(config_path: &Path) -> Result<CompilerConfig> {
-        const COMPILERS_CONFIG_URL: &str = "https://gist.githubusercontent.com/mxp96/798edeb8da39c7997948a9432d6f61bb/raw/compilers.toml";
-
-        let client = reqwest::Client::new();
-        let response = client
-            .get(COMPILERS_CONFIG_URL)
-            .header("User-Agent", "opencli/0.1.0")
-            .send()
-            .await
-            .map_err(|e| {
-                OpenCliError::Process(format!("Failed to download compilers config: {}", e).into())
-            })?;
+        NetworkSettings::ensure_online(&format!(
+            "Compiler configuration {}",
+            config_path.display()
+        ))?;
+
+        let settings = NetworkSettings::current();
+        let url = settings.rewrite_url(settings.compilers_config_url());
+        log::info!("Fetching compilers config from {}", url);
+
+        let client = settings.http_client()?;
+        let response = client.get(&url).send().await.map_err(|e| {
+            OpenCliError::Process(format!("Failed to download compilers config: {}", e).into())
+        })?;
 
         if !response.status().is_success() {
             return Err(OpenCliError::Process(
@@ -113,11 +150,15 @@ impl CompilerManager {
             .get_platform_config()
             .ok_or_else(|| OpenCliError::Config("Unsupported platform".into()))?;
 
+        validate_compiler_version(version)?;
+
         let compiler_dir = self.base_dir.join("compilers").join(version);
         let binary_path = compiler_dir.join(&platform_config.binary);
+        // Several versions of the same binary are installed side by side
+        let hash_key = format!("{}/{}", version, platform_config.binary);
 
         if binary_path.exists() && !force_download {
-            if let Ok(Some(cached_hash)) = self.cache.get_hash(&platform_config.binary).await {
+            if let Ok(Some(cached_hash)) = self.cache.get_hash(&hash_key).await {
                 match self.security.verify_file(&binary_path, &cached_hash).await {
                     Ok(true) => {
                         log::info!("Compiler verified successfully with cached hash");
@@ -133,10 +174,35 @@ impl CompilerManager {
             }
         }
 
+        NetworkSettings::ensure_online(&format!(
+            "Compiler {} ({})",
+            version,
+            binary_path.display()
+        ))?;
+
         self.download_and_install_compiler(version, platform_config)
             .await?;
 
         if binary_path.exists() {
+            let selftest_spinner = ProgressBar::new_spinner();
+            selftest_spinner.set_style(
+                ProgressStyle::default_spinner()
+                    .template("{spinner:.blue} {msg}")
+                    .unwrap(),
+            );
+            selftest_spinner.set_message("Running compiler self-test...");
+            selftest_spinner.enable_steady_tick(std::time::Duration::from_millis(80));
+
+            let report = CompilerSelfTest::new(&self.base_dir.join("temp"), &self.launcher)
+                .run(&binary_path)
+                .await;
+            selftest_spinner.finish_and_clear();
+
+            match report?.version {
+                Some(detected) => println!("Compiler self-test passed ({})", detected),
+                None => println!("Compiler self-test passed"),
+            }
+
             let security_spinner = ProgressBar::new_spinner();
             security_spinner.set_style(
                 ProgressStyle::default_spinner()
@@ -147,9 +213,7 @@ impl CompilerManager {
             security_spinner.enable_steady_tick(std::time::Duration::from_millis(80));
 
             let file_hash = self.security.hash_file(&binary_path).await?;
-            self.cache
-                .store_hash(&platform_config.binary, &file_hash)
-                .await?;
+            self.cache.update_hash(&hash_key, &file_hash).await?;
 
             security_spinner.finish_and_clear();
 
@@ -195,6 +259,8 @@ impl CompilerManager {
 
         spinner.finish_and_clear();
 
+        let format = ArchiveFormat::from_method(&platform_config.method)?;
+
         println!("Downloading version {}", version);
 
         let downloaded_file = temp_dir.join(&matching_asset.name);
@@ -214,15 +280,9 @@ impl CompilerManager {
         let extract_dir = self.base_dir.join("compilers").join(version);
         fs::create_dir_all(&extract_dir).await?;
 
-        match platform_config.method.as_str() {
-            "zip" => self.extract_zip(&downloaded_file, &extract_dir).await?,
-            "tgz" => self.extract_tgz(&downloaded_file, &extract_dir).await?,
-            _ => {
-                return Err(OpenCliError::Config(
-                    format!("Unsupported extraction method: {}", platform_config.method).into(),
-                ))
-            }
-        }
+        ArchiveExtractor::new(&extract_dir)
+            .extract(&downloaded_file, format)
+            .await?;
 
         extract_spinner.set_message("Organizing files...");
         self.organize_files(&extract_dir, platform_config).await?;
@@ -235,51 +295,6 @@ impl CompilerManager {
         Ok(())
     }
 
-    async fn extract_zip(&self, archive_path: &Path, extract_to: &Path) -> Result<()> {
-        let file = std::fs::File::open(archive_path)
-            .map_err(|e| OpenCliError::Process(format!("Failed to open zip file: {}", e).into()))?;
-
-        let mut archive = zip::ZipArchive::new(file).map_err(|e| {
-            OpenCliError::Process(format!("Failed to read zip archive: {}", e).into())
-        })?;
-
-        for i in 0..archive.len() {
-            let mut file = archive.by_index(i).map_err(|e| {
-                OpenCliError::Process(format!("Failed to read zip entry: {}", e).into())
-            })?;
-
-            let outpath = extract_to.join(file.name());
-
-            if file.name().ends_with('/') {
-                fs::create_dir_all(&outpath).await?;
-            } else {
-                if let Some(parent) = outpath.parent() {
-                    fs::create_dir_all(parent).await?;
-                }
-
-                let mut buffer = Vec::new();
-                std::io::copy(&mut file, &mut buffer).map_err(|e| {
-                    OpenCliError::Process(format!("Failed to read file: {}", e).into())
-                })?;
-                tokio::fs::write(&outpath, buffer).await?;
-            }
-        }
-
-        Ok(())
-    }
-
-    async fn extract_tgz(&self, archive_path: &Path, extract_to: &Path) -> Result<()> {
-        let file = tokio::fs::File::open(archive_path).await?;
-        let decoder = flate2::read::GzDecoder::new(file.into_std().await);
-        let mut archive = tar::Archive::new(decoder);
-
-        archive.unpack(extract_to).map_err(|e| {
-            OpenCliError::Process(format!("Failed to extract tar.gz: {}", e).into())
-        })?;
-
-        Ok(())
-    }
-
     async fn organize_files(
         &self,
         extract_dir: &Path,
@@ -374,3 +389,31 @@ impl CompilerManager {
         Ok(())
     }
 }
+
+/** Rejects compiler versions that cannot name a directory
+ *
+ * # Notes
+ * - Versions become `compilers/<version>/` and `.opencli/matrix/<version>/`,
+ *   so only ASCII letters, digits, `.`, `-`, `_` and `+` are allowed and a
+ *   leading `.` (as in `..`) is refused
+ */
+pub fn validate_compiler_version(version: &str) -> Result<()> {
+    let valid = !version.is_empty()
+        && version.len() <= 64
+        && !version.starts_with('.')
+        && version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | '+'));
+
+    if !valid {
+        return Err(OpenCliError::Config(
+            format!(
+                "Invalid compiler version '{}': use letters, digits, '.', '-', '_' or '+'",
+                version
+            )
+            .into(),
+        ));
+    }
+
+    Ok(())
+}
@@ -1,7 +1,13 @@
 pub mod config;
 pub mod downloader;
+pub mod flags;
 pub mod manager;
+pub mod selftest;
+pub mod toolchain;
 
 pub use config::*;
 pub use downloader::*;
+pub use flags::*;
 pub use manager::*;
+pub use selftest::*;
+pub use toolchain::*;
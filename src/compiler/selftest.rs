@@ -0,0 +1,159 @@
+use crate::result::{OpenCliError, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::fs;
+use tokio::process::Command;
+
+const SELF_TEST_SCRIPT: &str = "main()\n{\n}\n";
+const SELF_TEST_REPORT: &str = "selftest.toml";
+
+static COMPILER_VERSION_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i-u)pawn compiler\s+(?P<version>[0-9][0-9A-Za-z\.\-]*)").unwrap());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelfTestReport {
+    pub version: Option<String>,
+    pub tested_at: String,
+}
+
+pub struct CompilerSelfTest {
+    work_dir: PathBuf,
+    launcher: Vec<String>,
+}
+
+impl CompilerSelfTest {
+    pub fn new(work_dir: &Path, launcher: &[String]) -> Self {
+        Self {
+            work_dir: work_dir.join("selftest"),
+            launcher: launcher.to_vec(),
+        }
+    }
+
+    /// Compiles an embedded script with the freshly installed compiler and
+    /// records the version it reports next to the binary.
+    pub async fn run(&self, compiler_path: &Path) -> Result<SelfTestReport> {
+        fs::create_dir_all(&self.work_dir).await?;
+
+        let source_path = self.work_dir.join("selftest.pwn");
+        let output_path = self.work_dir.join("selftest.amx");
+        fs::write(&source_path, SELF_TEST_SCRIPT).await?;
+
+        let mut cmd = compiler_command(compiler_path, &self.launcher);
+        cmd.current_dir(&self.work_dir);
+        cmd.arg(format!("-o{}", output_path.display()));
+        cmd.arg(&source_path);
+        cmd.stdin(Stdio::null());
+        cmd.stdout(Stdio::piped());
+        cmd.stderr(Stdio::piped());
+
+        let output = cmd.output().await.map_err(|e| {
+            OpenCliError::Process(
+                format!(
+                    "Compiler self-test could not start {}: {}",
+                    compiler_path.display(),
+                    e
+                )
+                .into(),
+            )
+        })?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let produced_output = output_path.exists();
+
+        let _ = fs::remove_dir_all(&self.work_dir).await;
+
+        if !output.status.success() || !produced_output {
+            log::error!(
+                "Compiler self-test failed (exit code {}): {}{}",
+                output.status.code().unwrap_or(-1),
+                stdout,
+                stderr
+            );
+            return Err(OpenCliError::Process(
+                format!(
+                    "Compiler self-test failed with exit code {}. The archive may be incomplete or a shared library is missing:\n{}{}",
+                    output.status.code().unwrap_or(-1),
+                    stdout.trim_end(),
+                    stderr.trim_end()
+                )
+                .into(),
+            ));
+        }
+
+        let version = detect_version(&stdout).or_else(|| detect_version(&stderr));
+        match &version {
+            Some(v) => log::info!("Compiler self-test passed, detected version {}", v),
+            None => log::warn!("Compiler self-test passed but no version string was found"),
+        }
+
+        let report = SelfTestReport {
+            version,
+            tested_at: chrono::Utc::now().to_rfc3339(),
+        };
+
+        if let Some(compiler_dir) = compiler_path.parent() {
+            report.save_to_dir(compiler_dir).await?;
+        }
+
+        Ok(report)
+    }
+}
+
+impl SelfTestReport {
+    pub async fn load_from_dir(compiler_dir: &Path) -> Result<Option<Self>> {
+        let path = compiler_dir.join(SELF_TEST_REPORT);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(path).await?;
+        Ok(Some(toml::from_str(&content)?))
+    }
+
+    pub async fn save_to_dir(&self, compiler_dir: &Path) -> Result<()> {
+        let content = toml::to_string_pretty(self)?;
+        fs::write(compiler_dir.join(SELF_TEST_REPORT), content).await?;
+        Ok(())
+    }
+}
+
+/// Creates the command that runs the compiler, optionally through a
+/// launcher such as `qemu-i386` for binaries built for another architecture.
+pub fn compiler_command(compiler_path: &Path, launcher: &[String]) -> Command {
+    let mut cmd = match launcher.split_first() {
+        Some((program, launcher_args)) => {
+            let mut cmd = Command::new(program);
+            cmd.args(launcher_args);
+            cmd.arg(compiler_path);
+            cmd
+        }
+        None => Command::new(compiler_path),
+    };
+
+    configure_library_path(&mut cmd, compiler_path);
+    cmd
+}
+
+/// Prepends the compiler directory to `LD_LIBRARY_PATH` so the bundled
+/// libpawnc is picked up instead of whatever the system provides.
+pub fn configure_library_path(cmd: &mut Command, compiler_path: &Path) {
+    if let Some(compiler_dir) = compiler_path.parent() {
+        let mut ld_path = compiler_dir.to_string_lossy().to_string();
+        if let Ok(existing_ld) = std::env::var("LD_LIBRARY_PATH") {
+            ld_path = format!("{}:{}", ld_path, existing_ld);
+        }
+        cmd.env("LD_LIBRARY_PATH", ld_path);
+        log::debug!("Set LD_LIBRARY_PATH to: {}", compiler_dir.display());
+    }
+}
+
+pub fn detect_version(output: &str) -> Option<String> {
+    COMPILER_VERSION_REGEX
+        .captures(output)
+        .and_then(|caps| caps.name("version"))
+        .map(|m| m.as_str().to_string())
+}
@@ -0,0 +1,158 @@
+use crate::build::{BuildConfig, ToolchainKind};
+use crate::compiler::{compiler_command, detect_version, CompilerManager, SelfTestReport};
+use crate::result::{OpenCliError, Result};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::process::Stdio;
+use tokio::process::Command;
+
+pub type ToolchainFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + 'a>>;
+
+/// Everything a toolchain needs to turn a project into a compiled script.
+pub struct Invocation<'a> {
+    pub current_dir: &'a Path,
+    pub entry_file: &'a Path,
+    pub output_file: &'a Path,
+    pub include_paths: Vec<PathBuf>,
+    pub args: &'a [String],
+}
+
+/** Abstraction over a compiler installation used by the build commands
+ *
+ * # Responsibilities
+ * - **install**: Make the requested version available, downloading if needed
+ * - **detect_version**: Report the version string the binary identifies as
+ * - **invoke**: Build the process command for a compilation
+ *
+ * # Notes
+ * - Methods returning futures are boxed so toolchains can be selected at
+ *   runtime from `opencli.toml` and used as `Box<dyn Toolchain>`
+ * - `validate_args` defaults to accepting everything
+ * - `[toolchain] wrapper` runs pawncc through a launcher (e.g. `qemu-i386`)
+ */
+pub trait Toolchain {
+    fn name(&self) -> &str;
+
+    fn install<'a>(&'a mut self, version: &'a str, force: bool) -> ToolchainFuture<'a, PathBuf>;
+
+    fn detect_version<'a>(&'a self, compiler_path: &'a Path)
+        -> ToolchainFuture<'a, Option<String>>;
+
+    fn invoke(&self, compiler_path: &Path, invocation: &Invocation) -> Command;
+
+    fn validate_args(&self, _version: &str, _args: &[String]) -> Result<()> {
+        Ok(())
+    }
+}
+
+pub struct PawnccToolchain {
+    manager: CompilerManager,
+}
+
+impl PawnccToolchain {
+    pub fn new(manager: CompilerManager) -> Self {
+        Self { manager }
+    }
+
+    fn command(&self, compiler_path: &Path) -> Command {
+        compiler_command(compiler_path, self.manager.launcher())
+    }
+
+    fn apply_arguments(cmd: &mut Command, invocation: &Invocation) {
+        cmd.current_dir(invocation.current_dir);
+        cmd.arg(format!("-o{}", invocation.output_file.display()));
+
+        for include_path in &invocation.include_paths {
+            cmd.arg(format!("-i{}", include_path.display()));
+        }
+
+        // Optimisation flags are meaningless with full debug info and
+        // pawncc warns about them, so they are dropped in that case
+        let mut has_debug_flags = false;
+        for arg in invocation.args {
+            if arg == "-d2" || arg == "-d3" {
+                has_debug_flags = true;
+                cmd.arg(arg);
+            } else if arg.starts_with("-O") && has_debug_flags {
+                continue;
+            } else {
+                cmd.arg(arg);
+            }
+        }
+
+        cmd.arg(invocation.entry_file);
+    }
+}
+
+impl Toolchain for PawnccToolchain {
+    fn name(&self) -> &str {
+        "pawncc"
+    }
+
+    fn install<'a>(&'a mut self, version: &'a str, force: bool) -> ToolchainFuture<'a, PathBuf> {
+        Box::pin(self.manager.get_compiler_path(version, force))
+    }
+
+    fn detect_version<'a>(
+        &'a self,
+        compiler_path: &'a Path,
+    ) -> ToolchainFuture<'a, Option<String>> {
+        Box::pin(async move {
+            if let Some(compiler_dir) = compiler_path.parent() {
+                if let Some(report) = SelfTestReport::load_from_dir(compiler_dir).await? {
+                    if report.version.is_some() {
+                        return Ok(report.version);
+                    }
+                }
+            }
+
+            // pawncc prints its banner and usage when started without a source file
+            let output = self
+                .command(compiler_path)
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .output()
+                .await
+                .map_err(|e| {
+                    OpenCliError::Process(format!("Failed to execute compiler: {}", e).into())
+                })?;
+
+            Ok(detect_version(&String::from_utf8_lossy(&output.stdout))
+                .or_else(|| detect_version(&String::from_utf8_lossy(&output.stderr))))
+        })
+    }
+
+    fn invoke(&self, compiler_path: &Path, invocation: &Invocation) -> Command {
+        let mut cmd = self.command(compiler_path);
+        Self::apply_arguments(&mut cmd, invocation);
+        cmd
+    }
+
+    fn validate_args(&self, version: &str, args: &[String]) -> Result<()> {
+        self.manager.validate_args(version, args)
+    }
+}
+
+/// Creates the toolchain selected by the `[toolchain]` section of `opencli.toml`.
+pub async fn load_toolchain(
+    config: &BuildConfig,
+    update_config: bool,
+) -> Result<Box<dyn Toolchain>> {
+    let location = config.toolchain_location();
+    let manager = if update_config {
+        CompilerManager::new_with_update(location).await?
+    } else {
+        CompilerManager::new(location).await?
+    };
+
+    let toolchain_config = config.toolchain.clone().unwrap_or_default();
+
+    match toolchain_config.kind {
+        ToolchainKind::Pawncc => {
+            let manager = manager.with_launcher(toolchain_config.wrapper.unwrap_or_default());
+            Ok(Box::new(PawnccToolchain::new(manager)))
+        }
+    }
+}
@@ -0,0 +1,277 @@
+use crate::result::{OpenCliError, Result};
+use dirs::config_dir;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const TOKEN_ENV: &str = "GITHUB_TOKEN";
+const REDACTED: &str = "[REDACTED]";
+
+/// Tokens handed out during this process, removed verbatim from log lines.
+static KNOWN_SECRETS: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// GitHub token formats, `Authorization` values and passwords in URLs.
+static SECRET_PATTERNS: Lazy<Vec<(Regex, &'static str)>> = Lazy::new(|| {
+    vec![
+        (
+            Regex::new(r"gh[pousr]_[A-Za-z0-9]{20,}|github_pat_[A-Za-z0-9_]{20,}").unwrap(),
+            REDACTED,
+        ),
+        (
+            Regex::new(r"([Aa]uthorization:[ \t]*[A-Za-z]+[ \t]+)[^ \t\r\n]+").unwrap(),
+            "${1}[REDACTED]",
+        ),
+        (
+            Regex::new(r"([Bb]earer[ \t]+)[A-Za-z0-9._~+/=-]{8,}").unwrap(),
+            "${1}[REDACTED]",
+        ),
+        (
+            Regex::new(r"(://[^/ \t\r\n:@]+:)[^@ \t\r\n/]+@").unwrap(),
+            "${1}[REDACTED]@",
+        ),
+    ]
+});
+
+/// Token stored for one host.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostCredentials {
+    pub token: String,
+    /// Account the token belongs to, as reported when logging in
+    pub user: Option<String>,
+}
+
+/// Where the token used for a request came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenSource {
+    Environment,
+    CredentialsFile,
+}
+
+#[derive(Debug, Clone)]
+pub struct ResolvedToken {
+    pub token: String,
+    pub source: TokenSource,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CredentialsFile {
+    #[serde(default)]
+    hosts: BTreeMap<String, HostCredentials>,
+}
+
+/** Per-host GitHub tokens saved by `opencli auth login`
+ *
+ * # File Format
+ * ```toml
+ * [hosts."github.com"]
+ * token = "ghp_..."
+ * user = "octocat"
+ * ```
+ *
+ * # Notes
+ * - Stored as `credentials.toml` in the OpenCLI config directory
+ * - On Unix the file is created with mode `0600`; looser permissions found
+ *   on an existing file are tightened when it is read
+ * - `GITHUB_TOKEN` takes precedence over the stored token for the
+ *   configured API host
+ */
+pub struct CredentialStore {
+    path: PathBuf,
+    file: CredentialsFile,
+}
+
+impl CredentialStore {
+    pub fn default_path() -> Result<PathBuf> {
+        config_dir()
+            .map(|dir| dir.join("opencli").join("credentials.toml"))
+            .ok_or_else(|| OpenCliError::Config("Could not determine config directory".into()))
+    }
+
+    pub fn load() -> Result<Self> {
+        Self::load_from(Self::default_path()?)
+    }
+
+    pub fn load_from(path: PathBuf) -> Result<Self> {
+        let file = match std::fs::read_to_string(&path) {
+            Ok(content) => {
+                restrict_permissions(&path)?;
+                toml::from_str(&content).map_err(|e| {
+                    OpenCliError::Config(
+                        format!("Invalid credentials file {}: {}", path.display(), e).into(),
+                    )
+                })?
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => CredentialsFile::default(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self { path, file })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn get(&self, host: &str) -> Option<&HostCredentials> {
+        self.file.hosts.get(host)
+    }
+
+    pub fn hosts(&self) -> impl Iterator<Item = (&String, &HostCredentials)> {
+        self.file.hosts.iter()
+    }
+
+    pub fn set(&mut self, host: &str, credentials: HostCredentials) {
+        register_secret(&credentials.token);
+        self.file.hosts.insert(host.to_string(), credentials);
+    }
+
+    pub fn remove(&mut self, host: &str) -> Option<HostCredentials> {
+        self.file.hosts.remove(host)
+    }
+
+    /// Writes the file, readable by the current user only.
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let content = toml::to_string_pretty(&self.file)?;
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        let mut file = options.open(&self.path)?;
+        restrict_permissions(&self.path)?;
+        file.write_all(content.as_bytes())?;
+        file.flush()?;
+
+        Ok(())
+    }
+
+    /** Finds the token to use for `host`
+     *
+     * # Order
+     * 1. `GITHUB_TOKEN`, when `use_env` is set (only for the API host)
+     * 2. The token saved with `opencli auth login`
+     */
+    pub fn resolve(host: &str, use_env: bool) -> Option<ResolvedToken> {
+        if use_env {
+            if let Some(token) = std::env::var(TOKEN_ENV).ok().filter(|t| !t.is_empty()) {
+                register_secret(&token);
+                return Some(ResolvedToken {
+                    token,
+                    source: TokenSource::Environment,
+                });
+            }
+        }
+
+        let store = match Self::load() {
+            Ok(store) => store,
+            Err(e) => {
+                log::warn!("Ignoring stored credentials: {}", e);
+                return None;
+            }
+        };
+
+        store.get(host).map(|credentials| {
+            register_secret(&credentials.token);
+            ResolvedToken {
+                token: credentials.token.clone(),
+                source: TokenSource::CredentialsFile,
+            }
+        })
+    }
+}
+
+impl TokenSource {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            TokenSource::Environment => "GITHUB_TOKEN environment variable",
+            TokenSource::CredentialsFile => "credentials file",
+        }
+    }
+}
+
+#[cfg(unix)]
+fn restrict_permissions(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = std::fs::metadata(path)?.permissions().mode();
+    if mode & 0o077 != 0 {
+        log::warn!(
+            "Credentials file {} was accessible by other users (mode {:o}), restricting to 0600",
+            path.display(),
+            mode & 0o777
+        );
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &Path) -> Result<()> {
+    // The file lives in the per-user profile directory, which is private by default
+    Ok(())
+}
+
+/// Remembers a token so it is removed from anything written to the log.
+pub fn register_secret(secret: &str) {
+    if secret.len() >= 4 {
+        if let Ok(mut secrets) = KNOWN_SECRETS.lock() {
+            secrets.insert(secret.to_string());
+        }
+    }
+}
+
+/// Replaces tokens and other credentials in `text` with `[REDACTED]`.
+pub fn redact(text: &str) -> Cow<'_, str> {
+    let mut redacted = Cow::Borrowed(text);
+
+    if let Ok(secrets) = KNOWN_SECRETS.lock() {
+        for secret in secrets.iter() {
+            if redacted.contains(secret.as_str()) {
+                redacted = Cow::Owned(redacted.replace(secret.as_str(), REDACTED));
+            }
+        }
+    }
+
+    for (pattern, replacement) in SECRET_PATTERNS.iter() {
+        if pattern.is_match(&redacted) {
+            redacted = Cow::Owned(pattern.replace_all(&redacted, *replacement).into_owned());
+        }
+    }
+
+    redacted
+}
+
+/// Shortens a token for display, e.g. `ghp_****wxyz`.
+pub fn mask_token(token: &str) -> String {
+    let prefix = token
+        .find('_')
+        .filter(|pos| *pos <= 10)
+        .map_or("", |pos| &token[..=pos]);
+    let suffix: String = token
+        .chars()
+        .rev()
+        .take(4)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+
+    if token.len() <= prefix.len() + 8 {
+        format!("{}****", prefix)
+    } else {
+        format!("{}****{}", prefix, suffix)
+    }
+}
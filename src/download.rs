@@ -0,0 +1,430 @@
+use crate::network::NetworkSettings;
+use crate::result::{OpenCliError, Result};
+use crate::security::sha256_update_from_file;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use reqwest::header::{HeaderMap, CONTENT_RANGE, ETAG, IF_RANGE, LAST_MODIFIED, RANGE};
+use reqwest::{Client, StatusCode};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::fs::{self, OpenOptions};
+use tokio::io::AsyncWriteExt;
+
+const DEFAULT_MAX_RETRIES: u32 = 4;
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/** Streaming HTTP downloader shared by compiler and package downloads
+ *
+ * # Behaviour
+ * - Streams response chunks straight to disk instead of buffering them
+ * - Writes to `<file>.part` and renames once the transfer is complete
+ * - Resumes partial files with an HTTP `Range` request when possible; the
+ *   `ETag` or `Last-Modified` of the first response is kept next to the
+ *   `.part` file and sent as `If-Range`, so a partial file of a different
+ *   version of the resource is downloaded again instead of extended
+ * - Retries transient failures (network errors, 5xx, 429) with
+ *   exponential backoff
+ * - Enforces connect and read timeouts so stalled transfers are retried
+ * - Computes the SHA-256 of the file while it is written, so callers
+ *   never have to read it back just to hash it
+ * - Applies `[network]` mirror rules before contacting the host
+ * - Refuses to start in offline mode
+ * - Draws the progress bars of concurrent downloads together in one
+ *   [`MultiProgress`]
+ *
+ * # Example
+ * ```ignore
+ * let downloader = HttpDownloader::new()?;
+ * downloader
+ *     .download(url, Path::new("sscanf.zip"), "sscanf.zip", None)
+ *     .await?;
+ * ```
+ */
+pub struct HttpDownloader {
+    client: Client,
+    max_retries: u32,
+    initial_backoff: Duration,
+    progress: MultiProgress,
+}
+
+/// A finished download.
+#[derive(Debug, Clone)]
+pub struct DownloadedFile {
+    pub size: u64,
+    /// Lowercase hex SHA-256 of the file contents
+    pub sha256: String,
+}
+
+/// Outcome of a single download attempt.
+enum Attempt {
+    Complete(u64),
+    Retry(String),
+}
+
+/// Running SHA-256 of the bytes already in the `.part` file.
+struct PartHasher {
+    hasher: Sha256,
+    hashed: u64,
+}
+
+impl PartHasher {
+    fn new() -> Self {
+        Self {
+            hasher: Sha256::new(),
+            hashed: 0,
+        }
+    }
+
+    fn update(&mut self, chunk: &[u8]) {
+        self.hasher.update(chunk);
+        self.hashed += chunk.len() as u64;
+    }
+
+    /// Makes the state cover exactly the first `len` bytes of `part_path`,
+    /// re-reading the file only when an earlier attempt left it out of step.
+    async fn sync(&mut self, part_path: &Path, len: u64) -> Result<()> {
+        if self.hashed == len {
+            return Ok(());
+        }
+
+        *self = Self::new();
+        if len > 0 {
+            self.hashed = sha256_update_from_file(&mut self.hasher, part_path).await?;
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> String {
+        format!("{:x}", self.hasher.finalize())
+    }
+}
+
+impl HttpDownloader {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            client: NetworkSettings::current().http_client()?,
+            max_retries: DEFAULT_MAX_RETRIES,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            progress: MultiProgress::new(),
+        })
+    }
+
+    /// Overrides how often and after how long a failed transfer is retried.
+    pub fn with_retry_policy(mut self, max_retries: u32, initial_backoff: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Display the progress bars are drawn in. Other bars added to it are
+    /// kept clear of the download bars.
+    pub fn progress(&self) -> &MultiProgress {
+        &self.progress
+    }
+
+    /** Downloads `url` to `output_path`, resuming and retrying as needed
+     *
+     * # Arguments
+     * * `url` - Source URL
+     * * `output_path` - Final location of the file
+     * * `label` - Name shown next to the progress bar
+     * * `expected_size` - Size reported by the API, used when the server
+     *   does not send `Content-Length`
+     *
+     * # Returns
+     * - Size and SHA-256 of the completed file
+     */
+    pub async fn download(
+        &self,
+        url: &str,
+        output_path: &Path,
+        label: &str,
+        expected_size: Option<u64>,
+    ) -> Result<DownloadedFile> {
+        self.download_with_headers(url, output_path, label, expected_size, &HeaderMap::new())
+            .await
+    }
+
+    /// Like [`Self::download`], sending `headers` (e.g. credentials) with
+    /// every request. reqwest drops `Authorization` on cross-host redirects.
+    pub async fn download_with_headers(
+        &self,
+        url: &str,
+        output_path: &Path,
+        label: &str,
+        expected_size: Option<u64>,
+        headers: &HeaderMap,
+    ) -> Result<DownloadedFile> {
+        if let Some(parent) = output_path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        NetworkSettings::ensure_online(&format!("{} ({})", label, url))?;
+
+        let url = &NetworkSettings::current().rewrite_url(url);
+        let part_path = Self::part_path(output_path);
+        let mut backoff = self.initial_backoff;
+        let mut hasher = PartHasher::new();
+
+        for attempt in 0..=self.max_retries {
+            match self
+                .try_download(url, headers, &part_path, label, expected_size, &mut hasher)
+                .await?
+            {
+                Attempt::Complete(size) => {
+                    hasher.sync(&part_path, size).await?;
+                    fs::rename(&part_path, output_path).await?;
+                    let _ = fs::remove_file(Self::validator_path(&part_path)).await;
+                    let sha256 = hasher.finish();
+                    log::info!("Downloaded {} ({} bytes, sha256 {})", url, size, sha256);
+                    return Ok(DownloadedFile { size, sha256 });
+                }
+                Attempt::Retry(reason) if attempt < self.max_retries => {
+                    log::warn!(
+                        "Download of {} interrupted ({}), retrying in {:?} ({}/{})",
+                        url,
+                        reason,
+                        backoff,
+                        attempt + 1,
+                        self.max_retries
+                    );
+                    self.progress.suspend(|| {
+                        println!(
+                            "Download interrupted ({}), retrying in {}s...",
+                            reason,
+                            backoff.as_secs()
+                        )
+                    });
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                Attempt::Retry(reason) => {
+                    return Err(OpenCliError::Process(
+                        format!(
+                            "{}: {} after {} retries ({})",
+                            OpenCliError::DOWNLOAD_FAILED,
+                            url,
+                            self.max_retries,
+                            reason
+                        )
+                        .into(),
+                    ));
+                }
+            }
+        }
+
+        unreachable!("download loop always returns")
+    }
+
+    async fn try_download(
+        &self,
+        url: &str,
+        headers: &HeaderMap,
+        part_path: &Path,
+        label: &str,
+        expected_size: Option<u64>,
+        hasher: &mut PartHasher,
+    ) -> Result<Attempt> {
+        let validator_path = Self::validator_path(part_path);
+        let mut existing = match fs::metadata(part_path).await {
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        };
+
+        let validator = match existing {
+            0 => None,
+            _ => fs::read_to_string(&validator_path)
+                .await
+                .ok()
+                .map(|validator| validator.trim().to_string())
+                .filter(|validator| !validator.is_empty()),
+        };
+        if existing > 0 && validator.is_none() {
+            // Nothing tells which version of the resource the bytes belong to
+            log::info!(
+                "Discarding {}, it has no validator to resume it with",
+                part_path.display()
+            );
+            Self::discard_part(part_path).await?;
+            existing = 0;
+        }
+
+        let mut request = self.client.get(url).headers(headers.clone());
+        if let Some(validator) = &validator {
+            request = request
+                .header(RANGE, format!("bytes={}-", existing))
+                .header(IF_RANGE, validator.as_str());
+        }
+
+        let mut response = match request.send().await {
+            Ok(response) => response,
+            Err(e) => return Ok(Attempt::Retry(e.to_string())),
+        };
+
+        let status = response.status();
+
+        if status == StatusCode::RANGE_NOT_SATISFIABLE {
+            // The partial file already holds everything the server has
+            let total = Self::range_total(&response);
+            if total == Some(existing) || expected_size == Some(existing) {
+                return Ok(Attempt::Complete(existing));
+            }
+            Self::discard_part(part_path).await?;
+            return Ok(Attempt::Retry("stale partial download".to_string()));
+        }
+
+        if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+            return Ok(Attempt::Retry(format!("HTTP {}", status)));
+        }
+
+        if !status.is_success() {
+            return Err(OpenCliError::Process(
+                format!("Download failed with status: {}", status).into(),
+            ));
+        }
+
+        let resumed = status == StatusCode::PARTIAL_CONTENT;
+        let response_validator = Self::validator(response.headers());
+        if resumed && response_validator != validator {
+            // A server ignoring If-Range sent bytes of another version
+            Self::discard_part(part_path).await?;
+            return Ok(Attempt::Retry(
+                "partial download belongs to another version".to_string(),
+            ));
+        }
+        if !resumed {
+            match &response_validator {
+                Some(value) => fs::write(&validator_path, value).await?,
+                None => {
+                    let _ = fs::remove_file(&validator_path).await;
+                }
+            }
+        }
+
+        let offset = if resumed { existing } else { 0 };
+        hasher.sync(part_path, offset).await?;
+
+        let mut file = if resumed {
+            log::info!("Resuming {} at byte {}", url, existing);
+            OpenOptions::new().append(true).open(part_path).await?
+        } else {
+            fs::File::create(part_path).await?
+        };
+
+        let total = response
+            .content_length()
+            .map(|len| len + offset)
+            .or(expected_size);
+        let pb = self.progress_bar(label, total);
+        pb.set_position(offset);
+
+        let mut written = offset;
+        loop {
+            match response.chunk().await {
+                Ok(Some(chunk)) => {
+                    file.write_all(&chunk).await?;
+                    hasher.update(&chunk);
+                    written += chunk.len() as u64;
+                    pb.set_position(written);
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    file.flush().await?;
+                    pb.abandon();
+                    return Ok(Attempt::Retry(e.to_string()));
+                }
+            }
+        }
+
+        file.flush().await?;
+
+        if let Some(total) = total {
+            if written < total {
+                pb.abandon();
+                return Ok(Attempt::Retry(format!(
+                    "connection closed after {} of {} bytes",
+                    written, total
+                )));
+            }
+        }
+
+        pb.finish_with_message(format!("{} ({} bytes)", label, written));
+        Ok(Attempt::Complete(written))
+    }
+
+    fn part_path(output_path: &Path) -> PathBuf {
+        let mut file_name = output_path
+            .file_name()
+            .map(|n| n.to_os_string())
+            .unwrap_or_default();
+        file_name.push(".part");
+        output_path.with_file_name(file_name)
+    }
+
+    fn validator_path(part_path: &Path) -> PathBuf {
+        let mut file_name = part_path
+            .file_name()
+            .map(|n| n.to_os_string())
+            .unwrap_or_default();
+        file_name.push(".validator");
+        part_path.with_file_name(file_name)
+    }
+
+    /// Strong `ETag`, else `Last-Modified`: the values `If-Range` accepts.
+    fn validator(headers: &HeaderMap) -> Option<String> {
+        let header = |name| headers.get(name).and_then(|v| v.to_str().ok());
+
+        header(ETAG)
+            .filter(|etag| !etag.starts_with("W/"))
+            .or_else(|| header(LAST_MODIFIED))
+            .map(str::to_string)
+    }
+
+    async fn discard_part(part_path: &Path) -> Result<()> {
+        if fs::metadata(part_path).await.is_ok() {
+            fs::remove_file(part_path).await?;
+        }
+        let _ = fs::remove_file(Self::validator_path(part_path)).await;
+        Ok(())
+    }
+
+    fn range_total(response: &reqwest::Response) -> Option<u64> {
+        response
+            .headers()
+            .get(CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.rsplit('/').next())
+            .and_then(|v| v.parse().ok())
+    }
+
+    fn progress_bar(&self, label: &str, total: Option<u64>) -> ProgressBar {
+        match total {
+            Some(size) => {
+                let pb = self.progress.add(ProgressBar::new(size));
+                pb.set_style(ProgressStyle::default_bar()
+                    .template("{spinner:.green} {msg} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")
+                    .unwrap()
+                    .progress_chars("#>-"));
+                pb.set_message(label.to_string());
+                pb
+            }
+            None => {
+                let pb = self.progress.add(ProgressBar::new_spinner());
+                pb.set_style(
+                    ProgressStyle::default_spinner()
+                        .template(
+                            "{spinner:.green} {msg} [{elapsed_precise}] {bytes} ({bytes_per_sec})",
+                        )
+                        .unwrap(),
+                );
+                pb.set_message(label.to_string());
+                pb.enable_steady_tick(Duration::from_millis(100));
+                pb
+            }
+        }
+    }
+}
@@ -7,20 +7,28 @@
 /// - Dependency tracking
 ///
 /// Main modules:
+/// - archive: Hardened streaming extraction of zip and tar archives
 /// - build: Core build pipeline and dependency resolution
 /// - cache: File-based caching system with integrity validation
 /// - cli: Command-line interface parsing and execution
 /// - commands: Implementation of build commands and subcommands
 /// - compiler: Compiler abstraction and toolchain management
+/// - credentials: Stored GitHub tokens and log redaction
+/// - download: Streaming, resumable HTTP downloads with retries
+/// - network: Mirror, base-URL and client settings for network access
 /// - package: Package configuration and manifest handling
 /// - result: Error handling and result types
 /// - security: Cryptographic utilities and hash management
 /// - utils: Common utilities and helper functions
+pub mod archive;
 pub mod build;
 pub mod cache;
 pub mod cli;
 pub mod commands;
 pub mod compiler;
+pub mod credentials;
+pub mod download;
+pub mod network;
 pub mod package;
 pub mod result;
 pub mod security;
@@ -3,8 +3,10 @@ use dirs::config_dir;
 use env_logger::Builder;
 use log::LevelFilter;
 use opencli::cli::Cli;
+use opencli::credentials::redact;
 use opencli::result::Result;
 use std::fs::OpenOptions;
+use std::io::Write;
 
 /** Main entry point for the OpenCLI application
  *
@@ -43,8 +45,15 @@ async fn main() -> Result<()> {
         }
     };
 
-    // Execute the parsed command
-    cli.execute().await
+    // Execute the parsed command, printing errors as text since some
+    // explanations (e.g. dependency conflicts) span several lines
+    if let Err(e) = cli.execute().await {
+        log::error!("{}", e);
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+
+    Ok(())
 }
 
 /** Initializes the logging system with file-based output
@@ -54,6 +63,7 @@ async fn main() -> Result<()> {
  * - Log level: Info and above
  * - Output: Append mode to preserve historical logs
  * - Fallback: Current directory if config directory unavailable
+ * - Tokens and other credentials are replaced with `[REDACTED]`
  *
  * # Directory Structure
  * - Linux: `~/.config/opencli/opencli.log`
@@ -85,6 +95,17 @@ async fn init_logging() {
                     std::fs::File::create(&log_file).expect("Failed to create log file")
                 }),
         )))
+        .format(|buf, record| {
+            // Same layout as env_logger's default, which needs its `humantime` feature
+            writeln!(
+                buf,
+                "[{} {:<5} {}] {}",
+                chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ"),
+                record.level(),
+                record.module_path().unwrap_or_default(),
+                redact(&record.args().to_string())
+            )
+        })
         .filter_level(LevelFilter::Info) // Log info level and above
         .init();
 
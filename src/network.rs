@@ -0,0 +1,465 @@
+use crate::credentials::{CredentialStore, ResolvedToken};
+use crate::result::{OpenCliError, Result};
+use dirs::config_dir;
+use http_body_util::BodyExt;
+use octocrab::service::middleware::auth_header::AuthHeaderLayer;
+use octocrab::service::middleware::base_uri::BaseUriLayer;
+use octocrab::service::middleware::extra_headers::ExtraHeadersLayer;
+use octocrab::{AuthState, Octocrab, OctocrabBuilder};
+use once_cell::sync::{Lazy, OnceCell};
+use reqwest::{Certificate, Client, NoProxy, Proxy};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+const USER_AGENT: &str = "opencli/0.1.0";
+const GITHUB_API_URL: &str = "https://api.github.com";
+const GITHUB_HOST: &str = "github.com";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+const DEFAULT_RELEASE_CACHE_TTL: u64 = 600;
+const DEFAULT_COMPILERS_CONFIG_URL: &str =
+    "https://gist.githubusercontent.com/mxp96/798edeb8da39c7997948a9432d6f61bb/raw/compilers.toml";
+const DEFAULT_PROJECT_TEMPLATE_URL: &str =
+    "https://gist.githubusercontent.com/mxp96/82fd1b1b17ccb23a11fcbe40b83ceaa5/raw/opencli.toml";
+
+const OFFLINE_ENV: &str = "OPENCLI_OFFLINE";
+const PROJECT_CONFIG: &str = "opencli.toml";
+
+static SETTINGS: Lazy<NetworkSettings> = Lazy::new(NetworkSettings::load);
+
+static PROJECT_CONFIG_PATH: OnceCell<PathBuf> = OnceCell::new();
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/** `[network]` section shared by the global and the project configuration
+ *
+ * # Example
+ * ```toml
+ * [network]
+ * github_api_url = "https://artifacts.example.com/api/github"
+ * compilers_config_url = "https://artifacts.example.com/opencli/compilers.toml"
+ * project_template_url = "https://artifacts.example.com/opencli/opencli.toml"
+ * proxy = "socks5h://127.0.0.1:1080"
+ * no_proxy = "localhost,.corp.example.com"
+ * ca_bundle = "/etc/ssl/certs/corp-root.pem"
+ * release_cache_ttl = 600
+ *
+ * [[network.mirrors]]
+ * from = "https://github.com/"
+ * to = "https://artifacts.example.com/github/"
+ * ```
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NetworkConfig {
+    pub github_api_url: Option<String>,
+    pub compilers_config_url: Option<String>,
+    pub project_template_url: Option<String>,
+    pub proxy: Option<String>,
+    pub no_proxy: Option<String>,
+    pub ca_bundle: Option<PathBuf>,
+    pub release_cache_ttl: Option<u64>,
+    pub mirrors: Option<Vec<MirrorRule>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MirrorRule {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    network: Option<NetworkConfig>,
+}
+
+/** Effective network settings for the current process
+ *
+ * # Sources (highest priority first)
+ * 1. `[network]` in the project configuration: the file passed to
+ *    `build --config`, otherwise `opencli.toml` of the current directory
+ * 2. `[network]` in the global `config.toml` next to the opencli log file
+ * 3. Built-in defaults (api.github.com and the upstream gists)
+ *
+ * # Notes
+ * - Loaded once on first use
+ * - Mirror rules from the project are tried before global ones
+ * - Without a `proxy` key the usual `HTTPS_PROXY`, `HTTP_PROXY`,
+ *   `ALL_PROXY` and `NO_PROXY` environment variables apply
+ * - A relative `ca_bundle` is resolved against the file that sets it
+ */
+#[derive(Debug, Clone, Default)]
+pub struct NetworkSettings {
+    config: NetworkConfig,
+}
+
+impl NetworkSettings {
+    pub fn current() -> &'static NetworkSettings {
+        &SETTINGS
+    }
+
+    /// Settings from an already merged `[network]` section.
+    pub fn from_config(config: NetworkConfig) -> Self {
+        Self { config }
+    }
+
+    /// Reads the project `[network]` section from `path` instead of
+    /// `./opencli.toml`; has no effect once the settings were loaded.
+    pub fn set_project_config(path: impl Into<PathBuf>) {
+        let path = path.into();
+        if PROJECT_CONFIG_PATH.set(path.clone()).is_err() || Lazy::get(&SETTINGS).is_some() {
+            log::warn!(
+                "Network settings already loaded, ignoring [network] in {}",
+                path.display()
+            );
+        }
+    }
+
+    /// Enables offline mode for the rest of the process (`--offline`).
+    pub fn set_offline(offline: bool) {
+        OFFLINE.store(offline, Ordering::Relaxed);
+    }
+
+    /// True when `--offline` was passed or `OPENCLI_OFFLINE` is set to a
+    /// value other than `0`, `false`, `no` or an empty string.
+    pub fn is_offline() -> bool {
+        OFFLINE.load(Ordering::Relaxed)
+            || std::env::var(OFFLINE_ENV)
+                .map(|value| {
+                    !matches!(
+                        value.trim().to_ascii_lowercase().as_str(),
+                        "" | "0" | "false" | "no"
+                    )
+                })
+                .unwrap_or(false)
+    }
+
+    /** Fails when offline mode forbids fetching `artifact`
+     *
+     * # Arguments
+     * * `artifact` - Human readable name of what would have been downloaded,
+     *   included in the error so users know what to cache beforehand
+     */
+    pub fn ensure_online(artifact: &str) -> Result<()> {
+        if Self::is_offline() {
+            log::error!("Offline mode: refusing to download {}", artifact);
+            return Err(OpenCliError::NotFound(
+                format!(
+                    "{} is not available locally and offline mode forbids downloading it (unset --offline/{} to fetch it)",
+                    artifact, OFFLINE_ENV
+                )
+                .into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn global_config_path() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join("opencli").join("config.toml"))
+    }
+
+    fn load() -> Self {
+        let project = PROJECT_CONFIG_PATH
+            .get()
+            .cloned()
+            .unwrap_or_else(|| PathBuf::from(PROJECT_CONFIG));
+        Self::from_files(Self::global_config_path().as_deref(), &project)
+    }
+
+    /// Settings from the `[network]` sections of a global and a project
+    /// configuration file; missing files or sections are skipped.
+    pub fn from_files(global: Option<&Path>, project: &Path) -> Self {
+        let global = global
+            .and_then(Self::read_network_section)
+            .unwrap_or_default();
+        let project = Self::read_network_section(project).unwrap_or_default();
+
+        Self::from_config(Self::merge(project, global))
+    }
+
+    fn read_network_section(path: &Path) -> Option<NetworkConfig> {
+        let content = std::fs::read_to_string(path).ok()?;
+        match toml::from_str::<ConfigFile>(&content) {
+            Ok(file) => file.network.map(|mut network| {
+                if let (Some(ca_bundle), Some(dir)) = (&network.ca_bundle, path.parent()) {
+                    network.ca_bundle = Some(dir.join(ca_bundle));
+                }
+                network
+            }),
+            Err(e) => {
+                log::warn!("Ignoring [network] settings in {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Project values win over global ones; mirror rules of both are kept,
+    /// project rules first.
+    pub fn merge(project: NetworkConfig, global: NetworkConfig) -> NetworkConfig {
+        let mut mirrors = project.mirrors.unwrap_or_default();
+        mirrors.extend(global.mirrors.unwrap_or_default());
+
+        NetworkConfig {
+            github_api_url: project.github_api_url.or(global.github_api_url),
+            compilers_config_url: project.compilers_config_url.or(global.compilers_config_url),
+            project_template_url: project.project_template_url.or(global.project_template_url),
+            proxy: project.proxy.or(global.proxy),
+            no_proxy: project.no_proxy.or(global.no_proxy),
+            ca_bundle: project.ca_bundle.or(global.ca_bundle),
+            release_cache_ttl: project.release_cache_ttl.or(global.release_cache_ttl),
+            mirrors: (!mirrors.is_empty()).then_some(mirrors),
+        }
+    }
+
+    pub fn compilers_config_url(&self) -> &str {
+        self.config
+            .compilers_config_url
+            .as_deref()
+            .unwrap_or(DEFAULT_COMPILERS_CONFIG_URL)
+    }
+
+    pub fn project_template_url(&self) -> &str {
+        self.config
+            .project_template_url
+            .as_deref()
+            .unwrap_or(DEFAULT_PROJECT_TEMPLATE_URL)
+    }
+
+    /// How long cached release listings are used without revalidation.
+    pub fn release_cache_ttl(&self) -> Duration {
+        Duration::from_secs(
+            self.config
+                .release_cache_ttl
+                .unwrap_or(DEFAULT_RELEASE_CACHE_TTL),
+        )
+    }
+
+    /// Applies the first matching mirror rule to a download URL.
+    pub fn rewrite_url(&self, url: &str) -> String {
+        for rule in self.config.mirrors.iter().flatten() {
+            if let Some(rest) = url.strip_prefix(rule.from.as_str()) {
+                let rewritten = format!("{}{}", rule.to, rest);
+                log::debug!("Rewrote {} to {}", url, rewritten);
+                return rewritten;
+            }
+        }
+
+        url.to_string()
+    }
+
+    /** Builds the HTTP client used for every opencli request
+     *
+     * # Configuration
+     * - User agent, connect and read timeouts
+     * - `proxy`/`no_proxy` from `[network]`, falling back to the proxy
+     *   environment variables (`http://`, `https://` and `socks5://` URLs)
+     * - Certificates from `ca_bundle` trusted in addition to the built-in roots
+     */
+    pub fn http_client(&self) -> Result<Client> {
+        let mut builder = Client::builder()
+            .user_agent(USER_AGENT)
+            .connect_timeout(CONNECT_TIMEOUT)
+            .read_timeout(READ_TIMEOUT);
+
+        if let Some(proxy_url) = &self.config.proxy {
+            let no_proxy = match &self.config.no_proxy {
+                Some(list) => NoProxy::from_string(list),
+                None => NoProxy::from_env(),
+            };
+            let proxy = Proxy::all(proxy_url.as_str()).map_err(|e| {
+                OpenCliError::Config(format!("Invalid proxy '{}': {}", proxy_url, e).into())
+            })?;
+            builder = builder.proxy(proxy.no_proxy(no_proxy));
+            log::info!("Using proxy {}", proxy_url);
+        }
+
+        if let Some(ca_bundle) = &self.config.ca_bundle {
+            for certificate in Self::load_ca_bundle(ca_bundle)? {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        builder.build().map_err(|e| {
+            OpenCliError::Config(format!("Failed to create HTTP client: {}", e).into())
+        })
+    }
+
+    fn load_ca_bundle(path: &Path) -> Result<Vec<Certificate>> {
+        let pem = std::fs::read(path).map_err(|e| {
+            OpenCliError::Config(
+                format!("Failed to read ca_bundle {}: {}", path.display(), e).into(),
+            )
+        })?;
+
+        let certificates = Certificate::from_pem_bundle(&pem).map_err(|e| {
+            OpenCliError::Config(format!("Invalid ca_bundle {}: {}", path.display(), e).into())
+        })?;
+
+        if certificates.is_empty() {
+            return Err(OpenCliError::Config(
+                format!("ca_bundle {} contains no certificates", path.display()).into(),
+            ));
+        }
+
+        log::info!(
+            "Trusting {} certificate(s) from {}",
+            certificates.len(),
+            path.display()
+        );
+        Ok(certificates)
+    }
+
+    pub fn github_api_url(&self) -> &str {
+        self.config
+            .github_api_url
+            .as_deref()
+            .unwrap_or(GITHUB_API_URL)
+    }
+
+    /// Host that credentials for the configured API are stored under
+    /// (`github.com` for the public API, the server name for Enterprise).
+    pub fn github_host(&self) -> String {
+        let api_url = self.github_api_url();
+        if api_url.trim_end_matches('/') == GITHUB_API_URL {
+            return GITHUB_HOST.to_string();
+        }
+
+        api_url
+            .parse::<http::Uri>()
+            .ok()
+            .and_then(|uri| uri.authority().map(|authority| authority.to_string()))
+            .unwrap_or_else(|| api_url.to_string())
+    }
+
+    /// API base URL for `host`: the configured one for its own host,
+    /// otherwise the GitHub Enterprise convention `https://<host>/api/v3`.
+    pub fn api_url_for_host(&self, host: &str) -> String {
+        if host == self.github_host() {
+            self.github_api_url().trim_end_matches('/').to_string()
+        } else if host == GITHUB_HOST {
+            GITHUB_API_URL.to_string()
+        } else {
+            format!("https://{}/api/v3", host)
+        }
+    }
+
+    /// Token for the configured API host, from `GITHUB_TOKEN` or the
+    /// credentials saved by `opencli auth login`.
+    pub fn github_token(&self) -> Option<ResolvedToken> {
+        CredentialStore::resolve(&self.github_host(), true)
+    }
+
+    /** Token to send with a request to `url`
+     *
+     * # Notes
+     * - Only URLs that, after mirror rules, have the scheme, host and port
+     *   of the configured API get one, so a URL taken from a lock file or a
+     *   release listing cannot carry the token to another server
+     */
+    pub fn github_token_for(&self, url: &str) -> Option<ResolvedToken> {
+        let rewritten = self.rewrite_url(url);
+        let api_origin = Self::origin(self.github_api_url());
+        if api_origin.is_none() || Self::origin(&rewritten) != api_origin {
+            log::warn!(
+                "Not sending the GitHub token to {}, it is not the configured API host",
+                rewritten
+            );
+            return None;
+        }
+
+        self.github_token()
+    }
+
+    fn origin(url: &str) -> Option<(String, String, Option<u16>)> {
+        let uri = url.parse::<http::Uri>().ok()?;
+        Some((
+            uri.scheme_str()?.to_ascii_lowercase(),
+            uri.host()?.to_ascii_lowercase(),
+            uri.port_u16(),
+        ))
+    }
+
+    /** Builds the GitHub API client
+     *
+     * # Notes
+     * - Requests go through [`Self::http_client`] so API calls honour the
+     *   same proxy and CA settings as downloads
+     * - The token from [`Self::github_token`] is sent only to the configured
+     *   API host
+     */
+    pub fn github(&self) -> Result<Arc<Octocrab>> {
+        let api_url = self.github_api_url();
+        let base_uri: http::Uri = api_url.parse().map_err(|e| {
+            OpenCliError::Config(format!("Invalid github_api_url '{}': {}", api_url, e).into())
+        })?;
+
+        let auth_header = self.github_token().and_then(|resolved| {
+            log::info!(
+                "Authenticating to {} with token from {}",
+                self.github_host(),
+                resolved.source.describe()
+            );
+            format!("Bearer {}", resolved.token).parse().ok()
+        });
+
+        let service = ReqwestService {
+            client: self.http_client()?,
+        };
+
+        let crab = OctocrabBuilder::new_empty()
+            .with_service(service)
+            .with_layer(&ExtraHeadersLayer::new(Arc::new(Vec::new())))
+            .with_layer(&BaseUriLayer::new(base_uri.clone()))
+            .with_layer(&AuthHeaderLayer::new(
+                auth_header,
+                base_uri.clone(),
+                base_uri,
+            ))
+            .with_auth(AuthState::None)
+            .build()
+            .unwrap_or_else(|never| match never {});
+
+        Ok(Arc::new(crab))
+    }
+}
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Adapts the reqwest client to the tower service octocrab expects.
+#[derive(Clone)]
+struct ReqwestService {
+    client: Client,
+}
+
+impl<B> tower::Service<http::Request<B>> for ReqwestService
+where
+    B: http_body::Body<Data = bytes::Bytes> + Send + 'static,
+    B::Error: Into<BoxError>,
+{
+    type Response = http::Response<reqwest::Body>;
+    type Error = BoxError;
+    type Future =
+        Pin<Box<dyn Future<Output = std::result::Result<Self::Response, BoxError>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<std::result::Result<(), BoxError>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let client = self.client.clone();
+
+        Box::pin(async move {
+            let (parts, body) = request.into_parts();
+            let body = body.collect().await.map_err(Into::into)?.to_bytes();
+            let request = reqwest::Request::try_from(http::Request::from_parts(parts, body))?;
+            let response = client.execute(request).await?;
+            Ok(http::Response::from(response))
+        })
+    }
+}
@@ -1,20 +1,41 @@
+use crate::archive::{ArchiveExtractor, ArchiveFormat};
+use crate::download::{DownloadedFile, HttpDownloader};
+use crate::network::NetworkSettings;
+use crate::package::lock::LockedArtifact;
+use crate::package::manifest::{PackageManifest, MANIFEST_FILES};
+use crate::package::release_cache::{CachedResponse, ReleaseCache};
+use crate::package::source::{self, PackageSource};
+use crate::package::store::PackageStore;
 use crate::package::version::{Version, VersionConstraint};
 use crate::result::{OpenCliError, Result};
-use flate2::read::GzDecoder;
-use indicatif::{ProgressBar, ProgressStyle};
+use futures_util::stream::{self, StreamExt};
+use http::header::{HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION, ETAG, IF_NONE_MATCH};
+use http::StatusCode;
+use indicatif::MultiProgress;
+use octocrab::models::repos::Release;
 use octocrab::Octocrab;
 use once_cell::sync::Lazy;
 use regex::Regex;
-use reqwest::Client;
-use std::io::Read;
+use smol_str::SmolStr;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use tar::Archive;
-use tokio::fs::{create_dir_all, File};
-use tokio::io::AsyncWriteExt;
-use zip::ZipArchive;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::fs::{self, create_dir_all};
+use tokio::sync::{Mutex as AsyncMutex, Semaphore};
+
+const RELEASES_PER_PAGE: u32 = 100;
+
+/// Downloads and extractions that run at once unless `--jobs` says otherwise
+pub const DEFAULT_JOBS: usize = 4;
+
+/// Distinguishes the staging folders of extractions running at once
+static STAGING_COUNTER: AtomicU64 = AtomicU64::new(0);
 
 static GITHUB_REPO_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^([^/]+)/([^/]+)$").unwrap());
 
+static COMMIT_SHA_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[0-9a-fA-F]{40}$").unwrap());
+
 static INCLUDE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\.inc$").unwrap());
 
 static BINARY_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\.(dll|so|dylib)$").unwrap());
@@ -23,8 +44,22 @@ static AMX_LIB_REGEX: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"[Aa][Mm][Xx]|[Ll][Ii][Bb]|[Ll][Oo][Gg]-[Cc][Oo][Rr][Ee]").unwrap());
 
 pub struct PackageDownloader {
-    github: std::sync::Arc<Octocrab>,
-    client: Client,
+    github: Arc<Octocrab>,
+    http: HttpDownloader,
+    release_cache: ReleaseCache,
+    store: PackageStore,
+    listings: Mutex<HashMap<String, Arc<AsyncMutex<ReleaseListing>>>>,
+    jobs: usize,
+    /// One permit per running asset download and extraction
+    slots: Semaphore,
+}
+
+/// Release pages of one repository fetched so far.
+#[derive(Default)]
+struct ReleaseListing {
+    releases: Vec<GitHubRelease>,
+    pages_fetched: u32,
+    complete: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -37,113 +72,523 @@ pub struct GitHubRelease {
 pub struct GitHubAsset {
     pub name: String,
     pub download_url: String,
+    /// API endpoint of the asset, which also serves private repositories
+    pub api_url: String,
     pub size: u64,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct PackageFiles {
     pub includes: Vec<PathBuf>,
     pub binaries: Vec<PathBuf>,
     pub root_binaries: Vec<PathBuf>,
     pub component_binaries: Vec<PathBuf>,
     pub plugin_binaries: Vec<PathBuf>,
+    /// Release assets, or repository files, the package was installed from
+    pub artifacts: Vec<LockedArtifact>,
+    /// Commit a branch, tag or commit reference was resolved to
+    pub commit: Option<SmolStr>,
+    /// Directories the files were stored or extracted to; paths below them
+    /// are the paths inside the archive
+    pub roots: Vec<PathBuf>,
 }
 
-impl Default for PackageDownloader {
-    fn default() -> Self {
-        Self::new()
+impl PackageFiles {
+    pub fn is_empty(&self) -> bool {
+        self.includes.is_empty()
+            && self.binaries.is_empty()
+            && self.root_binaries.is_empty()
+            && self.component_binaries.is_empty()
+            && self.plugin_binaries.is_empty()
+    }
+
+    fn extend(&mut self, other: PackageFiles) {
+        self.includes.extend(other.includes);
+        self.binaries.extend(other.binaries);
+        self.root_binaries.extend(other.root_binaries);
+        self.component_binaries.extend(other.component_binaries);
+        self.plugin_binaries.extend(other.plugin_binaries);
+        self.roots.extend(other.roots);
+    }
+
+    /** Destination of each include, relative to the project's include folder
+     *
+     * # Layout
+     * - Includes below a folder named `include` or `includes` (such as
+     *   `pawno/include/`) keep their path below the deepest such folder
+     * - Other includes keep their path inside the archive, without the
+     *   top-level folder they all share (e.g. `YSI-Includes-5.x/`)
+     * - Files outside [`Self::roots`] keep only their name
+     */
+    pub fn include_layout(&self) -> Vec<(PathBuf, PathBuf)> {
+        let relative: Vec<PathBuf> = self
+            .includes
+            .iter()
+            .map(|include| {
+                match self
+                    .roots
+                    .iter()
+                    .find_map(|root| include.strip_prefix(root).ok())
+                {
+                    Some(relative) => relative.to_path_buf(),
+                    None => include.file_name().map(PathBuf::from).unwrap_or_default(),
+                }
+            })
+            .collect();
+
+        let below_include_folder = |path: &Path| {
+            let components: Vec<_> = path.components().collect();
+            let folder = components.iter().rposition(|component| {
+                let name = component.as_os_str().to_string_lossy().to_lowercase();
+                name == "include" || name == "includes"
+            })?;
+            (folder + 1 < components.len())
+                .then(|| components[folder + 1..].iter().collect::<PathBuf>())
+        };
+
+        let others: Vec<&PathBuf> = relative
+            .iter()
+            .filter(|path| below_include_folder(path).is_none())
+            .collect();
+        let wrapper = others.first().and_then(|first| {
+            let top = first.components().next()?;
+            others
+                .iter()
+                .all(|path| path.components().count() > 1 && path.components().next() == Some(top))
+                .then_some(top)
+        });
+
+        self.includes
+            .iter()
+            .zip(&relative)
+            .map(|(include, path)| {
+                let destination = below_include_folder(path).unwrap_or_else(|| {
+                    match wrapper.and_then(|top| path.strip_prefix(top).ok()) {
+                        Some(stripped) => stripped.to_path_buf(),
+                        None => path.clone(),
+                    }
+                });
+                (include.clone(), destination)
+            })
+            .collect()
     }
 }
 
 impl PackageDownloader {
-    pub fn new() -> Self {
-        let client = Client::new();
-
-        let github = if let Ok(token) = std::env::var("GITHUB_TOKEN") {
-            if !token.is_empty() {
-                let crab = Octocrab::builder()
-                    .personal_token(token)
-                    .build()
-                    .unwrap_or_else(|_| Octocrab::default());
-                octocrab::initialise(crab);
-                octocrab::instance()
-            } else {
-                octocrab::instance()
-            }
-        } else {
-            octocrab::instance()
-        };
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            github: NetworkSettings::current().github()?,
+            http: HttpDownloader::new()?,
+            release_cache: ReleaseCache::new()?,
+            store: PackageStore::new()?,
+            listings: Mutex::new(HashMap::new()),
+            jobs: DEFAULT_JOBS,
+            slots: Semaphore::new(DEFAULT_JOBS),
+        })
+    }
 
-        Self { github, client }
+    /// Limits how many assets are downloaded and extracted at once.
+    pub fn set_jobs(&mut self, jobs: usize) {
+        self.jobs = jobs.max(1);
+        self.slots = Semaphore::new(self.jobs);
     }
 
-    pub async fn get_releases(&self, repo: &str) -> Result<Vec<GitHubRelease>> {
+    pub fn jobs(&self) -> usize {
+        self.jobs
+    }
+
+    /// Display that download progress bars are drawn in.
+    pub fn progress(&self) -> &MultiProgress {
+        self.http.progress()
+    }
+
+    /** Fetches one page of the releases of `repo`, using the on-disk release cache
+     *
+     * # Behaviour
+     * - Fresh cache entries (or any entry in offline mode) skip the API
+     * - Stale entries are revalidated with `If-None-Match`; a `304` keeps
+     *   them without spending the body download
+     * - When GitHub is unreachable or rate limited, cached data is used and
+     *   the reason (including the rate-limit reset time) is printed
+     */
+    pub async fn get_release_page(&self, repo: &str, page: u32) -> Result<Vec<GitHubRelease>> {
         let (owner, repo_name) = self.parse_repo(repo)?;
+        let route = format!(
+            "/repos/{}/{}/releases?per_page={}&page={}",
+            owner, repo_name, RELEASES_PER_PAGE, page
+        );
+        let cache_key = Self::cache_key(owner, repo_name, &format!("page-{}", page));
+
+        let cached = self.release_cache.load(&cache_key).await;
+
+        if let Some(entry) = &cached {
+            if NetworkSettings::is_offline() || self.release_cache.is_fresh(entry) {
+                log::info!(
+                    "Using cached release list of {} page {} ({}s old)",
+                    repo,
+                    page,
+                    entry.age().as_secs()
+                );
+                return Self::parse_releases(&entry.body);
+            }
+        }
+
+        NetworkSettings::ensure_online(&format!("Release list of {} (page {})", repo, page))?;
 
-        let releases = self
+        let mut headers = HeaderMap::new();
+        if let Some(etag) = cached.as_ref().and_then(|entry| entry.etag.as_deref()) {
+            if let Ok(value) = HeaderValue::from_str(etag) {
+                headers.insert(IF_NONE_MATCH, value);
+            }
+        }
+
+        let response = match self
             .github
-            .repos(owner, repo_name)
-            .releases()
-            .list()
-            .send()
+            ._get_with_headers(route.as_str(), Some(headers))
             .await
-            .map_err(|e| {
-                OpenCliError::Process(format!("Failed to fetch releases: {}", e).into())
+        {
+            Ok(response) => response,
+            Err(e) => {
+                return Self::fall_back_to_cache(
+                    repo,
+                    cached,
+                    format!("Failed to fetch releases: {}", e),
+                );
+            }
+        };
+
+        let status = response.status();
+
+        if status == StatusCode::NOT_MODIFIED {
+            if let Some(mut entry) = cached {
+                log::info!("Release list of {} not modified, refreshing cache", repo);
+                entry.fetched_at = chrono::Utc::now().timestamp();
+                self.release_cache.store(&cache_key, &entry).await?;
+                return Self::parse_releases(&entry.body);
+            }
+        }
+
+        if let Some(reset) = Self::rate_limit_reset(&response) {
+            return Self::fall_back_to_cache(
+                repo,
+                cached,
+                format!(
+                    "GitHub API rate limit exceeded, resets at {}. Run `opencli auth login` or set GITHUB_TOKEN to raise the limit",
+                    reset
+                ),
+            );
+        }
+
+        if status == StatusCode::NOT_FOUND {
+            // GitHub answers 404 for private repositories without access
+            let hint = if NetworkSettings::current().github_token().is_none() {
+                " (private repositories require `opencli auth login`)"
+            } else {
+                ""
+            };
+            return Err(OpenCliError::NotFound(
+                format!("Repository {} not found{}", repo, hint).into(),
+            ));
+        }
+
+        if !status.is_success() {
+            return Self::fall_back_to_cache(
+                repo,
+                cached,
+                format!("Failed to fetch releases: HTTP {}", status),
+            );
+        }
+
+        let etag = response
+            .headers()
+            .get(ETAG)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+
+        let body =
+            self.github.body_to_string(response).await.map_err(|e| {
+                OpenCliError::Process(format!("Failed to read releases: {}", e).into())
             })?;
 
-        let mut github_releases = Vec::new();
+        let releases = Self::parse_releases(&body)?;
 
-        for release in releases.items {
-            let assets = release
-                .assets
-                .into_iter()
-                .map(|asset| GitHubAsset {
-                    name: asset.name,
-                    download_url: asset.browser_download_url.to_string(),
-                    size: asset.size as u64,
-                })
-                .collect();
-
-            github_releases.push(GitHubRelease {
-                tag_name: release.tag_name,
-                assets,
-            });
+        let entry = CachedResponse::new(&route, etag, body);
+        if let Err(e) = self.release_cache.store(&cache_key, &entry).await {
+            log::warn!("Failed to cache release list of {}: {}", repo, e);
+        }
+
+        Ok(releases)
+    }
+
+    /// Release cache key of `name` for a repository, scoped to the configured
+    /// API so listings of a GitHub Enterprise server never answer for github.com.
+    fn cache_key(owner: &str, repo_name: &str, name: &str) -> String {
+        let api_url = NetworkSettings::current().github_api_url();
+        let api = api_url
+            .split_once("://")
+            .map_or(api_url, |(_, rest)| rest)
+            .trim_end_matches('/');
+        format!("{}/{}/{}/{}", api, owner, repo_name, name)
+    }
+
+    fn fall_back_to_cache(
+        repo: &str,
+        cached: Option<CachedResponse>,
+        reason: String,
+    ) -> Result<Vec<GitHubRelease>> {
+        match cached {
+            Some(entry) => {
+                log::warn!("{}; using cached release list of {}", reason, repo);
+                println!(
+                    "Warning: {}. Using cached release list of {} from {} minutes ago.",
+                    reason,
+                    repo,
+                    entry.age().as_secs() / 60
+                );
+                Self::parse_releases(&entry.body)
+            }
+            None => Err(OpenCliError::Process(reason.into())),
+        }
+    }
+
+    /// Returns the local reset time when the response reports an exhausted rate limit.
+    pub fn rate_limit_reset<B>(response: &http::Response<B>) -> Option<String> {
+        let status = response.status();
+        if status != StatusCode::FORBIDDEN && status != StatusCode::TOO_MANY_REQUESTS {
+            return None;
+        }
+
+        let headers = response.headers();
+        let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+
+        if header("x-ratelimit-remaining") != Some("0") && header("retry-after").is_none() {
+            return None;
         }
 
-        Ok(github_releases)
+        let reset = header("x-ratelimit-reset")
+            .and_then(|value| value.parse::<i64>().ok())
+            .and_then(|epoch| chrono::DateTime::from_timestamp(epoch, 0))
+            .or_else(|| {
+                header("retry-after")
+                    .and_then(|value| value.parse::<i64>().ok())
+                    .map(|secs| chrono::Utc::now() + chrono::Duration::seconds(secs))
+            })?;
+
+        Some(
+            reset
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string(),
+        )
+    }
+
+    fn parse_releases(body: &str) -> Result<Vec<GitHubRelease>> {
+        let releases: Vec<Release> = serde_json::from_str(body)?;
+
+        Ok(releases
+            .into_iter()
+            .map(|release| GitHubRelease {
+                tag_name: release.tag_name,
+                assets: release
+                    .assets
+                    .into_iter()
+                    .map(|asset| GitHubAsset {
+                        name: asset.name,
+                        download_url: asset.browser_download_url.to_string(),
+                        api_url: asset.url.to_string(),
+                        size: asset.size as u64,
+                    })
+                    .collect(),
+            })
+            .collect())
     }
 
+    /** Resolves the newest release of `repo` satisfying `constraint`
+     *
+     * # Behaviour
+     * - Release pages are fetched lazily, newest first
+     * - Stops as soon as a fetched release satisfies the constraint, or
+     *   once a whole page lies below the constraint's lower bound
+     * - Pages fetched for one constraint are kept and reused by every
+     *   later or concurrent resolution of the same repository
+     */
     pub async fn find_matching_version(
         &self,
         repo: &str,
         constraint: &VersionConstraint,
     ) -> Result<GitHubRelease> {
-        let releases = self.get_releases(repo).await?;
+        self.find_release_matching_all(repo, std::slice::from_ref(constraint))
+            .await
+    }
+
+    /// Like [`Self::find_matching_version`], for a release satisfying every
+    /// one of `constraints` at once.
+    pub async fn find_release_matching_all(
+        &self,
+        repo: &str,
+        constraints: &[VersionConstraint],
+    ) -> Result<GitHubRelease> {
+        let listing = self.listing(repo);
+        let mut listing = listing.lock().await;
+
+        // Every constraint has to hold, so the highest lower bound applies
+        let lower_bound = constraints
+            .iter()
+            .filter_map(VersionConstraint::lower_bound)
+            .max();
+
+        loop {
+            if let Some(release) = Self::best_match(&listing.releases, constraints) {
+                return Ok(release.clone());
+            }
+
+            if listing.complete {
+                break;
+            }
+
+            let page = listing.pages_fetched + 1;
+            let releases = self.get_release_page(repo, page).await?;
+            listing.pages_fetched = page;
+            listing.complete = releases.len() < RELEASES_PER_PAGE as usize;
+
+            let below_lower_bound = lower_bound.is_some_and(|bound| {
+                !releases.is_empty()
+                    && releases.iter().all(|release| {
+                        Version::parse(&release.tag_name).map_or(true, |version| &version < bound)
+                    })
+            });
+
+            listing.releases.extend(releases);
 
-        let versions: Vec<(Version, &GitHubRelease)> = releases
+            if below_lower_bound {
+                log::info!(
+                    "Stopped listing releases of {} at page {}: all versions are older than the constraint",
+                    repo,
+                    page
+                );
+                if let Some(release) = Self::best_match(&listing.releases, constraints) {
+                    return Ok(release.clone());
+                }
+                break;
+            }
+        }
+
+        Err(OpenCliError::NotFound(
+            format!(
+                "No release of {} matches the version constraint ({} releases checked)",
+                repo,
+                listing.releases.len()
+            )
+            .into(),
+        ))
+    }
+
+    fn listing(&self, repo: &str) -> Arc<AsyncMutex<ReleaseListing>> {
+        let mut listings = self.listings.lock().unwrap_or_else(|e| e.into_inner());
+        listings.entry(repo.to_string()).or_default().clone()
+    }
+
+    fn best_match<'a>(
+        releases: &'a [GitHubRelease],
+        constraints: &[VersionConstraint],
+    ) -> Option<&'a GitHubRelease> {
+        releases
             .iter()
             .filter_map(|release| Version::parse(&release.tag_name).ok().map(|v| (v, release)))
-            .collect();
+            .filter(|(version, _)| constraints.iter().all(|c| c.matches(version)))
+            .max_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(_, release)| release)
+    }
 
-        let version_refs: Vec<&Version> = versions.iter().map(|(v, _)| v).collect();
-        if let Some(matched_version) =
-            constraint.latest_matching(&version_refs.iter().map(|&v| v.clone()).collect::<Vec<_>>())
-        {
-            if let Some((_, release)) = versions.iter().find(|(ver, _)| ver == matched_version) {
-                Ok((*release).clone())
-            } else {
-                Err(OpenCliError::NotFound(
-                    "No matching version found for constraint"
-                        .to_string()
-                        .into(),
-                ))
+    /** Reads the dependency manifest of `repo` at release `tag`
+     *
+     * # Behaviour
+     * - Looks for `opencli.toml` with a `[package]` section, then `pawn.json`
+     * - Results, including the absence of a manifest, are cached for good
+     *   since a release tag does not change its contents
+     * - Offline, an uncached manifest is treated as declaring no dependencies
+     */
+    pub async fn fetch_manifest(&self, repo: &str, tag: &str) -> Result<Option<PackageManifest>> {
+        let (owner, repo_name) = self.parse_repo(repo)?;
+        let cache_key = Self::cache_key(owner, repo_name, &format!("manifest-{}", tag));
+
+        if let Some(entry) = self.release_cache.load(&cache_key).await {
+            log::info!("Using cached manifest of {} {}", repo, tag);
+            return Self::parse_manifest(repo, &entry.url, &entry.body);
+        }
+
+        if NetworkSettings::is_offline() {
+            log::warn!(
+                "Offline: dependencies of {} {} are not known and were not checked",
+                repo,
+                tag
+            );
+            return Ok(None);
+        }
+
+        let mut found = None;
+        for file_name in MANIFEST_FILES {
+            if let Some(content) = self.get_file_content(repo, tag, file_name).await? {
+                if file_name != "opencli.toml"
+                    || PackageManifest::from_opencli_toml(&content)?.is_some()
+                {
+                    found = Some((file_name, content));
+                    break;
+                }
             }
-        } else {
-            Err(OpenCliError::NotFound(
-                "No matching version found for constraint"
-                    .to_string()
-                    .into(),
-            ))
+        }
+
+        let (file_name, body) = found.unwrap_or_default();
+        let entry = CachedResponse::new(file_name, None, body);
+        if let Err(e) = self.release_cache.store(&cache_key, &entry).await {
+            log::warn!("Failed to cache manifest of {} {}: {}", repo, tag, e);
+        }
+
+        Self::parse_manifest(repo, &entry.url, &entry.body)
+    }
+
+    fn parse_manifest(
+        repo: &str,
+        file_name: &str,
+        content: &str,
+    ) -> Result<Option<PackageManifest>> {
+        if file_name.is_empty() {
+            return Ok(None);
+        }
+
+        log::info!("Reading dependencies of {} from {}", repo, file_name);
+        PackageManifest::parse(file_name, content).map_err(|e| {
+            OpenCliError::Config(format!("Invalid {} in {}: {}", file_name, repo, e).into())
+        })
+    }
+
+    /// Fetches one file of the repository at `tag`, `None` when it does not exist.
+    async fn get_file_content(&self, repo: &str, tag: &str, path: &str) -> Result<Option<String>> {
+        let (owner, repo_name) = self.parse_repo(repo)?;
+
+        let result = self
+            .github
+            .repos(owner, repo_name)
+            .get_content()
+            .path(path)
+            .r#ref(tag)
+            .send()
+            .await;
+
+        match result {
+            Ok(mut contents) => Ok(contents
+                .take_items()
+                .into_iter()
+                .next()
+                .and_then(|item| item.decoded_content())),
+            Err(octocrab::Error::GitHub { source, .. })
+                if source.status_code == StatusCode::NOT_FOUND =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(OpenCliError::Process(
+                format!("Failed to read {} of {} {}: {}", path, repo, tag, e).into(),
+            )),
         }
     }
 
@@ -154,200 +599,437 @@ impl PackageDownloader {
         temp_dir: &Path,
         target: Option<&crate::build::config::PackageTarget>,
     ) -> Result<PackageFiles> {
-        create_dir_all(temp_dir).await?;
+        let artifacts = release.assets.iter().map(LockedArtifact::from_asset);
+        let (mut package_files, unsupported_archives) = self
+            .download_assets(repo, artifacts.collect(), temp_dir, target)
+            .await?;
+
+        if package_files.is_empty() {
+            // An unextractable archive must not silently install nothing
+            if let Some(error) = unsupported_archives.into_iter().next() {
+                return Err(error);
+            }
 
-        let mut package_files = PackageFiles {
-            includes: Vec::new(),
-            binaries: Vec::new(),
-            root_binaries: Vec::new(),
-            component_binaries: Vec::new(),
-            plugin_binaries: Vec::new(),
-        };
+            log::info!(
+                "{} {} has no usable assets, installing its source archive",
+                repo,
+                release.tag_name
+            );
+            package_files = self
+                .download_ref_archive(repo, &release.tag_name, temp_dir, target)
+                .await?;
+        }
 
-        for asset in &release.assets {
-            let asset_path = temp_dir.join(&asset.name);
-            self.download_asset(asset, &asset_path).await?;
+        Ok(package_files)
+    }
 
-            if self.is_archive(&asset.name) {
-                let extracted = self.extract_archive(&asset_path, temp_dir, target).await?;
-                package_files.includes.extend(extracted.includes);
-                package_files.binaries.extend(extracted.binaries);
-                package_files.root_binaries.extend(extracted.root_binaries);
-                package_files
-                    .component_binaries
-                    .extend(extracted.component_binaries);
-                package_files
-                    .plugin_binaries
-                    .extend(extracted.plugin_binaries);
-            } else if INCLUDE_REGEX.is_match(&asset.name) {
-                package_files.includes.push(asset_path);
-            } else if BINARY_REGEX.is_match(&asset.name) {
-                self.categorize_binary(&asset_path, &mut package_files);
+    /** Downloads exactly the artifacts recorded in the lock file
+     *
+     * # Notes
+     * - Neither the release list nor the repository contents are consulted,
+     *   so nothing but the artifacts themselves is fetched
+     * - An artifact whose SHA-256 differs from the recorded one is an error
+     */
+    pub async fn download_locked_package(
+        &self,
+        repo: &str,
+        tag: &str,
+        artifacts: &[LockedArtifact],
+        temp_dir: &Path,
+        target: Option<&crate::build::config::PackageTarget>,
+    ) -> Result<PackageFiles> {
+        let (package_files, unsupported_archives) = self
+            .download_assets(repo, artifacts.to_vec(), temp_dir, target)
+            .await?;
+
+        if package_files.is_empty() {
+            if let Some(error) = unsupported_archives.into_iter().next() {
+                return Err(error);
             }
-        }
 
-        if package_files.includes.is_empty()
-            && package_files.binaries.is_empty()
-            && package_files.root_binaries.is_empty()
-            && package_files.component_binaries.is_empty()
-            && package_files.plugin_binaries.is_empty()
-        {
-            self.download_repo_content(repo, &release.tag_name, temp_dir, &mut package_files)
-                .await?;
+            return Err(OpenCliError::NotFound(
+                format!(
+                    "The artifacts locked for {} {} contain no package files",
+                    repo, tag
+                )
+                .into(),
+            ));
         }
 
         Ok(package_files)
     }
 
-    async fn download_asset(&self, asset: &GitHubAsset, output_path: &Path) -> Result<()> {
-        let response = self
-            .client
-            .get(&asset.download_url)
-            .header("User-Agent", "opencli/0.1.0")
-            .send()
-            .await
-            .map_err(|e| OpenCliError::Process(format!("Download failed: {}", e).into()))?;
+    /** Downloads and unpacks `artifacts` through the package store
+     *
+     * # Returns
+     * - The package files, inside the store
+     * - The errors of archives that could not be extracted, kept separate
+     *
+     * # Notes
+     * - Artifacts are fetched concurrently, at most [`Self::jobs`] at once
+     *   across all packages, and combined in their original order
+     * - Artifacts whose SHA-256 is known and stored are not downloaded
+     */
+    async fn download_assets(
+        &self,
+        repo: &str,
+        artifacts: Vec<LockedArtifact>,
+        temp_dir: &Path,
+        target: Option<&crate::build::config::PackageTarget>,
+    ) -> Result<(PackageFiles, Vec<OpenCliError>)> {
+        create_dir_all(temp_dir).await?;
 
-        if !response.status().is_success() {
-            return Err(OpenCliError::Process(
-                format!("Download failed: HTTP {}", response.status()).into(),
-            ));
+        let mut supported = Vec::new();
+        let mut unsupported_archives = Vec::new();
+
+        for artifact in artifacts {
+            match ArchiveFormat::from_file_name(&artifact.name) {
+                Ok(format) => supported.push((artifact, format)),
+                Err(e) => {
+                    log::warn!("Skipping asset {} of {}: {}", artifact.name, repo, e);
+                    unsupported_archives.push(e);
+                }
+            }
         }
 
-        let pb = ProgressBar::new(asset.size);
-        pb.set_style(ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta})")
-            .unwrap()
-            .progress_chars("#>-"));
-        pb.set_message(format!("Downloading {}", asset.name));
+        let fetched: Vec<Result<Option<(LockedArtifact, PackageFiles)>>> = stream::iter(supported)
+            .map(|(artifact, format)| self.fetch_artifact(repo, artifact, format, temp_dir, target))
+            .buffered(self.jobs)
+            .collect()
+            .await;
+
+        let mut package_files = PackageFiles::default();
+        for result in fetched {
+            if let Some((artifact, files)) = result? {
+                package_files.extend(files);
+                package_files.artifacts.push(artifact);
+            }
+        }
 
-        let bytes = response
-            .bytes()
+        Ok((package_files, unsupported_archives))
+    }
+
+    /** Downloads or reuses one artifact and lists its package files
+     *
+     * # Returns
+     * - `None` for a plain file that is neither an include nor a binary
+     *
+     * # Notes
+     * - The download is hashed chunk by chunk as it is written to disk, and
+     *   extraction copies entries through a fixed buffer, so memory stays
+     *   bounded regardless of the archive size
+     * - Extraction starts once the download is complete: a locked SHA-256
+     *   must match before anything is unpacked, zip archives list their
+     *   entries at the end, and the store and resumed downloads need the
+     *   whole file on disk
+     */
+    async fn fetch_artifact(
+        &self,
+        repo: &str,
+        mut artifact: LockedArtifact,
+        format: Option<ArchiveFormat>,
+        temp_dir: &Path,
+        target: Option<&crate::build::config::PackageTarget>,
+    ) -> Result<Option<(LockedArtifact, PackageFiles)>> {
+        artifact.validate()?;
+        let asset = artifact.to_asset();
+
+        let _slot = self
+            .slots
+            .acquire()
             .await
-            .map_err(|e| OpenCliError::Process(format!("Download failed: {}", e).into()))?;
+            .map_err(|e| OpenCliError::Process(e.to_string().into()))?;
 
-        pb.set_position(bytes.len() as u64);
+        let stored = artifact
+            .sha256
+            .as_deref()
+            .and_then(|sha256| self.store.find(sha256, &asset.name));
 
-        let mut file = File::create(output_path).await?;
-        file.write_all(&bytes).await?;
-        file.flush().await?;
+        let (asset_path, sha256) = match stored {
+            Some(path) => {
+                log::info!("Using {} of {} from the package store", asset.name, repo);
+                (path, artifact.sha256.clone().unwrap_or_default())
+            }
+            None => {
+                let download_path = temp_dir.join(&asset.name);
+                let downloaded = self.download_asset(&asset, &download_path).await?;
+
+                if let Some(expected) = &artifact.sha256 {
+                    if !expected.eq_ignore_ascii_case(&downloaded.sha256) {
+                        return Err(OpenCliError::Process(
+                            format!(
+                                "Checksum mismatch for {} of {}: expected sha256 {}, the download has {}",
+                                asset.name, repo, expected, downloaded.sha256
+                            )
+                            .into(),
+                        ));
+                    }
+                }
 
-        pb.finish_with_message(format!("Downloaded {}", asset.name));
-        Ok(())
+                let path = self
+                    .store
+                    .add(&download_path, &downloaded.sha256, &asset.name)
+                    .await?;
+                (path, SmolStr::from(downloaded.sha256))
+            }
+        };
+        artifact.sha256 = Some(sha256.clone());
+
+        let package_files = match format {
+            Some(format) => {
+                self.extract_stored(&asset_path, format, &sha256, target)
+                    .await?
+            }
+            None => {
+                let mut package_files = PackageFiles::default();
+                if !self.add_file(&asset_path, &mut package_files) {
+                    return Ok(None);
+                }
+                package_files
+            }
+        };
+
+        Ok(Some((artifact, package_files)))
     }
 
-    async fn extract_archive(
+    /// Lists the extracted contents of a stored archive, extracting it first
+    /// when this has not happened before.
+    async fn extract_stored(
         &self,
         archive_path: &Path,
-        extract_dir: &Path,
+        format: ArchiveFormat,
+        sha256: &str,
         target: Option<&crate::build::config::PackageTarget>,
     ) -> Result<PackageFiles> {
-        let file_name = archive_path
+        let contents = self.store.contents_dir(sha256);
+
+        if !contents.is_dir() {
+            let staging = contents.with_file_name(format!(
+                "contents.partial-{}-{}",
+                std::process::id(),
+                STAGING_COUNTER.fetch_add(1, Ordering::Relaxed)
+            ));
+            if staging.exists() {
+                fs::remove_dir_all(&staging).await?;
+            }
+            create_dir_all(&staging).await?;
+            ArchiveExtractor::new(&staging)
+                .extract(archive_path, format)
+                .await?;
+
+            if let Err(e) = fs::rename(&staging, &contents).await {
+                // Another install extracted the same archive meanwhile
+                if !contents.is_dir() {
+                    return Err(e.into());
+                }
+                fs::remove_dir_all(&staging).await?;
+            }
+        }
+
+        let (files, entries) = list_files(&contents, None).await?;
+        Ok(PackageFiles {
+            roots: vec![contents],
+            ..self.filter_files_by_target(files, entries, target)
+        })
+    }
+
+    /// Adds a single include or binary to `package_files`. Returns false for
+    /// files that are neither.
+    fn add_file(&self, path: &Path, package_files: &mut PackageFiles) -> bool {
+        let name = path
             .file_name()
             .and_then(|n| n.to_str())
-            .unwrap_or("");
+            .unwrap_or_default();
 
-        if file_name.ends_with(".zip") {
-            self.extract_zip(archive_path, extract_dir, target).await
-        } else if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
-            self.extract_tar_gz(archive_path, extract_dir, target).await
+        if INCLUDE_REGEX.is_match(name) {
+            package_files.includes.push(path.to_path_buf());
+        } else if BINARY_REGEX.is_match(name) {
+            self.categorize_binary(path, package_files);
         } else {
-            Ok(PackageFiles {
-                includes: Vec::new(),
-                binaries: Vec::new(),
-                root_binaries: Vec::new(),
-                component_binaries: Vec::new(),
-                plugin_binaries: Vec::new(),
-            })
+            return false;
+        }
+
+        if let Some(parent) = path.parent() {
+            package_files.roots.push(parent.to_path_buf());
         }
+        true
     }
 
-    async fn extract_zip(
+    /** Fetches a package that is not a GitHub release
+     *
+     * # Behaviour
+     * - Git and URL sources are downloaded and unpacked like release assets;
+     *   a `sha256` given for a URL must match the download
+     * - The rev of a git source is resolved to its commit first, so the
+     *   archive and the lock file name an immutable snapshot
+     * - Local paths, relative to `project_dir`, are used in place: a
+     *   directory is searched like an extracted archive, a file is unpacked
+     *   or copied
+     * - A GitHub ref is resolved to its commit, whose source archive is
+     *   installed; the commit is returned in [`PackageFiles::commit`]
+     */
+    pub async fn download_source(
         &self,
-        zip_path: &Path,
-        extract_dir: &Path,
+        name: &str,
+        source: &PackageSource,
+        project_dir: &Path,
+        temp_dir: &Path,
         target: Option<&crate::build::config::PackageTarget>,
     ) -> Result<PackageFiles> {
-        let file = std::fs::File::open(zip_path)?;
-        let mut archive = ZipArchive::new(file)
-            .map_err(|e| OpenCliError::Process(format!("Invalid ZIP archive: {}", e).into()))?;
-
-        let mut all_files = Vec::new();
-        let mut archive_structure = Vec::new();
-
-        for i in 0..archive.len() {
-            let mut file = archive.by_index(i).map_err(|e| {
-                OpenCliError::Process(format!("ZIP extraction error: {}", e).into())
-            })?;
-
-            let file_path = extract_dir.join(file.name());
-            archive_structure.push(file.name().to_string());
-
-            if file.is_dir() {
-                create_dir_all(&file_path).await?;
-            } else {
-                if let Some(parent) = file_path.parent() {
-                    create_dir_all(parent).await?;
+        let package_files = match source {
+            PackageSource::Path { path } => {
+                self.read_local_package(name, &project_dir.join(path), temp_dir, target)
+                    .await?
+            }
+            PackageSource::Rev { rev } => {
+                self.download_ref_archive(name, rev, temp_dir, target)
+                    .await?
+            }
+            PackageSource::Git {
+                git,
+                rev: Some(rev),
+                forge,
+            } => {
+                let commit = self.resolve_git_commit(git, rev).await?;
+                let pinned = PackageSource::Git {
+                    git: git.clone(),
+                    rev: Some(commit.clone()),
+                    forge: *forge,
+                };
+                let artifacts = pinned.artifact()?.into_iter().collect();
+                let (mut package_files, unsupported_archives) = self
+                    .download_assets(name, artifacts, temp_dir, target)
+                    .await?;
+                if let Some(error) = unsupported_archives.into_iter().next() {
+                    return Err(error);
                 }
 
-                let mut output = File::create(&file_path).await?;
-                let mut buffer = Vec::new();
-                file.read_to_end(&mut buffer)
-                    .map_err(|e| OpenCliError::Process(format!("ZIP read error: {}", e).into()))?;
-                output.write_all(&buffer).await?;
-
-                all_files.push((file_path, file.name().to_string()));
+                log::info!("Installed {} {} from commit {}", name, rev, commit);
+                package_files.commit = Some(commit);
+                package_files
+            }
+            _ => {
+                let artifacts = source.artifact()?.into_iter().collect();
+                let (package_files, unsupported_archives) = self
+                    .download_assets(name, artifacts, temp_dir, target)
+                    .await?;
+                if let Some(error) = unsupported_archives.into_iter().next() {
+                    return Err(error);
+                }
+                package_files
             }
+        };
+
+        if package_files.is_empty() {
+            return Err(OpenCliError::NotFound(
+                format!(
+                    "{} ({}) contains no includes or server binaries",
+                    name, source
+                )
+                .into(),
+            ));
         }
 
-        Ok(self.filter_files_by_target(all_files, archive_structure, target))
+        Ok(package_files)
     }
 
-    async fn extract_tar_gz(
+    async fn read_local_package(
         &self,
-        tar_path: &Path,
-        extract_dir: &Path,
+        name: &str,
+        path: &Path,
+        temp_dir: &Path,
         target: Option<&crate::build::config::PackageTarget>,
     ) -> Result<PackageFiles> {
-        let file = std::fs::File::open(tar_path)?;
-        let decoder = GzDecoder::new(file);
-        let mut archive = Archive::new(decoder);
-
-        let mut all_files = Vec::new();
-        let mut archive_structure = Vec::new();
+        if !path.exists() {
+            return Err(OpenCliError::NotFound(
+                format!("Path {} of package {} does not exist", path.display(), name).into(),
+            ));
+        }
 
-        for entry in archive
-            .entries()
-            .map_err(|e| OpenCliError::Process(format!("TAR extraction error: {}", e).into()))?
-        {
-            let mut entry = entry
-                .map_err(|e| OpenCliError::Process(format!("TAR entry error: {}", e).into()))?;
+        if path.is_file() {
+            let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or(name);
+            let mut package_files = PackageFiles::default();
 
-            let entry_path = entry
-                .path()
-                .map_err(|e| OpenCliError::Process(format!("TAR path error: {}", e).into()))?;
-            let file_path = extract_dir.join(&entry_path);
-            let entry_path_string = entry_path.to_str().unwrap_or("").to_string();
+            match ArchiveFormat::from_file_name(file_name)? {
+                Some(format) => {
+                    create_dir_all(temp_dir).await?;
+                    package_files = self.extract_archive(path, format, temp_dir, target).await?;
+                }
+                None => {
+                    self.add_file(path, &mut package_files);
+                }
+            }
+            return Ok(package_files);
+        }
 
-            archive_structure.push(entry_path_string.clone());
+        // Paths are given relative to a top-level folder, like archive entries
+        let prefix = path.file_name().and_then(|n| n.to_str()).unwrap_or(name);
+        let (files, entries) = list_files(path, Some(prefix)).await?;
 
-            if entry.header().entry_type().is_file() {
-                if let Some(parent) = file_path.parent() {
-                    create_dir_all(parent).await?;
-                }
+        Ok(PackageFiles {
+            roots: vec![path.parent().unwrap_or(Path::new("")).to_path_buf()],
+            ..self.filter_files_by_target(files, entries, target)
+        })
+    }
 
-                let mut output = File::create(&file_path).await?;
-                let mut buffer = Vec::new();
-                entry
-                    .read_to_end(&mut buffer)
-                    .map_err(|e| OpenCliError::Process(format!("TAR read error: {}", e).into()))?;
-                output.write_all(&buffer).await?;
+    /// Downloads a release asset. With a token the asset is fetched through
+    /// the API, which is the only route that works for private repositories.
+    /// An API URL on another host is ignored and `download_url` is fetched
+    /// without credentials.
+    async fn download_asset(
+        &self,
+        asset: &GitHubAsset,
+        output_path: &Path,
+    ) -> Result<DownloadedFile> {
+        let expected_size = (asset.size > 0).then_some(asset.size);
+
+        let token = match asset.api_url.as_str() {
+            "" => None,
+            api_url => NetworkSettings::current().github_token_for(api_url),
+        };
 
-                all_files.push((file_path, entry_path_string));
+        match token {
+            Some(resolved) => {
+                let mut headers = HeaderMap::new();
+                let authorization = HeaderValue::from_str(&format!("Bearer {}", resolved.token))
+                    .map_err(|_| {
+                        OpenCliError::Config("GitHub token contains invalid characters".into())
+                    })?;
+                headers.insert(AUTHORIZATION, authorization);
+                headers.insert(ACCEPT, HeaderValue::from_static("application/octet-stream"));
+
+                self.http
+                    .download_with_headers(
+                        &asset.api_url,
+                        output_path,
+                        &asset.name,
+                        expected_size,
+                        &headers,
+                    )
+                    .await
+            }
+            _ => {
+                self.http
+                    .download(&asset.download_url, output_path, &asset.name, expected_size)
+                    .await
             }
         }
+    }
 
-        Ok(self.filter_files_by_target(all_files, archive_structure, target))
+    async fn extract_archive(
+        &self,
+        archive_path: &Path,
+        format: ArchiveFormat,
+        extract_dir: &Path,
+        target: Option<&crate::build::config::PackageTarget>,
+    ) -> Result<PackageFiles> {
+        let extracted = ArchiveExtractor::new(extract_dir)
+            .extract(archive_path, format)
+            .await?;
+        Ok(PackageFiles {
+            roots: vec![extract_dir.to_path_buf()],
+            ..self.filter_files_by_target(extracted.files, extracted.entries, target)
+        })
     }
 
     fn filter_files_by_target(
@@ -356,13 +1038,7 @@ impl PackageDownloader {
         archive_structure: Vec<String>,
         target: Option<&crate::build::config::PackageTarget>,
     ) -> PackageFiles {
-        let mut package_files = PackageFiles {
-            includes: Vec::new(),
-            binaries: Vec::new(),
-            root_binaries: Vec::new(),
-            component_binaries: Vec::new(),
-            plugin_binaries: Vec::new(),
-        };
+        let mut package_files = PackageFiles::default();
 
         if let Some(target) = target {
             match target {
@@ -493,50 +1169,142 @@ impl PackageDownloader {
         package_files
     }
 
-    async fn download_repo_content(
+    /** Downloads the source archive of `repo` at a branch, tag or commit
+     *
+     * # Notes
+     * - The ref is resolved to a commit first, so the archive and the
+     *   recorded artifact name an immutable snapshot
+     * - With a token the archive is fetched through the API, which also
+     *   serves private repositories
+     */
+    async fn download_ref_archive(
         &self,
         repo: &str,
-        tag: &str,
+        rev: &str,
         temp_dir: &Path,
-        package_files: &mut PackageFiles,
-    ) -> Result<()> {
+        target: Option<&crate::build::config::PackageTarget>,
+    ) -> Result<PackageFiles> {
         let (owner, repo_name) = self.parse_repo(repo)?;
+        let commit = self.resolve_commit(repo, rev).await?;
+
+        let settings = NetworkSettings::current();
+        let artifact = LockedArtifact {
+            name: format!("{}-{}.tar.gz", repo_name, &commit[..12]).into(),
+            url: format!(
+                "https://{}/{}/{}/archive/{}.tar.gz",
+                settings.github_host(),
+                owner,
+                repo_name,
+                commit
+            )
+            .into(),
+            api_url: Some(
+                format!(
+                    "{}/repos/{}/{}/tarball/{}",
+                    settings.github_api_url().trim_end_matches('/'),
+                    owner,
+                    repo_name,
+                    commit
+                )
+                .into(),
+            ),
+            size: None,
+            sha256: None,
+        };
 
-        let repo_obj = self.github.repos(owner, repo_name);
-        let contents = repo_obj
-            .get_content()
-            .r#ref(tag)
+        let (mut package_files, unsupported_archives) = self
+            .download_assets(repo, vec![artifact], temp_dir, target)
+            .await?;
+        if let Some(error) = unsupported_archives.into_iter().next() {
+            return Err(error);
+        }
+
+        log::info!("Installed {} {} from commit {}", repo, rev, commit);
+        package_files.commit = Some(commit);
+        Ok(package_files)
+    }
+
+    /// Resolves a branch, tag or abbreviated commit of `repo` to a full commit SHA.
+    async fn resolve_commit(&self, repo: &str, rev: &str) -> Result<SmolStr> {
+        if COMMIT_SHA_REGEX.is_match(rev) {
+            return Ok(rev.to_ascii_lowercase().into());
+        }
+
+        let (owner, repo_name) = self.parse_repo(repo)?;
+        NetworkSettings::ensure_online(&format!("Commit of {} {}", repo, rev))?;
+
+        let route = format!("/repos/{}/{}/commits/{}", owner, repo_name, rev);
+        let commit: serde_json::Value = match self.github.get(route, None::<&()>).await {
+            Ok(commit) => commit,
+            Err(octocrab::Error::GitHub { source, .. })
+                if source.status_code == StatusCode::NOT_FOUND
+                    || source.status_code == StatusCode::UNPROCESSABLE_ENTITY =>
+            {
+                return Err(OpenCliError::NotFound(
+                    format!("{} has no branch, tag or commit named {}", repo, rev).into(),
+                ));
+            }
+            Err(e) => {
+                return Err(OpenCliError::Process(
+                    format!("Failed to resolve {} of {}: {}", rev, repo, e).into(),
+                ));
+            }
+        };
+
+        commit["sha"]
+            .as_str()
+            .filter(|sha| COMMIT_SHA_REGEX.is_match(sha))
+            .map(SmolStr::from)
+            .ok_or_else(|| {
+                OpenCliError::Process(
+                    format!("GitHub returned no commit for {} of {}", rev, repo).into(),
+                )
+            })
+    }
+
+    /** Resolves a branch, tag or commit of the repository at `git` to a commit SHA
+     *
+     * # Notes
+     * - Refs are read from the smart HTTP advertisement every git host
+     *   serves, so neither a `git` executable nor a host API is needed
+     * - An abbreviated commit that no ref matches is kept as given
+     */
+    async fn resolve_git_commit(&self, git: &str, rev: &str) -> Result<SmolStr> {
+        if COMMIT_SHA_REGEX.is_match(rev) {
+            return Ok(rev.to_ascii_lowercase().into());
+        }
+
+        NetworkSettings::ensure_online(&format!("Commit of {} {}", git, rev))?;
+
+        let url = NetworkSettings::current().rewrite_url(&source::git_refs_url(git)?);
+        let advertisement = self
+            .http
+            .client()
+            .get(&url)
             .send()
             .await
+            .and_then(|response| response.error_for_status())
             .map_err(|e| {
-                OpenCliError::Process(format!("Failed to get repo content: {}", e).into())
+                OpenCliError::Process(format!("Failed to list refs of {}: {}", git, e).into())
+            })?
+            .bytes()
+            .await
+            .map_err(|e| {
+                OpenCliError::Process(format!("Failed to list refs of {}: {}", git, e).into())
             })?;
 
-        for item in contents.items {
-            if item.r#type == "file" {
-                let name = item.name;
-                if INCLUDE_REGEX.is_match(&name) {
-                    let file_path = temp_dir.join(&name);
-
-                    if let Some(download_url) = &item.download_url {
-                        let response = self.client.get(download_url).send().await.map_err(|e| {
-                            OpenCliError::Process(format!("Failed to download file: {}", e).into())
-                        })?;
-
-                        let content = response.bytes().await.map_err(|e| {
-                            OpenCliError::Process(format!("Failed to read file: {}", e).into())
-                        })?;
-
-                        let mut file = File::create(&file_path).await?;
-                        file.write_all(&content).await?;
+        if let Some(commit) = source::find_advertised_commit(&advertisement, rev) {
+            return Ok(commit);
+        }
 
-                        package_files.includes.push(file_path);
-                    }
-                }
-            }
+        if (7..40).contains(&rev.len()) && rev.bytes().all(|b| b.is_ascii_hexdigit()) {
+            log::warn!("{} names no ref of {}, using it as a commit", rev, git);
+            return Ok(rev.to_ascii_lowercase().into());
         }
 
-        Ok(())
+        Err(OpenCliError::NotFound(
+            format!("{} has no branch or tag named {}", git, rev).into(),
+        ))
     }
 
     fn parse_repo<'a>(&self, repo: &'a str) -> Result<(&'a str, &'a str)> {
@@ -587,11 +1355,54 @@ impl PackageDownloader {
             }
         }
     }
+}
+
+/** Lists the files below `dir` like the entries of an archive
+ *
+ * # Returns
+ * - Each file with its path, relative to `dir` and below `prefix` if given
+ * - Every folder (ending in `/`) and file path, for target detection
+ *
+ * # Notes
+ * - Hidden files and folders are skipped
+ */
+async fn list_files(
+    dir: &Path,
+    prefix: Option<&str>,
+) -> Result<(Vec<(PathBuf, String)>, Vec<String>)> {
+    let mut files = Vec::new();
+    let mut entries = Vec::new();
+    let mut pending = vec![(dir.to_path_buf(), prefix.unwrap_or_default().to_string())];
+
+    while let Some((dir, relative)) = pending.pop() {
+        if !relative.is_empty() {
+            entries.push(format!("{}/", relative));
+        }
+
+        let mut read_dir = fs::read_dir(&dir).await?;
+        while let Some(entry) = read_dir.next_entry().await? {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if file_name.starts_with('.') {
+                continue;
+            }
 
-    fn is_archive(&self, filename: &str) -> bool {
-        filename.ends_with(".zip")
-            || filename.ends_with(".tar.gz")
-            || filename.ends_with(".tgz")
-            || filename.ends_with(".rar")
+            let entry_path = entry.path();
+            let entry_relative = if relative.is_empty() {
+                file_name
+            } else {
+                format!("{}/{}", relative, file_name)
+            };
+            let file_type = entry.file_type().await?;
+
+            if file_type.is_dir() {
+                pending.push((entry_path, entry_relative));
+            } else if fs::metadata(&entry_path).await?.is_file() {
+                entries.push(entry_relative.clone());
+                files.push((entry_path, entry_relative));
+            }
+        }
     }
+
+    files.sort();
+    Ok((files, entries))
 }
@@ -1,23 +1,134 @@
 use crate::build::PackageTarget;
-use crate::result::Result;
+use crate::package::downloader::GitHubAsset;
+use crate::package::store::{is_file_name, is_sha256};
+use crate::package::version::{Version, VersionConstraint};
+use crate::package::{PackageSource, Requirement, Requirer};
+use crate::result::{OpenCliError, Result};
 use serde::{Deserialize, Serialize};
 use smol_str::SmolStr;
-use std::collections::HashMap;
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::Path;
 use tokio::fs;
 
+/// Lock file format written by this version.
+pub const LOCK_SCHEMA_VERSION: u32 = 2;
+
+/// Lock files from before the schema was versioned.
+fn legacy_schema_version() -> u32 {
+    1
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InstalledPackage {
+    /// Release tag the package was resolved to, or the source's label
     pub version: SmolStr,
     pub target: Option<PackageTarget>,
+    /// Where the package came from when it is not a GitHub release
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<PackageSource>,
+    /// Salted Argon2 hash of all files, only verifiable on this machine
     pub hash: SmolStr,
     pub installed_at: SmolStr,
     pub files: Vec<SmolStr>,
+    /// SHA-256 of each installed file, by path
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub checksums: BTreeMap<SmolStr, SmolStr>,
+    /// Version constraints the package's manifest declared, by repository
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dependencies: BTreeMap<SmolStr, SmolStr>,
+    /// Downloads the package was installed from, fetched again by `--locked`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artifacts: Vec<LockedArtifact>,
+}
+
+/// A release asset or repository file recorded in the lock file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedArtifact {
+    pub name: SmolStr,
+    pub url: SmolStr,
+    /// API endpoint of a release asset, used when a token is configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_url: Option<SmolStr>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// SHA-256 of the downloaded file, checked when it is fetched again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<SmolStr>,
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+impl LockedArtifact {
+    pub fn from_asset(asset: &GitHubAsset) -> Self {
+        Self {
+            name: asset.name.as_str().into(),
+            url: asset.download_url.as_str().into(),
+            api_url: (!asset.api_url.is_empty()).then(|| asset.api_url.as_str().into()),
+            size: (asset.size > 0).then_some(asset.size),
+            sha256: None,
+        }
+    }
+
+    /** Fails unless the artifact is safe to place on disk
+     *
+     * # Checks
+     * - `name` is a single plain file name, since it is joined onto the
+     *   temporary and store directories
+     * - `sha256`, when present, is 64 hex digits, since it names a store entry
+     */
+    pub fn validate(&self) -> Result<()> {
+        if !is_file_name(&self.name) {
+            return Err(OpenCliError::Config(
+                format!("Artifact name '{}' is not a plain file name", self.name).into(),
+            ));
+        }
+
+        if let Some(sha256) = &self.sha256 {
+            if !is_sha256(sha256) {
+                return Err(OpenCliError::Config(
+                    format!(
+                        "Artifact {} has an invalid sha256 '{}'; expected 64 hex digits",
+                        self.name, sha256
+                    )
+                    .into(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn to_asset(&self) -> GitHubAsset {
+        GitHubAsset {
+            name: self.name.to_string(),
+            download_url: self.url.to_string(),
+            api_url: self.api_url.as_deref().unwrap_or_default().to_string(),
+            size: self.size.unwrap_or(0),
+        }
+    }
+}
+
+/** Contents of `opencli.lock`
+ *
+ * # Format
+ * - `schema_version` identifies the layout; files without it are schema 1
+ * - Packages are kept sorted by name so the file only changes where the
+ *   installed packages do
+ */
+#[derive(Debug, Serialize, Deserialize)]
 pub struct PackageLock {
-    pub installed: HashMap<SmolStr, InstalledPackage>,
+    #[serde(default = "legacy_schema_version")]
+    pub schema_version: u32,
+    #[serde(default)]
+    pub installed: BTreeMap<SmolStr, InstalledPackage>,
+}
+
+impl Default for PackageLock {
+    fn default() -> Self {
+        Self {
+            schema_version: LOCK_SCHEMA_VERSION,
+            installed: BTreeMap::new(),
+        }
+    }
 }
 
 impl PackageLock {
@@ -27,10 +138,77 @@ impl PackageLock {
         }
 
         let content = fs::read_to_string(path).await?;
-        let lock: PackageLock = toml::from_str(&content)?;
+        let mut lock: PackageLock = toml::from_str(&content)?;
+
+        if lock.schema_version > LOCK_SCHEMA_VERSION {
+            return Err(OpenCliError::Config(
+                format!(
+                    "{} uses lock schema {}, but this opencli only reads up to schema {}; update opencli",
+                    path.display(),
+                    lock.schema_version,
+                    LOCK_SCHEMA_VERSION
+                )
+                .into(),
+            ));
+        }
+        if lock.schema_version < LOCK_SCHEMA_VERSION {
+            lock.migrate();
+        }
+
+        if let Some(project_dir) = path.parent() {
+            lock.relativize_files(project_dir);
+        }
+
+        for (name, package) in &lock.installed {
+            for artifact in &package.artifacts {
+                artifact.validate().map_err(|e| {
+                    OpenCliError::Config(format!("{} entry {}: {}", path.display(), name, e).into())
+                })?;
+            }
+        }
+
         Ok(lock)
     }
 
+    /** Upgrades a lock file read in an older schema
+     *
+     * # Notes
+     * - Schema 1 entries lack checksums and artifact digests; they are kept
+     *   as they are and gain them the next time the package is installed,
+     *   since hashing the files on disk now would vouch for whatever is there
+     * - The upgraded layout is written on the next save
+     */
+    fn migrate(&mut self) {
+        log::info!(
+            "Migrating opencli.lock from schema {} to {}",
+            self.schema_version,
+            LOCK_SCHEMA_VERSION
+        );
+        self.schema_version = LOCK_SCHEMA_VERSION;
+    }
+
+    /// Rewrites absolute file paths below `project_dir`, as older versions
+    /// recorded binaries, relative to it. Paths elsewhere are kept so that
+    /// removing them is refused.
+    fn relativize_files(&mut self, project_dir: &Path) {
+        let relative = |file: &SmolStr| -> SmolStr {
+            match Path::new(file.as_str()).strip_prefix(project_dir) {
+                Ok(stripped) if Path::new(file.as_str()).is_absolute() => {
+                    stripped.to_string_lossy().as_ref().into()
+                }
+                _ => file.clone(),
+            }
+        };
+
+        for package in self.installed.values_mut() {
+            package.files = package.files.iter().map(relative).collect();
+            package.checksums = std::mem::take(&mut package.checksums)
+                .into_iter()
+                .map(|(file, checksum)| (relative(&file), checksum))
+                .collect();
+        }
+    }
+
     pub async fn save_to_file(&self, path: &Path) -> Result<()> {
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent).await?;
@@ -48,16 +226,28 @@ impl PackageLock {
         target: Option<PackageTarget>,
         hash: SmolStr,
         files: Vec<SmolStr>,
-    ) {
+        dependencies: BTreeMap<SmolStr, SmolStr>,
+    ) -> &mut InstalledPackage {
         let installed_at = chrono::Utc::now().to_rfc3339().into();
         let package = InstalledPackage {
             version,
             target,
             hash,
             installed_at,
+            source: None,
             files,
+            checksums: BTreeMap::new(),
+            dependencies,
+            artifacts: Vec::new(),
         };
-        self.installed.insert(name, package);
+
+        match self.installed.entry(name) {
+            Entry::Occupied(mut entry) => {
+                entry.insert(package);
+                entry.into_mut()
+            }
+            Entry::Vacant(entry) => entry.insert(package),
+        }
     }
 
     pub fn remove_package(&mut self, name: &str) -> Option<InstalledPackage> {
@@ -82,4 +272,127 @@ impl PackageLock {
             .map(|(k, v)| (k.as_str(), v))
             .collect()
     }
+
+    /** Lists every way the lock file disagrees with `requirements`
+     *
+     * # Checks
+     * - Each requirement, and each dependency recorded for a locked
+     *   package, has a locked version satisfying its constraint
+     * - A target given in `opencli.toml` matches the locked one
+     * - Nothing is locked that no requirement leads to
+     */
+    pub fn out_of_sync(&self, requirements: Vec<Requirement>) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut required = BTreeSet::new();
+        let mut pending = requirements;
+        pending.reverse();
+
+        while let Some(requirement) = pending.pop() {
+            let repo = requirement.repo.as_str();
+            let Some(installed) = self.get_package(repo) else {
+                problems.push(format!(
+                    "{} {} (required by {}) is not locked",
+                    repo, requirement.constraint, requirement.required_by
+                ));
+                continue;
+            };
+
+            if requirement.source != installed.source {
+                let describe = |source: Option<&PackageSource>| {
+                    source.map_or("GitHub releases".to_string(), |source| source.to_string())
+                };
+                problems.push(format!(
+                    "{} comes from {} in {}, but is locked from {}",
+                    repo,
+                    describe(requirement.source.as_ref()),
+                    requirement.required_by,
+                    describe(installed.source.as_ref())
+                ));
+            }
+
+            let satisfied = installed.source.is_some()
+                || VersionConstraint::parse(&requirement.constraint)
+                    .and_then(|constraint| {
+                        Ok(constraint.matches(&Version::parse(&installed.version)?))
+                    })
+                    .unwrap_or(false);
+            if !satisfied {
+                problems.push(format!(
+                    "{} {} (required by {}) is locked at {}",
+                    repo, requirement.constraint, requirement.required_by, installed.version
+                ));
+            }
+
+            if let Some(target) = &requirement.target {
+                if installed.target.as_ref() != Some(target) {
+                    problems.push(format!(
+                        "{} targets {} in {}, but is locked for {}",
+                        repo,
+                        target.as_str(),
+                        requirement.required_by,
+                        installed
+                            .target
+                            .as_ref()
+                            .map_or("auto-detection", |t| t.as_str())
+                    ));
+                }
+            }
+
+            if required.insert(repo.to_string()) {
+                for (dependency, constraint) in installed.dependencies.iter().rev() {
+                    pending.push(Requirement::new(
+                        dependency,
+                        constraint,
+                        None,
+                        Requirer::Package {
+                            repo: repo.into(),
+                            version: installed.version.clone(),
+                        },
+                    ));
+                }
+            }
+        }
+
+        let unused = self
+            .list_packages()
+            .into_iter()
+            .filter(|(name, _)| !required.contains(*name));
+        for (name, package) in unused {
+            problems.push(format!(
+                "{} {} is locked but no longer required",
+                name, package.version
+            ));
+        }
+
+        problems
+    }
+
+    /// Locked package names, each after the packages it depends on.
+    pub fn install_order(&self) -> Vec<&str> {
+        let mut order = Vec::new();
+        let mut visited = BTreeSet::new();
+        for name in self.installed.keys() {
+            self.visit(name, &mut visited, &mut order);
+        }
+        order
+    }
+
+    fn visit<'a>(
+        &'a self,
+        name: &'a str,
+        visited: &mut BTreeSet<&'a str>,
+        order: &mut Vec<&'a str>,
+    ) {
+        let Some((name, package)) = self.installed.get_key_value(name) else {
+            return;
+        };
+        if !visited.insert(name.as_str()) {
+            return;
+        }
+
+        for dependency in package.dependencies.keys() {
+            self.visit(dependency, visited, order);
+        }
+        order.push(name.as_str());
+    }
 }
@@ -1,13 +1,20 @@
 use crate::build::{BuildConfig, PackageSpec, PackageTarget};
 use crate::cache::CacheManager;
+use crate::package::downloader::PackageFiles;
+use crate::package::outdated;
+use crate::package::store::{place_file, LinkMode, PackageStore};
 use crate::package::{
-    ConfigManager, PackageDownloader, PackageLock, VersionConstraint, WorkspaceDetector,
+    ConfigManager, DependencyResolver, InstalledPackage, PackageDownloader, PackageLock,
+    PackageSource, Requirement, Requirer, ResolvedPackage, VersionConstraint, WorkspaceDetector,
 };
 use crate::result::{OpenCliError, Result};
-use crate::security::SecurityManager;
+use crate::security::{sha256_file, sha256_update_from_file, SecurityManager};
+use futures_util::future::{FutureExt, LocalBoxFuture};
+use futures_util::stream::{self, StreamExt};
 use indicatif::{ProgressBar, ProgressStyle};
 use smol_str::SmolStr;
-use std::path::{Path, PathBuf};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
 use tokio::fs;
 
 pub struct PackageManager {
@@ -16,73 +23,613 @@ pub struct PackageManager {
     security: SecurityManager,
     cache: CacheManager,
     config_manager: ConfigManager,
+    store: PackageStore,
+    root: PathBuf,
     config_path: PathBuf,
     lock_path: PathBuf,
 }
 
 impl PackageManager {
-    pub fn new<P: AsRef<Path>>(workspace_root: P, config_path: P) -> Self {
+    pub fn new<P: AsRef<Path>>(workspace_root: P, config_path: P) -> Result<Self> {
         let workspace_path = workspace_root.as_ref();
         let config_path_buf = config_path.as_ref().to_path_buf();
         let lock_path = config_path_buf.with_extension("lock");
 
-        Self {
-            downloader: PackageDownloader::new(),
+        Ok(Self {
+            downloader: PackageDownloader::new()?,
             workspace: WorkspaceDetector::new(&workspace_root),
             security: SecurityManager::new(),
             cache: CacheManager::new(workspace_path),
             config_manager: ConfigManager::new(workspace_path),
+            store: PackageStore::new()?,
+            root: workspace_path.to_path_buf(),
             config_path: config_path_buf,
             lock_path,
-        }
+        })
+    }
+
+    /// Limits how many packages are downloaded and extracted at once.
+    pub fn set_jobs(&mut self, jobs: usize) {
+        self.downloader.set_jobs(jobs);
     }
 
+    /** Installs `repo` together with the dependencies its manifest declares
+     *
+     * # Behaviour
+     * - Packages already in the project and the dependencies of installed
+     *   packages keep constraining versions shared with the new package
+     * - Dependencies are installed first and recorded in the lock file, but
+     *   only `repo` is added to `[packages]`
+     * - Nothing in the project changes unless every package was fetched
+     */
     pub async fn install_package(
         &mut self,
         repo: &str,
         version_spec: Option<&str>,
         target: Option<PackageTarget>,
+    ) -> Result<()> {
+        self.resolve_and_install(repo, version_spec, target, false)
+            .await
+    }
+
+    /// Resolves and installs `repo`. With `upgrade`, its locked version is
+    /// ignored while resolving, but its files are only replaced once the
+    /// newly resolved version has been fetched.
+    async fn resolve_and_install(
+        &mut self,
+        repo: &str,
+        version_spec: Option<&str>,
+        target: Option<PackageTarget>,
+        upgrade: bool,
     ) -> Result<()> {
         let spinner = self.create_spinner("Installing package...");
 
         spinner.set_message("Checking lock file...");
         let mut lock = PackageLock::load_from_file(&self.lock_path).await?;
+        let config = BuildConfig::from_file(self.config_path.to_string_lossy().as_ref()).await?;
+        let previous = if upgrade {
+            lock.remove_package(repo)
+        } else {
+            None
+        };
+
+        let root = Requirement::new(
+            repo,
+            version_spec.unwrap_or("*"),
+            target,
+            Requirer::CommandLine,
+        );
+        let constraints = Self::project_requirements(&config)
+            .into_iter()
+            .filter(|requirement| requirement.repo != repo)
+            .chain(Self::installed_requirements(&lock, repo))
+            .collect();
+
+        spinner.set_message(format!("Resolving dependencies of {}", repo));
+        let plan = DependencyResolver::new(&self.downloader, &lock)
+            .resolve(vec![root], constraints)
+            .await?;
+
+        let Some(resolved) = plan.iter().find(|package| package.repo == repo).cloned() else {
+            return Err(OpenCliError::NotFound(
+                format!("Package {} could not be resolved", repo).into(),
+            ));
+        };
 
-        if lock.is_package_installed(repo) {
-            let installed_version = lock.get_installed_version(repo).unwrap();
+        if plan.iter().all(|package| package.release.is_none()) {
             spinner.finish_with_message(format!(
                 "Package {} {} already installed",
-                repo, installed_version
+                repo, resolved.tag
             ));
-            println!(
-                "Package {} {} is already installed",
-                repo, installed_version
-            );
+            println!("Package {} {} is already installed", repo, resolved.tag);
             return Ok(());
         }
 
-        let constraint = if let Some(spec) = version_spec {
-            VersionConstraint::parse(spec)?
-        } else {
-            VersionConstraint::parse("*")?
+        for package in &plan {
+            Self::announce_dependency(package);
+        }
+
+        let downloads = plan.iter().filter(|package| package.release.is_some());
+        spinner.set_message(format!("Downloading {} packages...", downloads.count()));
+        let fetches = plan
+            .iter()
+            .map(|package| self.fetch_resolved(package).boxed_local());
+        let fetched = self
+            .fetch_all(fetches)
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>>>()?;
+
+        // Put the old version back so installing the new one replaces its files
+        if let Some(previous) = previous {
+            lock.installed.insert(repo.into(), previous);
+        }
+
+        let mut result = Ok(());
+        for (package, package_files) in plan.iter().zip(fetched) {
+            result = self
+                .install_resolved(package, package_files, &mut lock, &spinner)
+                .await;
+            if result.is_err() {
+                break;
+            }
+        }
+        self.save_lock(&lock).await?;
+        result?;
+
+        spinner.set_message("Updating configuration...");
+        self.update_config(repo, &resolved.tag, resolved.target.clone())
+            .await?;
+
+        spinner.set_message("Updating config.json...");
+        self.config_manager
+            .update_legacy_plugins(&self.lock_path)
+            .await?;
+
+        spinner.finish_with_message(format!("Successfully installed {} {}", repo, resolved.tag));
+        log::info!("Package installed: {} {}", repo, resolved.tag);
+
+        Ok(())
+    }
+
+    /// Installs `name` from `source` and adds it to `[packages]`.
+    pub async fn install_package_source(
+        &mut self,
+        name: &str,
+        source: PackageSource,
+        target: Option<PackageTarget>,
+    ) -> Result<()> {
+        let spinner = self.create_spinner(format!("Installing {}...", name));
+        let mut lock = PackageLock::load_from_file(&self.lock_path).await?;
+
+        let package_files = self.fetch_source(name, &source, target.as_ref()).await?;
+        self.install_source(
+            name,
+            &source,
+            target.clone(),
+            package_files,
+            &mut lock,
+            &spinner,
+        )
+        .await?;
+        self.save_lock(&lock).await?;
+
+        spinner.set_message("Updating configuration...");
+        self.save_package_spec(name, PackageSpec::Source { source, target })
+            .await?;
+
+        spinner.set_message("Updating config.json...");
+        self.config_manager
+            .update_legacy_plugins(&self.lock_path)
+            .await?;
+
+        let version = lock.get_installed_version(name).unwrap_or_default();
+        spinner.finish_with_message(format!("Successfully installed {} {}", name, version));
+
+        Ok(())
+    }
+
+    /** Installs every package of `opencli.toml` and its dependencies
+     *
+     * # Behaviour
+     * - All versions are resolved before anything is downloaded
+     * - Packages are then downloaded and extracted concurrently, up to the
+     *   job limit, each with its own progress line
+     * - Finally files are placed and the lock file is written in plan
+     *   order, so the result does not depend on which download finished
+     *   first; a package that fails is reported and skipped
+     */
+    pub async fn install_all_packages(&mut self) -> Result<()> {
+        let config = BuildConfig::from_file(self.config_path.to_string_lossy().as_ref()).await?;
+
+        let roots = Self::project_requirements(&config);
+        if roots.is_empty() {
+            println!("No packages defined in configuration");
+            return Ok(());
+        }
+
+        let (sources, roots): (Vec<_>, Vec<_>) = roots
+            .into_iter()
+            .partition(|requirement| requirement.source.is_some());
+
+        let spinner = self.create_spinner("Resolving dependencies...");
+        let mut lock = PackageLock::load_from_file(&self.lock_path).await?;
+        let plan = DependencyResolver::new(&self.downloader, &lock)
+            .resolve(roots, Vec::new())
+            .await?;
+
+        for requirement in &sources {
+            if let Some(source) = &requirement.source {
+                println!("Installing package: {} = {}", requirement.repo, source);
+            }
+        }
+        for package in &plan {
+            if package.release.is_some() && !package.is_dependency() {
+                println!("Installing package: {} = {}", package.repo, package.tag);
+            }
+            Self::announce_dependency(package);
+        }
+
+        let downloads = plan.iter().filter(|package| package.release.is_some());
+        spinner.set_message(format!(
+            "Downloading {} packages...",
+            downloads.count() + sources.len()
+        ));
+        let source_fetches = sources.iter().filter_map(|requirement| {
+            let source = requirement.source.as_ref()?;
+            let fetch = self.fetch_source(&requirement.repo, source, requirement.target.as_ref());
+            Some(async move { fetch.await.map(Some) }.boxed_local())
+        });
+        let plan_fetches = plan
+            .iter()
+            .map(|package| self.fetch_resolved(package).boxed_local());
+        let mut fetched = self
+            .fetch_all(source_fetches.chain(plan_fetches))
+            .await
+            .into_iter();
+
+        for requirement in &sources {
+            let Some(source) = &requirement.source else {
+                continue;
+            };
+            let result = match fetched.next() {
+                Some(Ok(Some(package_files))) => {
+                    self.install_source(
+                        &requirement.repo,
+                        source,
+                        requirement.target.clone(),
+                        package_files,
+                        &mut lock,
+                        &spinner,
+                    )
+                    .await
+                }
+                Some(Err(e)) => Err(e),
+                _ => continue,
+            };
+            if let Err(e) = result {
+                eprintln!("Failed to install {}: {}", requirement.repo, e);
+                log::error!("Package installation failed: {} - {}", requirement.repo, e);
+            }
+        }
+
+        for package in &plan {
+            let result = match fetched.next() {
+                Some(Ok(package_files)) => {
+                    self.install_resolved(package, package_files, &mut lock, &spinner)
+                        .await
+                }
+                Some(Err(e)) => Err(e),
+                None => continue,
+            };
+            if let Err(e) = result {
+                eprintln!("Failed to install {}: {}", package.repo, e);
+                log::error!("Package installation failed: {} - {}", package.repo, e);
+            }
+        }
+
+        self.save_lock(&lock).await?;
+
+        spinner.set_message("Updating config.json...");
+        self.config_manager
+            .update_legacy_plugins(&self.lock_path)
+            .await?;
+
+        spinner.finish_with_message(format!(
+            "{} packages up to date",
+            plan.len() + sources.len()
+        ));
+
+        Ok(())
+    }
+
+    /** Installs exactly the versions and artifacts recorded in `opencli.lock`
+     *
+     * # Behaviour
+     * - Fails without changing anything when the lock file is missing or
+     *   out of sync with `opencli.toml`
+     * - Packages whose locked files are all present and unchanged are left
+     *   alone; a missing file or one that no longer matches its checksum in
+     *   the lock reinstalls the package
+     * - With `frozen`, only the recorded artifacts are downloaded; release
+     *   lists and manifests are never fetched
+     */
+    pub async fn install_locked_packages(&mut self, frozen: bool) -> Result<()> {
+        if !self.lock_path.exists() {
+            return Err(OpenCliError::NotFound(
+                format!(
+                    "{} not found, run `opencli package install` once to create it",
+                    self.lock_path.display()
+                )
+                .into(),
+            ));
+        }
+
+        let config = BuildConfig::from_file(self.config_path.to_string_lossy().as_ref()).await?;
+        let mut lock = PackageLock::load_from_file(&self.lock_path).await?;
+
+        let problems = lock.out_of_sync(Self::project_requirements(&config));
+        if !problems.is_empty() {
+            return Err(OpenCliError::Config(
+                format!(
+                    "opencli.lock is out of sync with opencli.toml:\n  {}\nRun `opencli package install` without --locked to update it",
+                    problems.join("\n  ")
+                )
+                .into(),
+            ));
+        }
+
+        let order: Vec<SmolStr> = lock
+            .install_order()
+            .into_iter()
+            .map(SmolStr::from)
+            .collect();
+        let spinner = self.create_spinner("Installing locked packages...");
+
+        let mut pending = Vec::new();
+        for repo in &order {
+            let Some(package) = lock.get_package(repo).cloned() else {
+                continue;
+            };
+
+            if self.is_unchanged(&package).await {
+                log::info!(
+                    "Locked package {} {} is present and unchanged",
+                    repo,
+                    package.version
+                );
+                continue;
+            }
+
+            println!("Installing locked package: {} = {}", repo, package.version);
+            pending.push((repo.clone(), package));
+        }
+        let installed = pending.len();
+
+        let fetches = pending
+            .iter()
+            .map(|(repo, package)| self.fetch_locked(repo, package, frozen).boxed_local());
+        let fetched = self
+            .fetch_all(fetches)
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut result = Ok(());
+        for ((repo, package), package_files) in pending.iter().zip(fetched) {
+            result = self
+                .install_locked(repo, package, package_files, &mut lock, &spinner)
+                .await;
+            if result.is_err() {
+                break;
+            }
+        }
+        self.save_lock(&lock).await?;
+        result?;
+
+        spinner.set_message("Updating config.json...");
+        self.config_manager
+            .update_legacy_plugins(&self.lock_path)
+            .await?;
+
+        spinner.finish_with_message(format!(
+            "{} locked packages installed, {} already present",
+            installed,
+            order.len() - installed
+        ));
+
+        Ok(())
+    }
+
+    /** True when every file of `package` exists with its locked checksum
+     *
+     * # Notes
+     * - Entries from before checksums were recorded are only checked for
+     *   existence, since there is nothing to compare against
+     */
+    async fn is_unchanged(&self, package: &InstalledPackage) -> bool {
+        if package.files.is_empty() {
+            return false;
+        }
+
+        for file in &package.files {
+            let Ok(path) = self.project_file(file) else {
+                return false;
+            };
+            if package.checksums.is_empty() {
+                if !path.exists() {
+                    return false;
+                }
+                continue;
+            }
+
+            let actual = sha256_file(&path)
+                .await
+                .map(|digest| format!("{:x}", digest))
+                .ok();
+            if actual.as_deref() != package.checksums.get(file).map(SmolStr::as_str) {
+                log::warn!("{} was modified or removed since it was installed", file);
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Downloads one locked package at its locked version.
+    async fn fetch_locked(
+        &self,
+        repo: &str,
+        package: &InstalledPackage,
+        frozen: bool,
+    ) -> Result<PackageFiles> {
+        let temp_dir = self.get_temp_dir(repo)?;
+        let spinner = self.create_spinner(format!("Downloading {} {}...", repo, package.version));
+
+        let package_files = match &package.source {
+            // Local paths have no artifact to pin, they are read again
+            Some(source @ PackageSource::Path { .. }) => {
+                self.downloader
+                    .download_source(
+                        repo,
+                        source,
+                        self.project_dir(),
+                        &temp_dir,
+                        package.target.as_ref(),
+                    )
+                    .await?
+            }
+            _ if !package.artifacts.is_empty() => {
+                self.downloader
+                    .download_locked_package(
+                        repo,
+                        &package.version,
+                        &package.artifacts,
+                        &temp_dir,
+                        package.target.as_ref(),
+                    )
+                    .await?
+            }
+            _ if frozen => {
+                return Err(OpenCliError::Config(
+                    format!(
+                        "opencli.lock records no artifacts for {} {}; run `opencli package install --locked` once to record them",
+                        repo, package.version
+                    )
+                    .into(),
+                ));
+            }
+            Some(source) => {
+                self.downloader
+                    .download_source(
+                        repo,
+                        source,
+                        self.project_dir(),
+                        &temp_dir,
+                        package.target.as_ref(),
+                    )
+                    .await?
+            }
+            None => {
+                let exact = VersionConstraint::parse(&format!("={}", package.version))?;
+                let release = self.downloader.find_matching_version(repo, &exact).await?;
+                self.downloader
+                    .download_package(repo, &release, &temp_dir, package.target.as_ref())
+                    .await?
+            }
         };
 
-        spinner.set_message(format!("Finding version for {}", repo));
-        let release = self
-            .downloader
-            .find_matching_version(repo, &constraint)
+        spinner.finish_and_clear();
+        Ok(package_files)
+    }
+
+    /// Reinstalls one locked package from its fetched files.
+    async fn install_locked(
+        &mut self,
+        repo: &str,
+        package: &InstalledPackage,
+        package_files: PackageFiles,
+        lock: &mut PackageLock,
+        spinner: &ProgressBar,
+    ) -> Result<()> {
+        let temp_dir = self.get_temp_dir(repo)?;
+        self.remove_package_files_from_lock(&package.files).await?;
+
+        spinner.set_message(format!("Installing {} {}...", repo, package.version));
+        let installed_files = self
+            .install_package_files(repo, &package_files, package.target.as_ref())
             .await?;
+        let combined_hash = self.compute_package_hash(&installed_files).await?;
 
-        spinner.set_message("Downloading package files...");
+        let file_names: Vec<SmolStr> = installed_files
+            .iter()
+            .map(|path| self.recorded_path(path))
+            .collect();
+
+        let checksums = self.file_checksums(&installed_files).await?;
+        let locked = lock.add_package(
+            repo.into(),
+            package.version.clone(),
+            package.target.clone(),
+            combined_hash.into(),
+            file_names,
+            package.dependencies.clone(),
+        );
+        locked.source = package.source.clone();
+        locked.checksums = checksums;
+        locked.artifacts = package_files.artifacts;
+
+        self.cleanup_temp_dir(&temp_dir).await?;
+        log::info!("Locked package installed: {} {}", repo, package.version);
+
+        Ok(())
+    }
+
+    /// Prints why a dependency that is about to be installed is needed.
+    fn announce_dependency(package: &ResolvedPackage) {
+        if package.release.is_none() || !package.is_dependency() {
+            return;
+        }
+
+        let required_by: Vec<String> = package
+            .required_by
+            .iter()
+            .map(|requirer| requirer.to_string())
+            .collect();
+        println!(
+            "Installing dependency {} {} (required by {})",
+            package.repo,
+            package.tag,
+            required_by.join(", ")
+        );
+    }
+
+    /// Downloads a package of a resolved plan. Packages kept at their
+    /// locked version have nothing to download.
+    async fn fetch_resolved(&self, package: &ResolvedPackage) -> Result<Option<PackageFiles>> {
+        let Some(release) = &package.release else {
+            return Ok(None);
+        };
+
+        let repo = package.repo.as_str();
+        let spinner = self.create_spinner(format!("Downloading {} {}...", repo, package.tag));
         let temp_dir = self.get_temp_dir(repo)?;
         let package_files = self
             .downloader
-            .download_package(repo, &release, &temp_dir, target.as_ref())
+            .download_package(repo, release, &temp_dir, package.target.as_ref())
             .await?;
 
-        spinner.set_message("Installing package files...");
+        spinner.finish_and_clear();
+        Ok(Some(package_files))
+    }
+
+    /// Installs one fetched package of a resolved plan, replacing an older version.
+    async fn install_resolved(
+        &mut self,
+        package: &ResolvedPackage,
+        package_files: Option<PackageFiles>,
+        lock: &mut PackageLock,
+        spinner: &ProgressBar,
+    ) -> Result<()> {
+        let repo = package.repo.as_str();
+
+        let Some(package_files) = package_files else {
+            // Kept at the locked version; only its declared dependencies may be new
+            if let Some(installed) = lock.installed.get_mut(repo) {
+                installed.dependencies = package.dependencies.clone();
+            }
+            return Ok(());
+        };
+        let temp_dir = self.get_temp_dir(repo)?;
+
+        if let Some(previous) = lock.remove_package(repo) {
+            spinner.set_message(format!("Removing {} {}...", repo, previous.version));
+            self.remove_package_files_from_lock(&previous.files).await?;
+        }
+
+        spinner.set_message(format!("Installing {} {}...", repo, package.tag));
         let installed_files = self
-            .install_package_files(repo, &package_files, target.as_ref())
+            .install_package_files(repo, &package_files, package.target.as_ref())
             .await?;
 
         spinner.set_message("Computing package hash...");
@@ -100,58 +647,174 @@ impl PackageManager {
         spinner.set_message("Updating lock file...");
         let file_names: Vec<SmolStr> = installed_files
             .iter()
-            .filter_map(|p| p.to_str().map(|s| s.into()))
+            .map(|path| self.recorded_path(path))
             .collect();
 
-        lock.add_package(
+        let checksums = self.file_checksums(&installed_files).await?;
+        let locked = lock.add_package(
             repo.into(),
-            release.tag_name.clone().into(),
-            target.clone(),
+            package.tag.clone(),
+            package.target.clone(),
             combined_hash.into(),
             file_names,
+            package.dependencies.clone(),
         );
-        lock.save_to_file(&self.lock_path).await?;
+        locked.checksums = checksums;
+        locked.artifacts = package_files.artifacts;
 
-        spinner.set_message("Updating configuration...");
-        self.update_config(repo, &release.tag_name, target).await?;
+        self.cleanup_temp_dir(&temp_dir).await?;
+        log::info!("Package installed: {} {}", repo, package.tag);
 
-        spinner.set_message("Updating config.json...");
-        self.config_manager
-            .update_legacy_plugins(&self.lock_path)
+        Ok(())
+    }
+
+    /// Fetches a package from a git host, a local path or a URL.
+    async fn fetch_source(
+        &self,
+        name: &str,
+        source: &PackageSource,
+        target: Option<&PackageTarget>,
+    ) -> Result<PackageFiles> {
+        let spinner = self.create_spinner(format!("Fetching {} from {}...", name, source));
+        let temp_dir = self.get_temp_dir(name)?;
+        let package_files = self
+            .downloader
+            .download_source(name, source, self.project_dir(), &temp_dir, target)
             .await?;
 
-        self.cleanup_temp_dir(&temp_dir).await?;
+        spinner.finish_and_clear();
+        Ok(package_files)
+    }
 
-        spinner.finish_with_message(format!(
-            "Successfully installed {} {}",
-            repo, release.tag_name
-        ));
-        log::info!("Package installed: {} {}", repo, release.tag_name);
+    /// Installs a package fetched from a git host, a local path or a URL.
+    async fn install_source(
+        &mut self,
+        name: &str,
+        source: &PackageSource,
+        target: Option<PackageTarget>,
+        package_files: PackageFiles,
+        lock: &mut PackageLock,
+        spinner: &ProgressBar,
+    ) -> Result<()> {
+        let temp_dir = self.get_temp_dir(name)?;
+
+        if let Some(previous) = lock.remove_package(name) {
+            spinner.set_message(format!("Removing {} {}...", name, previous.version));
+            self.remove_package_files_from_lock(&previous.files).await?;
+        }
+
+        spinner.set_message(format!("Installing {}...", name));
+        let installed_files = self
+            .install_package_files(name, &package_files, target.as_ref())
+            .await?;
+
+        spinner.set_message("Computing package hash...");
+        let combined_hash = self.compute_package_hash(&installed_files).await?;
+        log::info!("Package {} hash: {}", name, combined_hash);
+
+        spinner.set_message("Updating lock file...");
+        let file_names: Vec<SmolStr> = installed_files
+            .iter()
+            .map(|path| self.recorded_path(path))
+            .collect();
+
+        let checksums = self.file_checksums(&installed_files).await?;
+        let version = package_files
+            .commit
+            .clone()
+            .unwrap_or_else(|| source.version_label());
+        let locked = lock.add_package(
+            name.into(),
+            version,
+            target,
+            combined_hash.into(),
+            file_names,
+            BTreeMap::new(),
+        );
+        locked.source = Some(source.clone());
+        locked.checksums = checksums;
+        locked.artifacts = package_files.artifacts;
+
+        self.cleanup_temp_dir(&temp_dir).await?;
+        log::info!("Package installed: {} ({})", name, source);
 
         Ok(())
     }
 
-    pub async fn install_all_packages(&mut self) -> Result<()> {
-        let config = BuildConfig::from_file(self.config_path.to_string_lossy().as_ref()).await?;
+    /** Runs `fetches`, at most the job limit at once
+     *
+     * # Returns
+     * - The result of each fetch, in the order of `fetches`
+     */
+    async fn fetch_all<'a, T>(
+        &self,
+        fetches: impl Iterator<Item = LocalBoxFuture<'a, Result<T>>>,
+    ) -> Vec<Result<T>> {
+        stream::iter(fetches)
+            .buffered(self.downloader.jobs())
+            .collect()
+            .await
+    }
 
-        if let Some(packages) = config.get_packages() {
-            for (repo, spec) in packages {
-                let version = spec.version();
-                let target = spec.target().cloned();
-
-                println!("Installing package: {} = {}", repo, version);
-                if let Err(e) = self.install_package(repo, Some(version), target).await {
-                    eprintln!("Failed to install {}: {}", repo, e);
-                    log::error!("Package installation failed: {} - {}", repo, e);
-                }
-            }
-        } else {
-            println!("No packages defined in configuration");
+    /// Saves the lock file and registers it with the package store, so
+    /// `opencli cache gc` keeps what it references.
+    async fn save_lock(&self, lock: &PackageLock) -> Result<()> {
+        lock.save_to_file(&self.lock_path).await?;
+        if let Err(e) = self.store.remember_lock(&self.lock_path).await {
+            log::warn!(
+                "Failed to register {} with the package store: {}",
+                self.lock_path.display(),
+                e
+            );
         }
-
         Ok(())
     }
 
+    /// Requirements of `[packages]` and of the project's own `[package]` manifest.
+    fn project_requirements(config: &BuildConfig) -> Vec<Requirement> {
+        let declared = config.get_packages().into_iter().flatten().chain(
+            config
+                .package
+                .iter()
+                .flat_map(|manifest| manifest.dependencies()),
+        );
+
+        let mut requirements: Vec<Requirement> = declared
+            .map(|(repo, spec)| Requirement {
+                source: spec.source().cloned(),
+                ..Requirement::new(
+                    repo,
+                    spec.version(),
+                    spec.target().cloned(),
+                    Requirer::Project,
+                )
+            })
+            .collect();
+        requirements.sort_by(|a, b| a.repo.cmp(&b.repo));
+        requirements
+    }
+
+    /// Dependency constraints recorded for installed packages other than `except`.
+    fn installed_requirements(lock: &PackageLock, except: &str) -> Vec<Requirement> {
+        lock.list_packages()
+            .into_iter()
+            .filter(|(name, _)| *name != except)
+            .flat_map(|(name, package)| {
+                package.dependencies.iter().map(move |(repo, constraint)| {
+                    Requirement::new(
+                        repo,
+                        constraint,
+                        None,
+                        Requirer::Package {
+                            repo: name.into(),
+                            version: package.version.clone(),
+                        },
+                    )
+                })
+            })
+            .collect()
+    }
+
     pub async fn remove_package(&mut self, repo: &str) -> Result<()> {
         let spinner = self.create_spinner(format!("Removing package {}...", repo));
 
@@ -193,7 +856,7 @@ impl PackageManager {
 
         spinner.set_message("Updating lock file...");
         lock.remove_package(repo);
-        lock.save_to_file(&self.lock_path).await?;
+        self.save_lock(&lock).await?;
 
         spinner.set_message("Updating configuration...");
         self.remove_from_config(repo).await?;
@@ -214,15 +877,7 @@ impl PackageManager {
                 let target_info = package
                     .target
                     .as_ref()
-                    .map(|t| {
-                        format!(
-                            " ({})",
-                            match t {
-                                PackageTarget::Components => "components",
-                                PackageTarget::Plugins => "plugins",
-                            }
-                        )
-                    })
+                    .map(|t| format!(" ({})", t.as_str()))
                     .unwrap_or_default();
 
                 println!("  {} = {}{}", repo, package.version, target_info);
@@ -248,46 +903,63 @@ impl PackageManager {
 
         println!("Checking package integrity...");
         let mut all_valid = true;
+        let mut unverified = 0;
 
         for (repo, package) in packages {
             print!("Checking {} {}... ", repo, package.version);
 
-            let mut files_exist = true;
-            let mut valid_files = Vec::new();
-
-            for file_path_str in &package.files {
-                let file_path = std::path::Path::new(file_path_str.as_str());
-                if file_path.exists() {
-                    valid_files.push(file_path.to_path_buf());
-                } else {
-                    files_exist = false;
-                    break;
+            let missing: Vec<&SmolStr> = package
+                .files
+                .iter()
+                .filter(|file| self.project_file(file).map_or(true, |path| !path.exists()))
+                .collect();
+            if !missing.is_empty() {
+                println!("Missing files");
+                for file in missing {
+                    println!("  Missing: {}", file);
                 }
+                all_valid = false;
+                continue;
             }
 
-            if !files_exist {
-                println!("Missing files");
-                all_valid = false;
+            if package.checksums.is_empty() {
+                println!("No checksums recorded");
+                unverified += 1;
                 continue;
             }
 
-            match self.compute_package_hash(&valid_files).await {
-                Ok(computed_hash) => {
-                    if computed_hash == package.hash.as_str() {
-                        println!("Valid");
-                    } else {
-                        println!("Hash mismatch");
-                        all_valid = false;
-                    }
+            let mut modified = Vec::new();
+            for file in &package.files {
+                let actual = match self.project_file(file) {
+                    Ok(path) => sha256_file(&path)
+                        .await
+                        .map(|digest| format!("{:x}", digest))
+                        .ok(),
+                    Err(_) => None,
+                };
+                if actual.as_deref() != package.checksums.get(file).map(SmolStr::as_str) {
+                    modified.push(file);
                 }
-                Err(_) => {
-                    println!("Hash computation failed");
-                    all_valid = false;
+            }
+
+            if modified.is_empty() {
+                println!("Valid");
+            } else {
+                println!("Checksum mismatch");
+                for file in modified {
+                    println!("  Modified: {}", file);
                 }
+                all_valid = false;
             }
         }
 
-        if all_valid {
+        if all_valid && unverified > 0 {
+            println!(
+                "\nNo problems found, but {} packages have no checksums to verify",
+                unverified
+            );
+            println!("Reinstall them to record checksums in opencli.lock");
+        } else if all_valid {
             println!("\nAll packages are valid");
         } else {
             println!("\nSome packages have issues");
@@ -297,19 +969,52 @@ impl PackageManager {
         Ok(())
     }
 
-    pub async fn update_package(&mut self, repo: &str) -> Result<()> {
+    /// Reports installed packages with newer releases, as a table or as JSON.
+    pub async fn outdated_packages(&self, json: bool) -> Result<()> {
         let config = BuildConfig::from_file(self.config_path.to_string_lossy().as_ref()).await?;
         let lock = PackageLock::load_from_file(&self.lock_path).await?;
 
+        if lock.installed.is_empty() && !json {
+            println!("No packages installed");
+            return Ok(());
+        }
+
+        let report = outdated::check_outdated(&self.downloader, &config, &lock).await;
+
+        if json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            outdated::print_table(&report);
+        }
+
+        Ok(())
+    }
+
+    pub async fn update_package(&mut self, repo: &str) -> Result<()> {
+        let config = BuildConfig::from_file(self.config_path.to_string_lossy().as_ref()).await?;
+        let mut lock = PackageLock::load_from_file(&self.lock_path).await?;
+
         if let Some(packages) = config.get_packages() {
             if let Some(spec) = packages.get(repo) {
-                let _constraint = VersionConstraint::parse(spec.version())?;
-                let target = spec.target().cloned();
-
-                if let Some(package) = lock.get_package(repo) {
-                    self.remove_package_files_from_lock(&package.files).await?;
+                if let Some(source) = spec.source() {
+                    let spinner = self.create_spinner(format!("Updating {}...", repo));
+                    let package_files = self.fetch_source(repo, source, spec.target()).await?;
+                    self.install_source(
+                        repo,
+                        source,
+                        spec.target().cloned(),
+                        package_files,
+                        &mut lock,
+                        &spinner,
+                    )
+                    .await?;
+                    self.save_lock(&lock).await?;
+                    spinner.finish_with_message(format!("Successfully updated {}", repo));
+                    return Ok(());
                 }
-                self.install_package(repo, Some(spec.version()), target)
+
+                VersionConstraint::parse(spec.version())?;
+                self.resolve_and_install(repo, Some(spec.version()), spec.target().cloned(), true)
                     .await?;
             } else {
                 return Err(OpenCliError::NotFound(
@@ -334,11 +1039,15 @@ impl PackageManager {
         let mut installed_files = Vec::new();
         let include_paths = self.get_include_paths().await?;
         let workspace_info = self.workspace.get_workspace_info();
+        let link_mode = LinkMode::current();
 
-        for include_file in &package_files.includes {
+        for (include_file, relative) in package_files.include_layout() {
             if let Some(include_path) = include_paths.first() {
-                let dest_path = include_path.join(include_file.file_name().unwrap());
-                fs::copy(include_file, &dest_path).await?;
+                let dest_path = self.root.join(include_path).join(relative);
+                if let Some(parent) = dest_path.parent() {
+                    fs::create_dir_all(parent).await?;
+                }
+                place_file(&include_file, &dest_path, link_mode).await?;
                 installed_files.push(dest_path.clone());
                 log::info!(
                     "Copied include: {} -> {}",
@@ -350,7 +1059,7 @@ impl PackageManager {
 
         for binary_file in &package_files.root_binaries {
             let dest_path = workspace_info.root.join(binary_file.file_name().unwrap());
-            fs::copy(binary_file, &dest_path).await?;
+            place_file(binary_file, &dest_path, link_mode).await?;
             installed_files.push(dest_path.clone());
             log::info!(
                 "Copied root binary: {} -> {}",
@@ -377,7 +1086,7 @@ impl PackageManager {
                     let dest_path = workspace_info
                         .components
                         .join(binary_file.file_name().unwrap());
-                    fs::copy(binary_file, &dest_path).await?;
+                    place_file(binary_file, &dest_path, link_mode).await?;
                     installed_files.push(dest_path.clone());
                     log::info!(
                         "Copied component binary: {} -> {}",
@@ -391,7 +1100,7 @@ impl PackageManager {
                     let dest_path = workspace_info
                         .plugins
                         .join(binary_file.file_name().unwrap());
-                    fs::copy(binary_file, &dest_path).await?;
+                    place_file(binary_file, &dest_path, link_mode).await?;
                     installed_files.push(dest_path.clone());
                     log::info!(
                         "Copied plugin binary: {} -> {}",
@@ -404,7 +1113,7 @@ impl PackageManager {
                 for binary_file in component_files {
                     let target_folder = self.detect_binary_target(binary_file).await?;
                     let dest_path = target_folder.join(binary_file.file_name().unwrap());
-                    fs::copy(binary_file, &dest_path).await?;
+                    place_file(binary_file, &dest_path, link_mode).await?;
                     installed_files.push(dest_path.clone());
                     log::info!(
                         "Copied auto-detected binary: {} -> {}",
@@ -423,27 +1132,56 @@ impl PackageManager {
     async fn compute_package_hash(&self, installed_files: &[PathBuf]) -> Result<String> {
         use sha2::{Digest, Sha256};
 
-        let mut combined_content = Vec::new();
+        let mut hasher = Sha256::new();
 
         for file_path in installed_files {
             if file_path.exists() {
-                let content = fs::read(file_path).await?;
-                combined_content.extend_from_slice(&content);
-                combined_content.extend_from_slice(file_path.to_string_lossy().as_bytes());
+                sha256_update_from_file(&mut hasher, file_path).await?;
+                hasher.update(file_path.to_string_lossy().as_bytes());
             }
         }
 
-        let mut hasher = Sha256::new();
-        hasher.update(&combined_content);
         let combined_sha = hasher.finalize();
 
         let argon2_hash = self.security.hash_file_content(&combined_sha).await?;
         Ok(argon2_hash)
     }
 
+    /// SHA-256 of each installed file, keyed like the lock file's `files`.
+    async fn file_checksums(
+        &self,
+        installed_files: &[PathBuf],
+    ) -> Result<BTreeMap<SmolStr, SmolStr>> {
+        let mut checksums = BTreeMap::new();
+        for file_path in installed_files {
+            let digest = sha256_file(file_path).await?;
+            checksums.insert(
+                self.recorded_path(file_path),
+                format!("{:x}", digest).into(),
+            );
+        }
+        Ok(checksums)
+    }
+
+    /** Removes the files a lock file entry lists
+     *
+     * # Notes
+     * - Every path is checked first; nothing is removed when one of them
+     *   is absolute or leaves the project with `..`
+     */
     async fn remove_package_files_from_lock(&self, files: &[smol_str::SmolStr]) -> Result<()> {
-        for file_path in files {
-            let path = std::path::Path::new(file_path.as_str());
+        let paths = files
+            .iter()
+            .map(|file| self.project_file(file))
+            .collect::<Result<Vec<_>>>()?;
+        let include_paths: Vec<PathBuf> = self
+            .get_include_paths()
+            .await?
+            .iter()
+            .map(|include_path| self.root.join(include_path))
+            .collect();
+
+        for path in &paths {
             if path.exists() {
                 fs::remove_file(path).await.map_err(|e| {
                     log::warn!("Failed to remove file {}: {}", path.display(), e);
@@ -451,6 +1189,7 @@ impl PackageManager {
                 })?;
                 log::info!("Removed file: {}", path.display());
                 println!("Removed: {}", path.display());
+                Self::remove_empty_include_dirs(path, &include_paths).await;
             } else {
                 log::warn!("File not found (already removed?): {}", path.display());
             }
@@ -458,21 +1197,70 @@ impl PackageManager {
         Ok(())
     }
 
+    /// How an installed file is listed in the lock file: relative to the
+    /// project root.
+    fn recorded_path(&self, path: &Path) -> SmolStr {
+        path.strip_prefix(&self.root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .as_ref()
+            .into()
+    }
+
+    /// Location of a file listed in the lock file, which must be a relative
+    /// path that stays inside the project.
+    fn project_file(&self, recorded: &str) -> Result<PathBuf> {
+        let path = Path::new(recorded);
+        let inside = path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+        if recorded.is_empty() || !inside {
+            return Err(OpenCliError::Config(
+                format!(
+                    "opencli.lock lists '{}', which is not a path inside the project; refusing to touch it",
+                    recorded
+                )
+                .into(),
+            ));
+        }
+
+        Ok(self.root.join(path))
+    }
+
+    /// Removes the folders below an include path that `file` leaves empty.
+    async fn remove_empty_include_dirs(file: &Path, include_paths: &[PathBuf]) {
+        let mut dir = file.parent();
+        while let Some(current) = dir {
+            let below_include_path = include_paths
+                .iter()
+                .any(|include_path| current.starts_with(include_path) && current != include_path);
+            if !below_include_path || fs::remove_dir(current).await.is_err() {
+                break;
+            }
+            log::info!("Removed empty folder: {}", current.display());
+            dir = current.parent();
+        }
+    }
+
     async fn update_config(
         &self,
         repo: &str,
         version: &str,
         target: Option<PackageTarget>,
     ) -> Result<()> {
-        let mut config =
-            BuildConfig::from_file(self.config_path.to_string_lossy().as_ref()).await?;
-
         let spec = if let Some(target) = target {
             PackageSpec::new_detailed(version, Some(target))
         } else {
             PackageSpec::new_simple(version)
         };
 
+        self.save_package_spec(repo, spec).await
+    }
+
+    async fn save_package_spec(&self, repo: &str, spec: PackageSpec) -> Result<()> {
+        let mut config =
+            BuildConfig::from_file(self.config_path.to_string_lossy().as_ref()).await?;
+
         config.add_package(repo.into(), spec);
         config
             .save_to_file(self.config_path.to_string_lossy().as_ref())
@@ -513,6 +1301,11 @@ impl PackageManager {
         }
     }
 
+    /// Directory `path` sources in `opencli.toml` are relative to.
+    fn project_dir(&self) -> &Path {
+        self.config_path.parent().unwrap_or(Path::new("."))
+    }
+
     fn get_temp_dir(&self, repo: &str) -> Result<PathBuf> {
         let temp_name = repo.replace('/', "_");
         let temp_dir = std::env::temp_dir()
@@ -530,7 +1323,7 @@ impl PackageManager {
     }
 
     fn create_spinner(&self, message: impl Into<String>) -> ProgressBar {
-        let spinner = ProgressBar::new_spinner();
+        let spinner = self.downloader.progress().add(ProgressBar::new_spinner());
         spinner.set_style(
             ProgressStyle::default_spinner()
                 .template("{spinner:.green} {msg}")
@@ -0,0 +1,160 @@
+use crate::build::PackageSpec;
+use crate::package::{PackageSource, VersionConstraint};
+use crate::result::{OpenCliError, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use smol_str::SmolStr;
+use std::collections::BTreeMap;
+
+/// File names checked for a manifest, in order of preference.
+pub const MANIFEST_FILES: [&str; 2] = ["opencli.toml", "pawn.json"];
+
+/// Standard library packages that sampctl projects depend on, but which
+/// open.mp already ships with its compiler in `qawno/include`.
+const TOOLCHAIN_PROVIDED: [&str; 6] = [
+    "pawn-lang/samp-stdlib",
+    "pawn-lang/pawn-stdlib",
+    "sampctl/samp-stdlib",
+    "sampctl/pawn-stdlib",
+    "southclaws/samp-stdlib",
+    "openmultiplayer/omp-stdlib",
+];
+
+/// `[https://][github.com/]owner/repo[:tag|@branch|#commit]`
+static SAMPCTL_DEPENDENCY_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?:https?://)?(?:github\.com/)?([A-Za-z0-9_.-]+)/([A-Za-z0-9_.-]+?)(?:\.git)?/?(?:([:@#])(.+))?$")
+        .unwrap()
+});
+
+/** Package metadata declared by a package's own `opencli.toml`
+ *
+ * # Format
+ * ```toml
+ * [package]
+ * name = "owner/repo"
+ *
+ * [package.dependencies]
+ * "pawn-lang/YSI-Includes" = "^5.10"
+ * "katursis/Pawn.RakNet" = { version = "^1.6", target = "components" }
+ * ```
+ *
+ * # Notes
+ * - Packages without this section are read from a sampctl `pawn.json`
+ *   instead, see [`PackageManifest::from_pawn_json`]
+ * - A project may declare the section too; its dependencies are installed
+ *   together with `[packages]`
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PackageManifest {
+    pub name: Option<SmolStr>,
+    pub dependencies: Option<BTreeMap<SmolStr, PackageSpec>>,
+}
+
+/// The parts of a package's `opencli.toml` relevant for resolution.
+#[derive(Deserialize)]
+struct ManifestFile {
+    package: Option<PackageManifest>,
+}
+
+/// The parts of a sampctl `pawn.json` relevant for resolution.
+#[derive(Deserialize)]
+struct PawnJson {
+    #[serde(default)]
+    dependencies: Vec<String>,
+}
+
+impl PackageManifest {
+    /// Parses the manifest file `file_name` (one of [`MANIFEST_FILES`]).
+    pub fn parse(file_name: &str, content: &str) -> Result<Option<Self>> {
+        match file_name {
+            "opencli.toml" => Self::from_opencli_toml(content),
+            "pawn.json" => Self::from_pawn_json(content).map(Some),
+            _ => Err(OpenCliError::Config(
+                format!("Unknown manifest file: {}", file_name).into(),
+            )),
+        }
+    }
+
+    /// Reads the `[package]` section, ignoring the rest of the file.
+    pub fn from_opencli_toml(content: &str) -> Result<Option<Self>> {
+        let file: ManifestFile = toml::from_str(content)
+            .map_err(|e| OpenCliError::Config(format!("Invalid package manifest: {}", e).into()))?;
+        Ok(file.package)
+    }
+
+    /** Converts the `dependencies` of a sampctl `pawn.json`
+     *
+     * # Mapping
+     * - `owner/repo` becomes `*`
+     * - `owner/repo:tag` pins that exact version
+     * - Branch (`@branch`) and commit (`#sha`) references become
+     *   `{ rev = "..." }` sources
+     */
+    pub fn from_pawn_json(content: &str) -> Result<Self> {
+        let pawn: PawnJson = serde_json::from_str(content)?;
+
+        let mut dependencies = BTreeMap::new();
+        for dependency in &pawn.dependencies {
+            match parse_sampctl_dependency(dependency) {
+                Ok((repo, spec)) => {
+                    dependencies.insert(repo, spec);
+                }
+                Err(e) => log::warn!("Skipping pawn.json dependency {}: {}", dependency, e),
+            }
+        }
+
+        Ok(Self {
+            name: None,
+            dependencies: Some(dependencies),
+        })
+    }
+
+    /// Declared dependencies, without the ones the toolchain already provides.
+    pub fn dependencies(&self) -> Vec<(&SmolStr, &PackageSpec)> {
+        self.dependencies
+            .iter()
+            .flatten()
+            .filter(|(repo, _)| {
+                let provided = is_toolchain_provided(repo);
+                if provided {
+                    log::info!("Skipping dependency {}: provided by the toolchain", repo);
+                }
+                !provided
+            })
+            .collect()
+    }
+}
+
+pub fn is_toolchain_provided(repo: &str) -> bool {
+    let repo = repo.to_ascii_lowercase();
+    TOOLCHAIN_PROVIDED.contains(&repo.as_str())
+}
+
+/// Splits a sampctl dependency string into `owner/repo` and either a version
+/// constraint or, for `@branch` and `#commit`, a `rev` source.
+pub fn parse_sampctl_dependency(dependency: &str) -> Result<(SmolStr, PackageSpec)> {
+    let caps = SAMPCTL_DEPENDENCY_REGEX
+        .captures(dependency.trim())
+        .ok_or_else(|| {
+            OpenCliError::Config(format!("Invalid sampctl dependency: {}", dependency).into())
+        })?;
+
+    let repo = format!("{}/{}", &caps[1], &caps[2]);
+
+    let spec = match (caps.get(3).map(|m| m.as_str()), caps.get(4)) {
+        (Some(":"), Some(tag)) => {
+            VersionConstraint::parse(tag.as_str())?;
+            PackageSpec::new_simple(format!("={}", tag.as_str()))
+        }
+        (Some("@" | "#"), Some(rev)) => PackageSpec::Source {
+            source: PackageSource::Rev {
+                rev: rev.as_str().into(),
+            },
+            target: None,
+        },
+        _ => PackageSpec::new_simple("*"),
+    };
+
+    Ok((repo.into(), spec))
+}
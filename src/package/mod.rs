@@ -2,12 +2,23 @@ pub mod config_manager;
 pub mod downloader;
 pub mod lock;
 pub mod manager;
+pub mod manifest;
+pub mod outdated;
+pub mod release_cache;
+pub mod resolver;
+pub mod source;
+pub mod store;
 pub mod version;
 pub mod workspace;
 
 pub use config_manager::ConfigManager;
 pub use downloader::PackageDownloader;
-pub use lock::{InstalledPackage, PackageLock};
+pub use lock::{InstalledPackage, LockedArtifact, PackageLock};
 pub use manager::PackageManager;
+pub use manifest::PackageManifest;
+pub use release_cache::ReleaseCache;
+pub use resolver::{DependencyResolver, Requirement, Requirer, ResolvedPackage};
+pub use source::PackageSource;
+pub use store::PackageStore;
 pub use version::VersionConstraint;
 pub use workspace::WorkspaceDetector;
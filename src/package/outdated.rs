@@ -0,0 +1,212 @@
+use crate::build::BuildConfig;
+use crate::package::version::Version;
+use crate::package::{PackageDownloader, PackageLock, VersionConstraint};
+use crate::result::Result;
+use serde::Serialize;
+use smol_str::SmolStr;
+
+/// One installed package compared against its constraint and the newest release.
+#[derive(Debug, Clone, Serialize)]
+pub struct OutdatedPackage {
+    pub name: SmolStr,
+    pub current: SmolStr,
+    /// From `opencli.toml`, followed by those of the packages depending on it
+    pub constraint: Vec<SmolStr>,
+    /// Newest release the constraint allows
+    pub wanted: Option<String>,
+    /// Newest release overall
+    pub latest: Option<String>,
+    pub outdated: bool,
+    /// Whether `latest` leaves the `^`/`~` range of the current version
+    pub breaking: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/** Compares every package in the lock file with its releases
+ *
+ * # Notes
+ * - `wanted` has to satisfy the `opencli.toml` constraint and the ones
+ *   recorded for the packages requiring it, like an install would
+ * - `breaking` is judged by the first of them
+ * - A package that cannot be checked is reported with `error` set instead
+ *   of failing the whole report
+ */
+pub async fn check_outdated(
+    downloader: &PackageDownloader,
+    config: &BuildConfig,
+    lock: &PackageLock,
+) -> Vec<OutdatedPackage> {
+    let mut report = Vec::new();
+    for (name, installed) in lock.list_packages() {
+        if let Some(source) = &installed.source {
+            log::info!(
+                "Not checking {} for updates: installed from {}",
+                name,
+                source
+            );
+            continue;
+        }
+
+        let constraint = constraints_for(config, lock, name);
+
+        let mut entry = OutdatedPackage {
+            name: name.into(),
+            current: installed.version.clone(),
+            constraint: constraint.clone(),
+            wanted: None,
+            latest: None,
+            outdated: false,
+            breaking: false,
+            error: None,
+        };
+
+        if let Err(e) = compare(
+            downloader,
+            name,
+            &installed.version,
+            &constraint,
+            &mut entry,
+        )
+        .await
+        {
+            log::warn!("Could not check {} for updates: {}", name, e);
+            entry.error = Some(e.to_string());
+        }
+
+        report.push(entry);
+    }
+
+    report
+}
+
+async fn compare(
+    downloader: &PackageDownloader,
+    name: &str,
+    current: &str,
+    constraint: &[SmolStr],
+    entry: &mut OutdatedPackage,
+) -> Result<()> {
+    let constraints = constraint
+        .iter()
+        .map(|c| VersionConstraint::parse(c))
+        .collect::<Result<Vec<_>>>()?;
+    let any = VersionConstraint::parse("*")?;
+
+    let latest = downloader.find_matching_version(name, &any).await?;
+    entry.latest = Some(latest.tag_name.clone());
+
+    entry.wanted = match downloader
+        .find_release_matching_all(name, &constraints)
+        .await
+    {
+        Ok(release) => Some(release.tag_name),
+        Err(e) => {
+            log::info!("No release of {} satisfies its constraint: {}", name, e);
+            None
+        }
+    };
+
+    let current = Version::parse(current)?;
+    let latest = Version::parse(&latest.tag_name)?;
+    entry.outdated = latest > current;
+    entry.breaking = entry.outdated
+        && constraints
+            .first()
+            .unwrap_or(&any)
+            .is_breaking_update(&current, &latest);
+
+    Ok(())
+}
+
+fn constraints_for(config: &BuildConfig, lock: &PackageLock, name: &str) -> Vec<SmolStr> {
+    let declared = config
+        .get_packages()
+        .and_then(|packages| packages.get(name))
+        .or_else(|| {
+            config
+                .package
+                .as_ref()
+                .and_then(|manifest| manifest.dependencies.as_ref())
+                .and_then(|dependencies| dependencies.get(name))
+        });
+
+    let mut required: Vec<SmolStr> = lock
+        .list_packages()
+        .into_iter()
+        .filter_map(|(_, package)| package.dependencies.get(name).cloned())
+        .collect();
+    required.sort();
+    required.dedup();
+
+    // The declared constraint comes first, it decides what counts as breaking
+    if let Some(spec) = declared {
+        let declared: SmolStr = spec.version().into();
+        required.retain(|constraint| *constraint != declared);
+        required.insert(0, declared);
+    }
+    required
+}
+
+/// Prints the packages with a newer release as a table.
+pub fn print_table(report: &[OutdatedPackage]) {
+    let rows: Vec<[String; 6]> = report
+        .iter()
+        .filter(|entry| entry.outdated)
+        .map(|entry| {
+            [
+                entry.name.to_string(),
+                entry.current.to_string(),
+                entry.wanted.clone().unwrap_or_else(|| "-".to_string()),
+                entry.latest.clone().unwrap_or_else(|| "-".to_string()),
+                if entry.constraint.is_empty() {
+                    "*".to_string()
+                } else {
+                    entry.constraint.join(", ")
+                },
+                if entry.breaking { "yes" } else { "no" }.to_string(),
+            ]
+        })
+        .collect();
+
+    if rows.is_empty() {
+        println!("All packages are up to date");
+    } else {
+        let header = [
+            "PACKAGE",
+            "CURRENT",
+            "WANTED",
+            "LATEST",
+            "CONSTRAINT",
+            "BREAKING",
+        ];
+        let mut widths = header.map(str::len);
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.len());
+            }
+        }
+
+        let format_row = |cells: [&str; 6]| {
+            cells
+                .iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        };
+
+        println!("  {}", format_row(header));
+        for row in &rows {
+            println!("  {}", format_row(row.each_ref().map(String::as_str)));
+        }
+    }
+
+    for entry in report {
+        if let Some(error) = &entry.error {
+            println!("Could not check {}: {}", entry.name, error);
+        }
+    }
+}
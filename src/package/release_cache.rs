@@ -0,0 +1,97 @@
+use crate::network::NetworkSettings;
+use crate::result::{OpenCliError, Result};
+use dirs::config_dir;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::fs;
+
+/// One cached GitHub API response together with its validator.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub url: String,
+    pub etag: Option<String>,
+    pub fetched_at: i64,
+    pub body: String,
+}
+
+/** On-disk cache of GitHub release listings
+ *
+ * # Layout
+ * - One JSON file per API URL and request under
+ *   `<config dir>/opencli/cache/releases/`
+ * - Each entry keeps the response body, its `ETag` and the fetch time
+ *
+ * # Notes
+ * - Entries younger than `[network] release_cache_ttl` are used without
+ *   contacting GitHub; older ones are revalidated with `If-None-Match`
+ * - Stale entries remain usable as a fallback when the API is unreachable
+ *   or rate limited
+ */
+pub struct ReleaseCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl CachedResponse {
+    pub fn new(url: &str, etag: Option<String>, body: String) -> Self {
+        Self {
+            url: url.to_string(),
+            etag,
+            fetched_at: chrono::Utc::now().timestamp(),
+            body,
+        }
+    }
+
+    pub fn age(&self) -> Duration {
+        let elapsed = chrono::Utc::now().timestamp() - self.fetched_at;
+        Duration::from_secs(elapsed.max(0) as u64)
+    }
+}
+
+impl ReleaseCache {
+    pub fn new() -> Result<Self> {
+        let config_dir = config_dir()
+            .ok_or_else(|| OpenCliError::Config("Could not determine config directory".into()))?;
+
+        Ok(Self {
+            dir: config_dir.join("opencli").join("cache").join("releases"),
+            ttl: NetworkSettings::current().release_cache_ttl(),
+        })
+    }
+
+    pub fn is_fresh(&self, entry: &CachedResponse) -> bool {
+        entry.age() < self.ttl
+    }
+
+    pub async fn load(&self, key: &str) -> Option<CachedResponse> {
+        let content = fs::read_to_string(self.entry_path(key)).await.ok()?;
+        match serde_json::from_str(&content) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                log::warn!("Ignoring corrupt release cache entry {}: {}", key, e);
+                None
+            }
+        }
+    }
+
+    pub async fn store(&self, key: &str, entry: &CachedResponse) -> Result<()> {
+        fs::create_dir_all(&self.dir).await?;
+        fs::write(self.entry_path(key), serde_json::to_string(entry)?).await?;
+        Ok(())
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        let file_name: String = key
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(format!("{}.json", file_name))
+    }
+}
@@ -0,0 +1,501 @@
+use crate::build::PackageTarget;
+use crate::package::downloader::GitHubRelease;
+use crate::package::version::Version;
+use crate::package::{PackageDownloader, PackageLock, PackageSource, VersionConstraint};
+use crate::result::{OpenCliError, Result};
+use smol_str::SmolStr;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::fmt;
+
+/// Upper bound on version selections, in case requirements keep replacing each other.
+const MAX_RESOLUTION_STEPS: usize = 1000;
+
+/// Where a requirement comes from, shown when explaining conflicts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Requirer {
+    /// The project's `opencli.toml`
+    Project,
+    /// `opencli package install owner/repo`
+    CommandLine,
+    /// The manifest of another package at the given version
+    Package { repo: SmolStr, version: SmolStr },
+}
+
+/// A version constraint one requirer places on a package.
+#[derive(Debug, Clone)]
+pub struct Requirement {
+    pub repo: SmolStr,
+    pub constraint: SmolStr,
+    pub target: Option<PackageTarget>,
+    pub required_by: Requirer,
+    /// Set for packages that are not GitHub releases; these are never resolved
+    pub source: Option<PackageSource>,
+}
+
+/// A package of the resolved graph, in installation order.
+#[derive(Debug, Clone)]
+pub struct ResolvedPackage {
+    pub repo: SmolStr,
+    pub tag: SmolStr,
+    /// Release to install, `None` when the version in the lock file is kept
+    pub release: Option<GitHubRelease>,
+    pub target: Option<PackageTarget>,
+    /// Constraints declared by the manifest of this version, by repository
+    pub dependencies: BTreeMap<SmolStr, SmolStr>,
+    pub required_by: Vec<Requirer>,
+}
+
+/// A requirement, and whether it pulls the package into the graph or only
+/// constrains it in case something else does.
+struct Edge {
+    requirement: Requirement,
+    binding: bool,
+}
+
+struct Selection {
+    tag: SmolStr,
+    version: Version,
+    release: Option<GitHubRelease>,
+    dependencies: BTreeMap<SmolStr, SmolStr>,
+}
+
+/** Resolves the dependency graph declared by package manifests
+ *
+ * # Algorithm
+ * - Every package gets the newest release satisfying all constraints
+ *   placed on it so far; the installed version is kept while it still does
+ * - Selecting a version reads its manifest and adds its dependencies;
+ *   replacing a version withdraws the requirements of the previous one
+ * - When no release satisfies every constraint, the error lists each
+ *   constraint with the package or file that declared it
+ *
+ * # Notes
+ * - The result lists dependencies before the packages requiring them
+ * - Cycles are reported in the log and broken at the repeated package
+ */
+pub struct DependencyResolver<'a> {
+    downloader: &'a PackageDownloader,
+    lock: &'a PackageLock,
+    requirements: BTreeMap<SmolStr, Vec<Edge>>,
+    selected: BTreeMap<SmolStr, Selection>,
+}
+
+impl<'a> DependencyResolver<'a> {
+    pub fn new(downloader: &'a PackageDownloader, lock: &'a PackageLock) -> Self {
+        Self {
+            downloader,
+            lock,
+            requirements: BTreeMap::new(),
+            selected: BTreeMap::new(),
+        }
+    }
+
+    /** Resolves `roots` and everything they depend on
+     *
+     * # Arguments
+     * * `roots` - Packages to install
+     * * `constraints` - Requirements that only apply if the package ends up
+     *   in the graph, e.g. those of other packages already installed
+     */
+    pub async fn resolve(
+        mut self,
+        roots: Vec<Requirement>,
+        constraints: Vec<Requirement>,
+    ) -> Result<Vec<ResolvedPackage>> {
+        let mut root_repos: Vec<SmolStr> = Vec::new();
+        let mut pending = VecDeque::new();
+
+        for requirement in roots {
+            if !root_repos.contains(&requirement.repo) {
+                root_repos.push(requirement.repo.clone());
+                pending.push_back(requirement.repo.clone());
+            }
+            self.add_requirement(requirement, true);
+        }
+        for requirement in constraints {
+            self.add_requirement(requirement, false);
+        }
+
+        let mut steps = 0;
+        while let Some(repo) = pending.pop_front() {
+            steps += 1;
+            if steps > MAX_RESOLUTION_STEPS {
+                return Err(OpenCliError::Process(
+                    format!(
+                        "Dependency resolution did not settle: the requirements on {} keep changing",
+                        repo
+                    )
+                    .into(),
+                ));
+            }
+
+            if !self.is_required(&repo) {
+                if self.selected.remove(&repo).is_some() {
+                    self.withdraw_requirements_of(&repo, &mut pending);
+                }
+                continue;
+            }
+
+            let constraints = self.constraints_for(&repo)?;
+            if let Some(selection) = self.selected.get(&repo) {
+                if constraints.iter().all(|c| c.matches(&selection.version)) {
+                    continue;
+                }
+            }
+
+            let selection = self.select_version(&repo, &constraints).await?;
+            self.withdraw_requirements_of(&repo, &mut pending);
+
+            let selection = self
+                .read_dependencies(&repo, selection, &mut pending)
+                .await?;
+            log::info!("Resolved {} to {}", repo, selection.tag);
+            self.selected.insert(repo, selection);
+        }
+
+        Ok(self.into_install_order(&root_repos))
+    }
+
+    fn add_requirement(&mut self, requirement: Requirement, binding: bool) {
+        let edges = self
+            .requirements
+            .entry(requirement.repo.clone())
+            .or_default();
+
+        if let Some(existing) = edges.iter_mut().find(|edge| {
+            edge.requirement.required_by == requirement.required_by
+                && edge.requirement.constraint == requirement.constraint
+        }) {
+            existing.binding |= binding;
+            return;
+        }
+
+        edges.push(Edge {
+            requirement,
+            binding,
+        });
+    }
+
+    fn is_required(&self, repo: &str) -> bool {
+        self.requirements
+            .get(repo)
+            .is_some_and(|edges| edges.iter().any(|edge| edge.binding))
+    }
+
+    /// Removes the requirements declared by the selected version of `parent`.
+    fn withdraw_requirements_of(&mut self, parent: &str, pending: &mut VecDeque<SmolStr>) {
+        for (repo, edges) in self.requirements.iter_mut() {
+            let before = edges.len();
+            edges.retain(|edge| {
+                !matches!(&edge.requirement.required_by, Requirer::Package { repo, .. } if repo == parent)
+            });
+            if edges.len() != before {
+                pending.push_back(repo.clone());
+            }
+        }
+    }
+
+    fn constraints_for(&self, repo: &str) -> Result<Vec<VersionConstraint>> {
+        self.requirements
+            .get(repo)
+            .into_iter()
+            .flatten()
+            .map(|edge| {
+                let requirement = &edge.requirement;
+                VersionConstraint::parse(&requirement.constraint).map_err(|e| {
+                    OpenCliError::Config(
+                        format!(
+                            "Invalid version constraint \"{}\" for {} (required by {}): {}",
+                            requirement.constraint, repo, requirement.required_by, e
+                        )
+                        .into(),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    async fn select_version(
+        &self,
+        repo: &str,
+        constraints: &[VersionConstraint],
+    ) -> Result<Selection> {
+        if let Some(installed) = self.lock.get_package(repo) {
+            if let Ok(version) = Version::parse(&installed.version) {
+                if constraints.iter().all(|c| c.matches(&version)) {
+                    return Ok(Selection {
+                        tag: installed.version.clone(),
+                        version,
+                        release: None,
+                        dependencies: BTreeMap::new(),
+                    });
+                }
+            }
+        }
+
+        match self
+            .downloader
+            .find_release_matching_all(repo, constraints)
+            .await
+        {
+            Ok(release) => Ok(Selection {
+                tag: release.tag_name.as_str().into(),
+                version: Version::parse(&release.tag_name)?,
+                release: Some(release),
+                dependencies: BTreeMap::new(),
+            }),
+            Err(OpenCliError::NotFound(_)) => Err(self.explain_conflict(repo, constraints).await),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Adds the dependencies declared by the manifest of the selected version.
+    async fn read_dependencies(
+        &mut self,
+        repo: &SmolStr,
+        mut selection: Selection,
+        pending: &mut VecDeque<SmolStr>,
+    ) -> Result<Selection> {
+        let Some(manifest) = self.downloader.fetch_manifest(repo, &selection.tag).await? else {
+            return Ok(selection);
+        };
+
+        let required_by = Requirer::Package {
+            repo: repo.clone(),
+            version: selection.tag.clone(),
+        };
+
+        for (dependency, spec) in manifest.dependencies() {
+            if dependency == repo {
+                continue;
+            }
+            if let Some(source) = spec.source() {
+                log::warn!(
+                    "Skipping dependency {} ({}) of {}: package dependencies must be GitHub releases",
+                    dependency,
+                    source,
+                    repo
+                );
+                continue;
+            }
+
+            selection
+                .dependencies
+                .insert(dependency.clone(), spec.version().into());
+            self.add_requirement(
+                Requirement::new(
+                    dependency.as_str(),
+                    spec.version(),
+                    spec.target().cloned(),
+                    required_by.clone(),
+                ),
+                true,
+            );
+            pending.push_back(dependency.clone());
+        }
+
+        Ok(selection)
+    }
+
+    async fn explain_conflict(
+        &self,
+        repo: &str,
+        constraints: &[VersionConstraint],
+    ) -> OpenCliError {
+        let edges = self.requirements.get(repo).map_or(&[][..], Vec::as_slice);
+
+        if let [edge] = edges {
+            return OpenCliError::NotFound(
+                format!(
+                    "No release of {} matches {} (required by {})",
+                    repo, edge.requirement.constraint, edge.requirement.required_by
+                )
+                .into(),
+            );
+        }
+
+        let mut message = format!("Conflicting version requirements for {}:", repo);
+        for (edge, constraint) in edges.iter().zip(constraints) {
+            let newest = match self
+                .downloader
+                .find_matching_version(repo, constraint)
+                .await
+            {
+                Ok(release) => format!("newest match {}", release.tag_name),
+                Err(_) => "no release matches".to_string(),
+            };
+            message.push_str(&format!(
+                "\n  {} required by {} ({})",
+                edge.requirement.constraint, edge.requirement.required_by, newest
+            ));
+        }
+        message.push_str("\nNo release satisfies all of them at once");
+
+        OpenCliError::Config(message.into())
+    }
+
+    /// Orders the packages reachable from `roots` so dependencies come first.
+    fn into_install_order(mut self, roots: &[SmolStr]) -> Vec<ResolvedPackage> {
+        let graph = self
+            .selected
+            .iter()
+            .map(|(repo, selection)| {
+                (
+                    repo.clone(),
+                    selection.dependencies.keys().cloned().collect(),
+                )
+            })
+            .collect();
+        let (order, cycles) = install_order(roots, &graph);
+        for cycle in cycles {
+            log::warn!(
+                "Dependency cycle: {}",
+                cycle
+                    .iter()
+                    .map(SmolStr::as_str)
+                    .collect::<Vec<_>>()
+                    .join(" -> ")
+            );
+        }
+        let ordered: HashSet<&SmolStr> = order.iter().collect();
+
+        order
+            .iter()
+            .filter_map(|repo| {
+                let selection = self.selected.remove(repo)?;
+                let edges = self.requirements.remove(repo).unwrap_or_default();
+
+                let required_by = edges
+                    .iter()
+                    .filter(|edge| edge.binding)
+                    .map(|edge| edge.requirement.required_by.clone())
+                    .filter(|requirer| match requirer {
+                        Requirer::Package { repo, .. } => ordered.contains(repo),
+                        _ => true,
+                    })
+                    .collect();
+
+                let target = edges
+                    .iter()
+                    .find_map(|edge| edge.requirement.target.clone())
+                    .or_else(|| match selection.release {
+                        Some(_) => None,
+                        None => self
+                            .lock
+                            .get_package(repo)
+                            .and_then(|installed| installed.target.clone()),
+                    });
+
+                Some(ResolvedPackage {
+                    repo: repo.clone(),
+                    tag: selection.tag,
+                    release: selection.release,
+                    target,
+                    dependencies: selection.dependencies,
+                    required_by,
+                })
+            })
+            .collect()
+    }
+}
+
+/** Orders the packages of `graph` reachable from `roots`, dependencies first
+ *
+ * # Arguments
+ * * `graph` - Dependencies of each package; packages missing from it are
+ *   not part of the result
+ *
+ * # Returns
+ * - The installation order
+ * - Every cycle met, from the package that starts it back to that package;
+ *   cycles are broken at the repeated package
+ */
+pub fn install_order(
+    roots: &[SmolStr],
+    graph: &BTreeMap<SmolStr, Vec<SmolStr>>,
+) -> (Vec<SmolStr>, Vec<Vec<SmolStr>>) {
+    let mut order = Vec::new();
+    let mut cycles = Vec::new();
+    let mut visited = HashSet::new();
+    let mut path = Vec::new();
+
+    for root in roots {
+        visit(
+            root,
+            graph,
+            &mut path,
+            &mut visited,
+            &mut order,
+            &mut cycles,
+        );
+    }
+
+    (order, cycles)
+}
+
+fn visit(
+    repo: &SmolStr,
+    graph: &BTreeMap<SmolStr, Vec<SmolStr>>,
+    path: &mut Vec<SmolStr>,
+    visited: &mut HashSet<SmolStr>,
+    order: &mut Vec<SmolStr>,
+    cycles: &mut Vec<Vec<SmolStr>>,
+) {
+    if visited.contains(repo) {
+        return;
+    }
+    if let Some(start) = path.iter().position(|entry| entry == repo) {
+        let mut cycle = path[start..].to_vec();
+        cycle.push(repo.clone());
+        cycles.push(cycle);
+        return;
+    }
+    let Some(dependencies) = graph.get(repo) else {
+        return;
+    };
+
+    path.push(repo.clone());
+    for dependency in dependencies {
+        visit(dependency, graph, path, visited, order, cycles);
+    }
+    path.pop();
+
+    visited.insert(repo.clone());
+    order.push(repo.clone());
+}
+
+impl Requirement {
+    pub fn new(
+        repo: &str,
+        constraint: &str,
+        target: Option<PackageTarget>,
+        required_by: Requirer,
+    ) -> Self {
+        Self {
+            repo: repo.into(),
+            constraint: constraint.into(),
+            target,
+            required_by,
+            source: None,
+        }
+    }
+}
+
+impl ResolvedPackage {
+    /// Whether the package is only installed because another package needs it.
+    pub fn is_dependency(&self) -> bool {
+        self.required_by
+            .iter()
+            .all(|requirer| matches!(requirer, Requirer::Package { .. }))
+    }
+}
+
+impl fmt::Display for Requirer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Requirer::Project => write!(f, "opencli.toml"),
+            Requirer::CommandLine => write!(f, "the command line"),
+            Requirer::Package { repo, version } => write!(f, "{} {}", repo, version),
+        }
+    }
+}
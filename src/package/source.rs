@@ -0,0 +1,225 @@
+use crate::package::lock::LockedArtifact;
+use crate::result::{OpenCliError, Result};
+use serde::{Deserialize, Serialize};
+use smol_str::SmolStr;
+use std::fmt;
+use std::path::PathBuf;
+
+/** Where a package comes from when it is not a GitHub release
+ *
+ * # Format
+ * ```toml
+ * [packages]
+ * "my-fork" = { git = "https://gitlab.com/me/my-fork", rev = "v1.2.0" }
+ * "shared" = { path = "../my-includes" }
+ * "tool" = { url = "https://example.com/tool.zip", sha256 = "..." }
+ * "owner/repo" = { rev = "main" }
+ * ```
+ *
+ * # Notes
+ * - Git sources are fetched as a snapshot archive of `rev` from the host,
+ *   so no `git` executable is needed; like a bare `rev`, it is locked at
+ *   the commit it resolved to
+ * - Paths are relative to the project and may name a directory or an archive
+ * - A bare `rev` is a branch, tag or commit of the GitHub repository named
+ *   by the package key; it is locked at the commit it resolved to
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PackageSource {
+    Git {
+        git: SmolStr,
+        rev: Option<SmolStr>,
+        /// Hosting software, detected from the URL when not given
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        forge: Option<GitForge>,
+    },
+    Path {
+        path: PathBuf,
+    },
+    Url {
+        url: SmolStr,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sha256: Option<SmolStr>,
+    },
+    /// Listed last so `{ git, rev }` is not mistaken for it
+    Rev {
+        rev: SmolStr,
+    },
+}
+
+/// Git hosts with a known snapshot archive URL layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GitForge {
+    Github,
+    Gitlab,
+    /// Gitea, Forgejo and Codeberg
+    Gitea,
+}
+
+impl PackageSource {
+    /// Recorded as the version in the lock file.
+    pub fn version_label(&self) -> SmolStr {
+        match self {
+            PackageSource::Git { rev, .. } => rev.clone().unwrap_or_else(|| "HEAD".into()),
+            PackageSource::Path { .. } => "path".into(),
+            PackageSource::Url { .. } => "url".into(),
+            PackageSource::Rev { rev } => rev.clone(),
+        }
+    }
+
+    /// The file to download, `None` for local paths and GitHub refs, whose
+    /// archive depends on the commit they resolve to.
+    pub fn artifact(&self) -> Result<Option<LockedArtifact>> {
+        match self {
+            PackageSource::Git { git, rev, forge } => {
+                let rev = rev.as_ref().ok_or_else(|| {
+                    OpenCliError::Config(
+                        format!("Git source {} needs a rev (tag, branch or commit)", git).into(),
+                    )
+                })?;
+                git_archive(git, rev, *forge).map(Some)
+            }
+            PackageSource::Path { .. } | PackageSource::Rev { .. } => Ok(None),
+            PackageSource::Url { url, sha256 } => {
+                let name = url
+                    .split(['?', '#'])
+                    .next()
+                    .and_then(|url| url.trim_end_matches('/').rsplit('/').next())
+                    .filter(|name| !name.is_empty() && !name.contains(':'))
+                    .ok_or_else(|| {
+                        OpenCliError::Config(format!("Cannot tell the file name of {}", url).into())
+                    })?;
+
+                Ok(Some(LockedArtifact {
+                    name: name.into(),
+                    url: url.clone(),
+                    api_url: None,
+                    size: None,
+                    sha256: sha256.clone(),
+                }))
+            }
+        }
+    }
+}
+
+impl fmt::Display for PackageSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackageSource::Git { git, rev, .. } => {
+                write!(f, "git {} @ {}", git, rev.as_deref().unwrap_or("HEAD"))
+            }
+            PackageSource::Path { path } => write!(f, "path {}", path.display()),
+            PackageSource::Url { url, .. } => write!(f, "url {}", url),
+            PackageSource::Rev { rev } => write!(f, "ref {}", rev),
+        }
+    }
+}
+
+/** Snapshot archive of `rev` in the repository at `git`
+ *
+ * # Layouts
+ * - GitHub, Gitea, Forgejo: `<repo>/archive/<rev>.tar.gz`
+ * - GitLab: `<repo>/-/archive/<rev>/<name>-<rev>.tar.gz`
+ */
+fn git_archive(git: &str, rev: &str, forge: Option<GitForge>) -> Result<LockedArtifact> {
+    let (base, host) = repository_base(git)?;
+
+    let name = base.rsplit('/').next().unwrap_or(base);
+    let file_rev = rev.replace('/', "-");
+
+    let forge = forge.unwrap_or(if host.contains("gitlab") {
+        GitForge::Gitlab
+    } else if host == "github.com" {
+        GitForge::Github
+    } else {
+        GitForge::Gitea
+    });
+
+    let url = match forge {
+        GitForge::Gitlab => format!("{}/-/archive/{}/{}-{}.tar.gz", base, rev, name, file_rev),
+        GitForge::Github | GitForge::Gitea => format!("{}/archive/{}.tar.gz", base, rev),
+    };
+
+    Ok(LockedArtifact {
+        name: format!("{}-{}.tar.gz", name, file_rev).into(),
+        url: url.into(),
+        api_url: None,
+        size: None,
+        sha256: None,
+    })
+}
+
+/// Repository URL without a trailing `.git`, and its host.
+fn repository_base(git: &str) -> Result<(&str, &str)> {
+    let base = git.trim_end_matches('/').trim_end_matches(".git");
+
+    base.strip_prefix("https://")
+        .or_else(|| base.strip_prefix("http://"))
+        .and_then(|rest| rest.split('/').next())
+        .map(|host| (base, host))
+        .ok_or_else(|| {
+            OpenCliError::Config(
+                format!(
+                    "Git source {} must be an http(s) URL; packages are downloaded as snapshot archives, not cloned",
+                    git
+                )
+                .into(),
+            )
+        })
+}
+
+/// URL of the smart HTTP ref advertisement of the repository at `git`,
+/// which lists its branches and tags like `git ls-remote` does.
+pub fn git_refs_url(git: &str) -> Result<String> {
+    let (base, _) = repository_base(git)?;
+    Ok(format!("{}.git/info/refs?service=git-upload-pack", base))
+}
+
+/** Commit that `rev` names in a smart HTTP ref advertisement
+ *
+ * # Resolution
+ * - `HEAD` and full ref names (`refs/...`) are looked up as given
+ * - Otherwise tags are tried before branches, as `git rev-parse` does
+ * - Annotated tags resolve to the commit they point at
+ */
+pub fn find_advertised_commit(advertisement: &[u8], rev: &str) -> Option<SmolStr> {
+    let mut refs = Vec::new();
+    let mut rest = advertisement;
+    while rest.len() >= 4 {
+        let length = std::str::from_utf8(&rest[..4])
+            .ok()
+            .and_then(|length| usize::from_str_radix(length, 16).ok())?;
+        if length == 0 {
+            rest = &rest[4..];
+            continue;
+        }
+        if length < 4 || length > rest.len() {
+            return None;
+        }
+
+        let line = String::from_utf8_lossy(&rest[4..length]);
+        let line = line.split('\0').next().unwrap_or_default().trim_end();
+        if let Some((sha, name)) = line.split_once(' ') {
+            if sha.len() == 40 && sha.bytes().all(|b| b.is_ascii_hexdigit()) {
+                refs.push((sha.to_ascii_lowercase(), name.to_string()));
+            }
+        }
+        rest = &rest[length..];
+    }
+
+    let candidates = if rev == "HEAD" || rev.starts_with("refs/") {
+        vec![rev.to_string()]
+    } else {
+        vec![format!("refs/tags/{}", rev), format!("refs/heads/{}", rev)]
+    };
+
+    candidates.iter().find_map(|candidate| {
+        let peeled = format!("{}^{{}}", candidate);
+        refs.iter()
+            .find(|(_, name)| *name == peeled)
+            .or_else(|| refs.iter().find(|(_, name)| name == candidate))
+            .map(|(sha, _)| SmolStr::from(sha.as_str()))
+    })
+}
@@ -0,0 +1,353 @@
+use crate::package::PackageLock;
+use crate::result::{OpenCliError, Result};
+use dirs::data_dir;
+use std::collections::BTreeSet;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::fs;
+
+const LINK_ENV: &str = "OPENCLI_STORE_LINK";
+
+const LOCK_FILES: &str = "lockfiles.txt";
+const LOCK_FILES_GUARD: &str = "lockfiles.txt.lock";
+/// A guard older than this was left behind by a crashed process
+const STALE_GUARD: Duration = Duration::from_secs(30);
+const GUARD_RETRY: Duration = Duration::from_millis(20);
+
+/// How installed files are made from their store copy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkMode {
+    Copy,
+    /// Falls back to copying across file systems. Installed files share
+    /// their data with the store, so editing one in place changes it for
+    /// every project; `package check` reports such edits
+    Hardlink,
+}
+
+/// What `opencli cache gc` removed or would remove.
+#[derive(Debug, Default)]
+pub struct GcReport {
+    pub removed: Vec<String>,
+    pub freed_bytes: u64,
+    pub kept: usize,
+    pub lock_files: usize,
+}
+
+/** Content-addressed store of package downloads shared by all projects
+ *
+ * # Layout
+ * - `<data dir>/opencli/store/<sha256>/<file name>`: a downloaded artifact
+ * - `<data dir>/opencli/store/<sha256>/contents/`: the artifact, extracted
+ * - `<data dir>/opencli/store/lockfiles.txt`: lock files written by installs
+ * - `<data dir>/opencli/store/lockfiles.txt.lock`: held while that list is
+ *   rewritten, so concurrent installs do not drop each other's entries
+ *
+ * # Notes
+ * - Entries are keyed by the SHA-256 of the download and never change, so
+ *   an artifact whose digest `opencli.lock` records is not fetched again
+ * - Archives are extracted to a staging folder that is renamed into place
+ *   once complete, so an interrupted extraction is redone next time
+ */
+pub struct PackageStore {
+    dir: PathBuf,
+}
+
+impl PackageStore {
+    pub fn new() -> Result<Self> {
+        let data_dir = data_dir()
+            .ok_or_else(|| OpenCliError::Config("Could not determine data directory".into()))?;
+
+        Ok(Self::at(data_dir.join("opencli").join("store")))
+    }
+
+    pub fn at(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Directory of the entry with digest `sha256`, which must be 64 hex
+    /// digits so it cannot name anything outside the store.
+    pub fn entry_dir(&self, sha256: &str) -> PathBuf {
+        assert!(is_sha256(sha256), "invalid store digest '{}'", sha256);
+        self.dir.join(sha256.to_ascii_lowercase())
+    }
+
+    pub fn contents_dir(&self, sha256: &str) -> PathBuf {
+        self.entry_dir(sha256).join("contents")
+    }
+
+    /// Stored artifact `name` with digest `sha256`, if present.
+    pub fn find(&self, sha256: &str, name: &str) -> Option<PathBuf> {
+        if !is_sha256(sha256) || !is_file_name(name) {
+            return None;
+        }
+
+        let path = self.entry_dir(sha256).join(name);
+        path.is_file().then_some(path)
+    }
+
+    /// Moves a downloaded file into the store and returns its stored path.
+    pub async fn add(&self, file: &Path, sha256: &str, name: &str) -> Result<PathBuf> {
+        if !is_sha256(sha256) || !is_file_name(name) {
+            return Err(OpenCliError::Config(
+                format!("Refusing to store '{}' under digest '{}'", name, sha256).into(),
+            ));
+        }
+
+        let entry_dir = self.entry_dir(sha256);
+        fs::create_dir_all(&entry_dir).await?;
+
+        let stored = entry_dir.join(name);
+        if stored.is_file() {
+            fs::remove_file(file).await?;
+            return Ok(stored);
+        }
+
+        if fs::rename(file, &stored).await.is_err() {
+            // The temporary directory may be on another file system
+            let partial = entry_dir.join(format!("{}.partial", name));
+            fs::copy(file, &partial).await?;
+            fs::rename(&partial, &stored).await?;
+            fs::remove_file(file).await?;
+        }
+
+        log::info!("Stored {} as {}", name, stored.display());
+        Ok(stored)
+    }
+
+    /// Records `lock_path` so `gc` keeps what it references.
+    pub async fn remember_lock(&self, lock_path: &Path) -> Result<()> {
+        let lock_path = fs::canonicalize(lock_path).await?;
+        let _guard = self.guard_lock_files().await?;
+        let mut lock_files = self.lock_files().await;
+
+        if lock_files.insert(lock_path) {
+            self.save_lock_files(&lock_files).await?;
+        }
+        Ok(())
+    }
+
+    /// Removes `forgotten` from the remembered lock files.
+    async fn forget_locks(&self, forgotten: &BTreeSet<PathBuf>) -> Result<()> {
+        let _guard = self.guard_lock_files().await?;
+        let mut lock_files = self.lock_files().await;
+
+        let before = lock_files.len();
+        lock_files.retain(|path| !forgotten.contains(path));
+        if lock_files.len() != before {
+            self.save_lock_files(&lock_files).await?;
+        }
+        Ok(())
+    }
+
+    /** Waits until this process alone may rewrite `lockfiles.txt`
+     *
+     * # Notes
+     * - The guard file is created exclusively and removed when the returned
+     *   guard is dropped
+     * - A guard left behind by a crashed process is taken over once stale
+     */
+    async fn guard_lock_files(&self) -> Result<LockFilesGuard> {
+        fs::create_dir_all(&self.dir).await?;
+        let path = self.dir.join(LOCK_FILES_GUARD);
+
+        loop {
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+                .await
+            {
+                Ok(_) => return Ok(LockFilesGuard { path }),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    let stale = fs::metadata(&path)
+                        .await
+                        .and_then(|metadata| metadata.modified())
+                        .ok()
+                        .and_then(|modified| modified.elapsed().ok())
+                        .is_some_and(|age| age > STALE_GUARD);
+                    if stale {
+                        log::warn!("Removing stale {}", path.display());
+                        let _ = fs::remove_file(&path).await;
+                    } else {
+                        tokio::time::sleep(GUARD_RETRY).await;
+                    }
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    async fn lock_files(&self) -> BTreeSet<PathBuf> {
+        fs::read_to_string(self.dir.join(LOCK_FILES))
+            .await
+            .unwrap_or_default()
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(PathBuf::from)
+            .collect()
+    }
+
+    /// Replaces `lockfiles.txt` through a rename, so readers never see it half written.
+    async fn save_lock_files(&self, lock_files: &BTreeSet<PathBuf>) -> Result<()> {
+        let content: String = lock_files
+            .iter()
+            .map(|path| format!("{}\n", path.display()))
+            .collect();
+        let partial = self.dir.join(format!("{}.partial", LOCK_FILES));
+        fs::write(&partial, content).await?;
+        fs::rename(&partial, self.dir.join(LOCK_FILES)).await?;
+        Ok(())
+    }
+
+    /** Removes entries that no remembered lock file references
+     *
+     * # Behaviour
+     * - Lock files that no longer exist are forgotten first
+     * - A remembered lock file that cannot be read aborts the collection,
+     *   since its artifacts would be removed otherwise
+     * - With `dry_run`, nothing is changed and the report lists what would be
+     */
+    pub async fn gc(&self, dry_run: bool) -> Result<GcReport> {
+        let mut report = GcReport::default();
+        if !self.dir.exists() {
+            return Ok(report);
+        }
+
+        let remembered = self.lock_files().await;
+        let lock_files: BTreeSet<PathBuf> = remembered
+            .iter()
+            .filter(|path| path.is_file())
+            .cloned()
+            .collect();
+
+        let mut referenced = BTreeSet::new();
+        for path in &lock_files {
+            let lock = PackageLock::load_from_file(path).await.map_err(|e| {
+                OpenCliError::Config(
+                    format!("Cannot read {} to collect the store: {}", path.display(), e).into(),
+                )
+            })?;
+            referenced.extend(
+                lock.installed
+                    .values()
+                    .flat_map(|package| &package.artifacts)
+                    .filter_map(|artifact| artifact.sha256.as_deref())
+                    .map(str::to_ascii_lowercase),
+            );
+        }
+        report.lock_files = lock_files.len();
+
+        let mut entries = fs::read_dir(&self.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !entry.file_type().await?.is_dir() || !is_sha256(&name) {
+                continue;
+            }
+
+            if referenced.contains(&name) {
+                report.kept += 1;
+                continue;
+            }
+
+            report.freed_bytes += dir_size(&entry.path()).await;
+            if !dry_run {
+                fs::remove_dir_all(entry.path()).await?;
+                log::info!("Removed unreferenced store entry {}", name);
+            }
+            report.removed.push(name);
+        }
+        report.removed.sort();
+
+        if !dry_run && lock_files.len() != remembered.len() {
+            let missing = remembered.difference(&lock_files).cloned().collect();
+            self.forget_locks(&missing).await?;
+        }
+
+        Ok(report)
+    }
+}
+
+/// Removes the `lockfiles.txt` guard file when dropped.
+struct LockFilesGuard {
+    path: PathBuf,
+}
+
+impl Drop for LockFilesGuard {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            log::warn!("Failed to remove {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+impl LinkMode {
+    /// `OPENCLI_STORE_LINK=hardlink` links installed files to the store,
+    /// anything else copies them.
+    pub fn current() -> Self {
+        match std::env::var(LINK_ENV) {
+            Ok(value) if value.trim().eq_ignore_ascii_case("hardlink") => LinkMode::Hardlink,
+            _ => LinkMode::Copy,
+        }
+    }
+}
+
+/** Places `source` at `destination`, replacing what is there
+ *
+ * # Notes
+ * - The destination is removed first, so writing a copy never goes through
+ *   a hard link into the store
+ */
+pub async fn place_file(source: &Path, destination: &Path, mode: LinkMode) -> Result<()> {
+    if fs::symlink_metadata(destination).await.is_ok() {
+        fs::remove_file(destination).await?;
+    }
+
+    if mode == LinkMode::Hardlink {
+        match fs::hard_link(source, destination).await {
+            Ok(()) => return Ok(()),
+            Err(e) => log::info!("Copying {} instead of linking it: {}", source.display(), e),
+        }
+    }
+
+    fs::copy(source, destination).await?;
+    Ok(())
+}
+
+/// True for a SHA-256 digest written as 64 hex digits.
+pub fn is_sha256(name: &str) -> bool {
+    name.len() == 64 && name.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// True for a single plain file name that stays in the directory it is joined onto.
+pub fn is_file_name(name: &str) -> bool {
+    !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.contains(['/', '\\'])
+        && Path::new(name).file_name() == Some(name.as_ref())
+}
+
+async fn dir_size(dir: &Path) -> u64 {
+    let mut size = 0;
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        let Ok(mut entries) = fs::read_dir(&dir).await else {
+            continue;
+        };
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            match entry.metadata().await {
+                Ok(metadata) if metadata.is_dir() => pending.push(entry.path()),
+                Ok(metadata) => size += metadata.len(),
+                Err(_) => {}
+            }
+        }
+    }
+
+    size
+}
@@ -62,7 +62,7 @@ impl VersionConstraint {
                 ">" => Ok(VersionConstraint::GreaterThan(version)),
                 "<=" => Ok(VersionConstraint::LessEqual(version)),
                 "<" => Ok(VersionConstraint::LessThan(version)),
-                "" => Ok(VersionConstraint::Exact(version)),
+                "" | "=" => Ok(VersionConstraint::Exact(version)),
                 _ => Err(OpenCliError::Config(
                     format!("Invalid version constraint: {}", constraint).into(),
                 )),
@@ -126,6 +126,34 @@ impl VersionConstraint {
         }
     }
 
+    /// Smallest version the constraint can match, if it has a lower bound.
+    pub fn lower_bound(&self) -> Option<&Version> {
+        match self {
+            VersionConstraint::Exact(v)
+            | VersionConstraint::Caret(v)
+            | VersionConstraint::Tilde(v)
+            | VersionConstraint::GreaterThan(v)
+            | VersionConstraint::GreaterEqual(v)
+            | VersionConstraint::Range(v, _) => Some(v),
+            VersionConstraint::LessThan(_) | VersionConstraint::LessEqual(_) => None,
+        }
+    }
+
+    /** Whether updating from `current` to `candidate` is breaking
+     *
+     * # Semantics
+     * - `~` constraints treat patch releases as compatible
+     * - Every other constraint follows `^`: minor and patch releases are
+     *   compatible, or only patch releases below `1.0.0`
+     */
+    pub fn is_breaking_update(&self, current: &Version, candidate: &Version) -> bool {
+        let compatible = match self {
+            VersionConstraint::Tilde(_) => VersionConstraint::Tilde(current.clone()),
+            _ => VersionConstraint::Caret(current.clone()),
+        };
+        !compatible.matches(candidate)
+    }
+
     pub fn latest_matching<'a>(&self, versions: &'a [Version]) -> Option<&'a Version> {
         versions.iter().filter(|v| self.matches(v)).max()
     }
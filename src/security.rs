@@ -2,9 +2,13 @@ use crate::result::{OpenCliError, Result};
 use argon2::password_hash::SaltString;
 use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
 use rand_core::OsRng;
+use sha2::digest::Output;
 use sha2::{Digest, Sha256};
 use std::path::Path;
 use tokio::fs;
+use tokio::io::AsyncReadExt;
+
+const HASH_BUFFER_SIZE: usize = 64 * 1024;
 
 /** Cryptographic manager for file hashing and verification operations
  *
@@ -69,13 +73,8 @@ impl SecurityManager {
      * ```
      */
     pub async fn hash_file(&self, file_path: &Path) -> Result<String> {
-        // Read file content asynchronously
-        let content = fs::read(file_path).await?;
-
         // Compute SHA-256 hash of file content
-        let mut hasher = Sha256::new();
-        hasher.update(&content);
-        let file_hash = hasher.finalize();
+        let file_hash = sha256_file(file_path).await?;
 
         // Generate cryptographically secure salt
         let salt = SaltString::generate(&mut OsRng);
@@ -127,10 +126,7 @@ impl SecurityManager {
      */
     pub async fn verify_file(&self, file_path: &Path, stored_hash: &str) -> Result<bool> {
         // Compute current SHA-256 hash of file
-        let content = fs::read(file_path).await?;
-        let mut hasher = Sha256::new();
-        hasher.update(&content);
-        let file_hash = hasher.finalize();
+        let file_hash = sha256_file(file_path).await?;
 
         // Parse stored Argon2 hash
         let parsed_hash = PasswordHash::new(stored_hash)
@@ -208,3 +204,28 @@ impl SecurityManager {
  *    - Resistant to: Rainbow tables, GPU cracking, Timing attacks
  *    - Assumes: Secure storage of resulting hash values
  */
+
+/// Computes the SHA-256 digest of a file without loading it into memory.
+pub async fn sha256_file(file_path: &Path) -> Result<Output<Sha256>> {
+    let mut hasher = Sha256::new();
+    sha256_update_from_file(&mut hasher, file_path).await?;
+    Ok(hasher.finalize())
+}
+
+/// Feeds a file into `hasher` in fixed-size chunks, returning the bytes read.
+pub async fn sha256_update_from_file(hasher: &mut Sha256, file_path: &Path) -> Result<u64> {
+    let mut file = fs::File::open(file_path).await?;
+    let mut buffer = vec![0u8; HASH_BUFFER_SIZE];
+    let mut total = 0u64;
+
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        total += read as u64;
+    }
+
+    Ok(total)
+}
@@ -0,0 +1,397 @@
+use bzip2::write::BzEncoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use lzma_rust2::{XzOptions, XzWriter};
+use opencli::archive::{ArchiveExtractor, ArchiveFormat};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tar::{EntryType, Header};
+use tempfile::TempDir;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+enum TarEntry<'a> {
+    File(&'a str, &'a [u8]),
+    Link(&'a str, EntryType, &'a str),
+    Special(&'a str, EntryType),
+}
+
+/// Builds a tar with raw header names, bypassing the `tar` crate's own path checks.
+fn tar_bytes(entries: &[TarEntry]) -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::new());
+
+    for entry in entries {
+        let mut header = Header::new_gnu();
+        let (name, entry_type, data, link): (&str, EntryType, &[u8], Option<&str>) = match entry {
+            TarEntry::File(name, data) => (name, EntryType::Regular, data, None),
+            TarEntry::Link(name, entry_type, target) => (name, *entry_type, &[], Some(target)),
+            TarEntry::Special(name, entry_type) => (name, *entry_type, &[], None),
+        };
+
+        let old = header.as_old_mut();
+        old.name[..name.len()].copy_from_slice(name.as_bytes());
+        if let Some(link) = link {
+            old.linkname[..link.len()].copy_from_slice(link.as_bytes());
+        }
+        header.set_entry_type(entry_type);
+        header.set_mode(0o644);
+        header.set_size(data.len() as u64);
+        header.set_cksum();
+        builder.append(&header, data).unwrap();
+    }
+
+    builder.into_inner().unwrap()
+}
+
+fn write_tar_gz(path: &Path, entries: &[TarEntry]) {
+    let mut encoder = GzEncoder::new(File::create(path).unwrap(), Compression::default());
+    encoder.write_all(&tar_bytes(entries)).unwrap();
+    encoder.finish().unwrap();
+}
+
+fn write_zip(path: &Path, build: impl FnOnce(&mut ZipWriter<File>)) {
+    let mut writer = ZipWriter::new(File::create(path).unwrap());
+    build(&mut writer);
+    writer.finish().unwrap();
+}
+
+fn add_zip_file(writer: &mut ZipWriter<File>, name: &str, data: &[u8]) {
+    writer
+        .start_file(name, SimpleFileOptions::default())
+        .unwrap();
+    writer.write_all(data).unwrap();
+}
+
+/// Temporary root holding the archive and an `out/` extraction directory.
+fn workspace() -> (TempDir, PathBuf) {
+    let root = tempfile::tempdir().unwrap();
+    let out = root.path().join("out");
+    (root, out)
+}
+
+async fn extract_err(archive: &Path, out: &Path, format: ArchiveFormat) -> String {
+    ArchiveExtractor::new(out)
+        .extract(archive, format)
+        .await
+        .expect_err("unsafe archive must be rejected")
+        .to_string()
+}
+
+fn files_under(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                files.extend(files_under(&path));
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files
+}
+
+#[tokio::test]
+async fn extracts_regular_archives() {
+    let (root, out) = workspace();
+    let zip_path = root.path().join("pkg.zip");
+    write_zip(&zip_path, |writer| {
+        add_zip_file(writer, "pkg/include/a.inc", b"// a");
+        add_zip_file(writer, "./pkg/b.inc", b"// b");
+    });
+
+    let extracted = ArchiveExtractor::new(&out)
+        .extract(&zip_path, ArchiveFormat::Zip)
+        .await
+        .unwrap();
+
+    assert_eq!(extracted.files.len(), 2);
+    assert_eq!(
+        std::fs::read_to_string(out.join("pkg/include/a.inc")).unwrap(),
+        "// a"
+    );
+    assert_eq!(
+        std::fs::read_to_string(out.join("pkg/b.inc")).unwrap(),
+        "// b"
+    );
+
+    let tar_path = root.path().join("pkg.tar.gz");
+    write_tar_gz(&tar_path, &[TarEntry::File("pawno/pawncc", b"elf")]);
+    let extracted = ArchiveExtractor::new(&out)
+        .extract(&tar_path, ArchiveFormat::TarGz)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        extracted.files,
+        vec![(out.join("pawno/pawncc"), "pawno/pawncc".to_string())]
+    );
+}
+
+#[tokio::test]
+async fn rejects_zip_path_traversal() {
+    let (root, out) = workspace();
+    let archive = root.path().join("evil.zip");
+    write_zip(&archive, |writer| {
+        add_zip_file(writer, "safe.inc", b"// safe");
+        add_zip_file(writer, "../escape.inc", b"// evil");
+        add_zip_file(writer, "include\\..\\..\\backslash.inc", b"// evil");
+    });
+
+    let error = extract_err(&archive, &out, ArchiveFormat::Zip).await;
+
+    assert!(error.contains("../escape.inc"), "{}", error);
+    assert!(
+        error.contains("include\\..\\..\\backslash.inc"),
+        "{}",
+        error
+    );
+    assert!(error.contains("2 unsafe entries"), "{}", error);
+    assert!(!root.path().join("escape.inc").exists());
+    assert!(!root.path().join("backslash.inc").exists());
+    assert!(
+        files_under(&out).is_empty(),
+        "safe entries must be cleaned up"
+    );
+}
+
+#[tokio::test]
+async fn rejects_absolute_paths() {
+    let (root, out) = workspace();
+    let target = root.path().join("absolute.inc");
+    let absolute = target.to_string_lossy().to_string();
+
+    let zip_path = root.path().join("abs.zip");
+    write_zip(&zip_path, |writer| {
+        add_zip_file(writer, &absolute, b"// evil");
+        add_zip_file(writer, "C:\\Windows\\evil.dll", b"MZ");
+    });
+
+    let error = extract_err(&zip_path, &out, ArchiveFormat::Zip).await;
+    assert!(error.contains(&absolute), "{}", error);
+    assert!(error.contains("C:\\Windows\\evil.dll"), "{}", error);
+    assert!(!target.exists());
+
+    let tar_path = root.path().join("abs.tar.gz");
+    write_tar_gz(&tar_path, &[TarEntry::File(&absolute, b"// evil")]);
+
+    let error = extract_err(&tar_path, &out, ArchiveFormat::TarGz).await;
+    assert!(error.contains(&absolute), "{}", error);
+    assert!(!target.exists());
+}
+
+#[tokio::test]
+async fn rejects_tar_path_traversal() {
+    let (root, out) = workspace();
+    let archive = root.path().join("evil.tar.gz");
+    write_tar_gz(
+        &archive,
+        &[
+            TarEntry::File("pkg/ok.inc", b"// ok"),
+            TarEntry::File("pkg/../../escape.inc", b"// evil"),
+        ],
+    );
+
+    let error = extract_err(&archive, &out, ArchiveFormat::TarGz).await;
+
+    assert!(error.contains("pkg/../../escape.inc"), "{}", error);
+    assert!(!root.path().join("escape.inc").exists());
+    assert!(files_under(&out).is_empty());
+}
+
+#[tokio::test]
+async fn rejects_device_files() {
+    let (root, out) = workspace();
+    let archive = root.path().join("devices.tar.gz");
+    write_tar_gz(
+        &archive,
+        &[
+            TarEntry::Special("dev/null", EntryType::Char),
+            TarEntry::Special("dev/sda", EntryType::Block),
+            TarEntry::Special("pipe", EntryType::Fifo),
+        ],
+    );
+
+    let error = extract_err(&archive, &out, ArchiveFormat::TarGz).await;
+
+    for name in ["dev/null", "dev/sda", "pipe"] {
+        assert!(error.contains(name), "{}", error);
+    }
+    assert!(error.contains("device or special file"), "{}", error);
+    assert!(files_under(&out).is_empty());
+}
+
+#[tokio::test]
+async fn copies_symlinks_inside_the_archive() {
+    let (root, out) = workspace();
+    let archive = root.path().join("links.tar.gz");
+    write_tar_gz(
+        &archive,
+        &[
+            TarEntry::File("lib/libpawnc.so.3", b"so"),
+            TarEntry::Link("lib/libpawnc.so", EntryType::Symlink, "libpawnc.so.3"),
+            TarEntry::Link(
+                "bin/libpawnc.so",
+                EntryType::Symlink,
+                "../lib/./libpawnc.so.3",
+            ),
+            TarEntry::Link("lib/hard.so", EntryType::Link, "lib/libpawnc.so.3"),
+        ],
+    );
+
+    let extracted = ArchiveExtractor::new(&out)
+        .extract(&archive, ArchiveFormat::TarGz)
+        .await
+        .unwrap();
+
+    assert_eq!(extracted.files.len(), 4);
+    for link in ["lib/libpawnc.so", "bin/libpawnc.so", "lib/hard.so"] {
+        let path = out.join(link);
+        let metadata = std::fs::symlink_metadata(&path).unwrap();
+        assert!(metadata.is_file(), "{} must be a regular file", link);
+        assert_eq!(std::fs::read(&path).unwrap(), b"so");
+    }
+}
+
+#[tokio::test]
+async fn rejects_escaping_symlinks() {
+    let (root, out) = workspace();
+    std::fs::write(root.path().join("secret"), "secret").unwrap();
+
+    let archive = root.path().join("links.tar.gz");
+    write_tar_gz(
+        &archive,
+        &[
+            TarEntry::Link("up", EntryType::Symlink, "../secret"),
+            TarEntry::Link("abs", EntryType::Symlink, "/etc/passwd"),
+            TarEntry::Link("hard", EntryType::Link, "../secret"),
+            TarEntry::Link("dangling", EntryType::Symlink, "missing.inc"),
+            // A file written through a symlinked directory must not escape either
+            TarEntry::Link("dir", EntryType::Symlink, ".."),
+            TarEntry::File("dir/escape.inc", b"// evil"),
+        ],
+    );
+
+    let error = extract_err(&archive, &out, ArchiveFormat::TarGz).await;
+
+    for name in ["up", "abs", "hard", "dangling", "dir"] {
+        assert!(error.contains(&format!("  - {}:", name)), "{}", error);
+    }
+    assert!(!root.path().join("escape.inc").exists());
+    assert!(files_under(&out).is_empty());
+}
+
+#[tokio::test]
+async fn handles_zip_symlinks() {
+    let (root, out) = workspace();
+    let archive = root.path().join("links.zip");
+    write_zip(&archive, |writer| {
+        add_zip_file(writer, "include/real.inc", b"// real");
+        writer
+            .add_symlink(
+                "include/alias.inc",
+                "real.inc",
+                SimpleFileOptions::default(),
+            )
+            .unwrap();
+    });
+
+    ArchiveExtractor::new(&out)
+        .extract(&archive, ArchiveFormat::Zip)
+        .await
+        .unwrap();
+    let alias = out.join("include/alias.inc");
+    assert!(std::fs::symlink_metadata(&alias).unwrap().is_file());
+    assert_eq!(std::fs::read_to_string(alias).unwrap(), "// real");
+
+    let (root, out) = workspace();
+    let archive = root.path().join("evil.zip");
+    write_zip(&archive, |writer| {
+        writer
+            .add_symlink(
+                "passwd",
+                "../../../../etc/passwd",
+                SimpleFileOptions::default(),
+            )
+            .unwrap();
+    });
+
+    let error = extract_err(&archive, &out, ArchiveFormat::Zip).await;
+    assert!(error.contains("  - passwd:"), "{}", error);
+    assert!(!out.join("passwd").exists());
+}
+
+#[tokio::test]
+async fn extracts_every_tar_flavour() {
+    let (root, out) = workspace();
+    let tar = tar_bytes(&[TarEntry::File("pkg/include/a.inc", b"// a")]);
+
+    let plain = root.path().join("pkg.tar");
+    std::fs::write(&plain, &tar).unwrap();
+
+    let xz = root.path().join("pkg.tar.xz");
+    let mut writer = XzWriter::new(File::create(&xz).unwrap(), XzOptions::with_preset(6)).unwrap();
+    writer.write_all(&tar).unwrap();
+    writer.finish().unwrap();
+
+    let bz2 = root.path().join("pkg.tar.bz2");
+    let mut writer = BzEncoder::new(File::create(&bz2).unwrap(), bzip2::Compression::default());
+    writer.write_all(&tar).unwrap();
+    writer.finish().unwrap();
+
+    for archive in [plain, xz, bz2] {
+        let name = archive.file_name().unwrap().to_string_lossy().to_string();
+        let format = ArchiveFormat::from_file_name(&name).unwrap().unwrap();
+        let out = out.join(&name);
+
+        let extracted = ArchiveExtractor::new(&out)
+            .extract(&archive, format)
+            .await
+            .unwrap();
+
+        assert_eq!(extracted.files.len(), 1, "{}", name);
+        assert_eq!(
+            std::fs::read_to_string(out.join("pkg/include/a.inc")).unwrap(),
+            "// a"
+        );
+    }
+}
+
+#[test]
+fn detects_formats_by_name_and_method() {
+    let cases = [
+        ("sscanf.zip", ArchiveFormat::Zip),
+        ("pawnc.tar", ArchiveFormat::Tar),
+        ("pawnc.TAR.GZ", ArchiveFormat::TarGz),
+        ("pawnc.tgz", ArchiveFormat::TarGz),
+        ("pawnc.tar.xz", ArchiveFormat::TarXz),
+        ("pawnc.txz", ArchiveFormat::TarXz),
+        ("pawnc.tar.bz2", ArchiveFormat::TarBz2),
+        ("pawnc.tbz2", ArchiveFormat::TarBz2),
+    ];
+    for (name, format) in cases {
+        assert_eq!(ArchiveFormat::from_file_name(name).unwrap(), Some(format));
+    }
+    assert_eq!(ArchiveFormat::from_file_name("a_samp.inc").unwrap(), None);
+
+    for method in ["zip", "tar", "tgz", "tar.gz", "tar.xz", "tar.bz2"] {
+        assert!(ArchiveFormat::from_method(method).is_ok(), "{}", method);
+    }
+}
+
+#[test]
+fn rejects_unsupported_formats() {
+    for (name, kind) in [("plugin.rar", "RAR"), ("plugin.7z", "7-Zip")] {
+        let error = ArchiveFormat::from_file_name(name).unwrap_err().to_string();
+        assert!(error.contains("Unsupported archive format"), "{}", error);
+        assert!(error.contains(name) && error.contains(kind), "{}", error);
+    }
+
+    for method in ["rar", "7z", "cab"] {
+        let error = ArchiveFormat::from_method(method).unwrap_err().to_string();
+        assert!(error.contains("Unsupported extraction method"), "{}", error);
+        assert!(error.contains(method), "{}", error);
+    }
+}
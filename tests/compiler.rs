@@ -0,0 +1,22 @@
+use opencli::compiler::validate_compiler_version;
+
+#[test]
+fn accepts_only_version_names_that_are_safe_directory_names() {
+    for version in ["v3.10.11", "3.10.10", "v3.10.11-rc1", "nightly_2024+linux"] {
+        assert!(validate_compiler_version(version).is_ok(), "{}", version);
+    }
+
+    for version in [
+        "",
+        ".",
+        "..",
+        "../../etc",
+        "v3.10/../..",
+        "C:\\compilers",
+        "v3 10",
+        ".hidden",
+        "v3.10.11\n",
+    ] {
+        assert!(validate_compiler_version(version).is_err(), "{:?}", version);
+    }
+}
@@ -0,0 +1,162 @@
+use opencli::download::HttpDownloader;
+use sha2::{Digest, Sha256};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const CONTENT: &[u8] = b"0123456789";
+
+/// Serves `responses` to consecutive connections and records each request head.
+fn serve(responses: Vec<String>) -> (String, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/lib.zip", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(Vec::new()));
+
+    let recorded = requests.clone();
+    std::thread::spawn(move || {
+        for response in responses {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut head = String::new();
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap() == 0 || line == "\r\n" {
+                    break;
+                }
+                head.push_str(&line.to_ascii_lowercase());
+            }
+            recorded.lock().unwrap().push(head);
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+
+    (url, requests)
+}
+
+fn full(etag: &str) -> String {
+    format!(
+        "HTTP/1.1 200 OK\r\nContent-Length: 10\r\nETag: {}\r\nConnection: close\r\n\r\n0123456789",
+        etag
+    )
+}
+
+fn rest(etag: &str) -> String {
+    format!(
+        "HTTP/1.1 206 Partial Content\r\nContent-Length: 5\r\nContent-Range: bytes 5-9/10\r\nETag: {}\r\nConnection: close\r\n\r\n56789",
+        etag
+    )
+}
+
+fn downloader() -> HttpDownloader {
+    HttpDownloader::new()
+        .unwrap()
+        .with_retry_policy(2, Duration::from_millis(1))
+}
+
+fn leave_partial(output: &Path, bytes: &[u8], validator: Option<&str>) {
+    std::fs::write(output.with_file_name("lib.zip.part"), bytes).unwrap();
+    if let Some(validator) = validator {
+        std::fs::write(output.with_file_name("lib.zip.part.validator"), validator).unwrap();
+    }
+}
+
+async fn download(url: &str, output: &Path) {
+    let downloaded = downloader()
+        .download(url, output, "lib.zip", None)
+        .await
+        .unwrap();
+
+    assert_eq!(std::fs::read(output).unwrap(), CONTENT);
+    assert_eq!(downloaded.size, CONTENT.len() as u64);
+    assert_eq!(downloaded.sha256, format!("{:x}", Sha256::digest(CONTENT)));
+    assert!(!output.with_file_name("lib.zip.part").exists());
+    assert!(!output.with_file_name("lib.zip.part.validator").exists());
+}
+
+#[tokio::test]
+async fn resumes_an_interrupted_transfer_with_if_range() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("lib.zip");
+    let truncated =
+        "HTTP/1.1 200 OK\r\nContent-Length: 10\r\nETag: \"v1\"\r\nConnection: close\r\n\r\n01234";
+    let (url, requests) = serve(vec![truncated.to_string(), rest("\"v1\"")]);
+
+    download(&url, &output).await;
+
+    let requests = requests.lock().unwrap();
+    assert!(!requests[0].contains("range:"));
+    assert!(requests[1].contains("range: bytes=5-"));
+    assert!(requests[1].contains("if-range: \"v1\""));
+}
+
+#[tokio::test]
+async fn hashes_the_bytes_of_a_leftover_partial_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("lib.zip");
+    leave_partial(&output, b"01234", Some("\"v1\""));
+    let (url, requests) = serve(vec![rest("\"v1\"")]);
+
+    download(&url, &output).await;
+
+    assert!(requests.lock().unwrap()[0].contains("if-range: \"v1\""));
+}
+
+#[tokio::test]
+async fn restarts_when_the_resource_changed() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("lib.zip");
+    leave_partial(&output, b"abcde", Some("\"v0\""));
+    let (url, _) = serve(vec![full("\"v1\"")]);
+
+    download(&url, &output).await;
+}
+
+#[tokio::test]
+async fn restarts_when_the_server_ignores_if_range() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("lib.zip");
+    leave_partial(&output, b"abcde", Some("\"v0\""));
+    let (url, requests) = serve(vec![rest("\"v1\""), full("\"v1\"")]);
+
+    download(&url, &output).await;
+
+    assert!(!requests.lock().unwrap()[1].contains("range:"));
+}
+
+#[tokio::test]
+async fn discards_a_partial_file_without_validator() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("lib.zip");
+    leave_partial(&output, b"abcde", None);
+    let (url, requests) = serve(vec![full("\"v1\"")]);
+
+    download(&url, &output).await;
+
+    assert!(!requests.lock().unwrap()[0].contains("range:"));
+}
+
+#[tokio::test]
+async fn completes_a_partial_file_the_server_reports_as_whole() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("lib.zip");
+    leave_partial(&output, CONTENT, Some("\"v1\""));
+    let not_satisfiable = "HTTP/1.1 416 Range Not Satisfiable\r\nContent-Range: bytes */10\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    let (url, _) = serve(vec![not_satisfiable.to_string()]);
+
+    download(&url, &output).await;
+}
+
+#[tokio::test]
+async fn restarts_a_partial_file_longer_than_the_resource() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("lib.zip");
+    leave_partial(&output, b"0123456789ab", Some("\"v1\""));
+    let not_satisfiable = "HTTP/1.1 416 Range Not Satisfiable\r\nContent-Range: bytes */10\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    let (url, requests) = serve(vec![not_satisfiable.to_string(), full("\"v1\"")]);
+
+    download(&url, &output).await;
+
+    assert!(!requests.lock().unwrap()[1].contains("range:"));
+}
@@ -0,0 +1,88 @@
+use opencli::compiler::{FlagRule, FlagTable};
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[test]
+fn validates_flags_against_the_compiler_version() {
+    let table = FlagTable::new(None);
+
+    // (compiler version, arguments, accepted)
+    let cases: &[(&str, &[&str], bool)] = &[
+        ("3.2.3664", &["-d3", "-;+", "-(+", "-\\+"], true),
+        ("3.2.3664", &["-XD4096", "-X1024", "-O1"], true),
+        ("3.10.11", &["-d3", "-;+", "-(+", "-\\+", "-Z+", "-E"], true),
+        ("3.2.3664", &["-Z+"], false),
+        ("3.10.0", &["-Z+"], false),
+        ("3.10.1", &["-Z+"], true),
+        ("3.10.2", &["-E"], false),
+        ("3.10.3", &["-E"], true),
+        // Unknown flags only warn
+        ("3.10.11", &["-Q", "-unknown"], true),
+        // Not flags at all
+        ("3.2.3664", &["MAX_PLAYERS=100", "Z+"], true),
+        // Versions that cannot be parsed skip validation
+        ("nightly", &["-Z+", "-E"], true),
+    ];
+
+    for (version, flags, accepted) in cases {
+        let result = table.validate(version, &args(flags));
+        assert_eq!(
+            result.is_ok(),
+            *accepted,
+            "{} {:?}: {:?}",
+            version,
+            flags,
+            result
+        );
+    }
+}
+
+#[test]
+fn reports_the_suggestion_of_a_too_new_flag() {
+    let error = FlagTable::new(None)
+        .validate("3.10.0", &args(&["-Z+"]))
+        .unwrap_err()
+        .to_string();
+
+    assert!(error.contains("'-Z+'"), "{}", error);
+    assert!(
+        error.contains("requires compiler 3.10.1 or newer"),
+        "{}",
+        error
+    );
+    assert!(error.contains("Suggestion:"), "{}", error);
+}
+
+#[test]
+fn prefers_rules_from_compilers_toml() {
+    let overrides = [
+        FlagRule {
+            flag: "Z".to_string(),
+            since: None,
+            until: None,
+            suggestion: None,
+        },
+        FlagRule {
+            flag: "t".to_string(),
+            since: None,
+            until: Some("3.10.8".to_string()),
+            suggestion: Some("drop it".to_string()),
+        },
+    ];
+    let table = FlagTable::new(Some(&overrides));
+
+    assert!(table.validate("3.2.3664", &args(&["-Z+"])).is_ok());
+    assert!(table.validate("3.10.8", &args(&["-t4"])).is_ok());
+
+    let error = table
+        .validate("3.10.9", &args(&["-t4"]))
+        .unwrap_err()
+        .to_string();
+    assert!(
+        error.contains("was removed after compiler 3.10.8"),
+        "{}",
+        error
+    );
+}
@@ -0,0 +1,52 @@
+use opencli::package::downloader::PackageFiles;
+use std::path::PathBuf;
+
+fn layout(root: &str, includes: &[&str]) -> Vec<String> {
+    let files = PackageFiles {
+        roots: vec![PathBuf::from(root)],
+        includes: includes.iter().map(PathBuf::from).collect(),
+        ..PackageFiles::default()
+    };
+
+    files
+        .include_layout()
+        .into_iter()
+        .map(|(_, destination)| destination.to_string_lossy().replace('\\', "/"))
+        .collect()
+}
+
+#[test]
+fn keeps_include_folders_below_the_archive_root() {
+    assert_eq!(
+        layout(
+            "/tmp/ysi",
+            &[
+                "/tmp/ysi/YSI-Includes-5.x/YSI_Coding/y_hooks.inc",
+                "/tmp/ysi/YSI-Includes-5.x/YSI_Core/y_utils.inc",
+                "/tmp/ysi/YSI-Includes-5.x/YSI.inc",
+            ]
+        ),
+        vec!["YSI_Coding/y_hooks.inc", "YSI_Core/y_utils.inc", "YSI.inc"]
+    );
+
+    assert_eq!(
+        layout(
+            "/tmp/lib",
+            &[
+                "/tmp/lib/lib-1.0/pawno/include/lib.inc",
+                "/tmp/lib/lib-1.0/README.inc"
+            ]
+        ),
+        vec!["lib.inc", "README.inc"]
+    );
+
+    assert_eq!(
+        layout("/tmp/one", &["/tmp/one/mylib/only.inc"]),
+        vec!["only.inc"]
+    );
+    assert_eq!(
+        layout("/tmp/flat", &["/tmp/flat/a.inc", "/tmp/flat/nested/b.inc"]),
+        vec!["a.inc", "nested/b.inc"]
+    );
+    assert_eq!(layout("/tmp/other", &["/elsewhere/c.inc"]), vec!["c.inc"]);
+}
@@ -0,0 +1,206 @@
+use opencli::build::PackageTarget;
+use opencli::package::lock::LOCK_SCHEMA_VERSION;
+use opencli::package::{PackageLock, PackageStore, Requirement, Requirer};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Locked name, version and dependency constraints.
+type LockedEntry<'a> = (&'a str, &'a str, &'a [(&'a str, &'a str)]);
+
+fn lock_with(packages: &[LockedEntry]) -> PackageLock {
+    let mut lock = PackageLock::default();
+    for (name, version, dependencies) in packages {
+        let dependencies: BTreeMap<_, _> = dependencies
+            .iter()
+            .map(|(repo, constraint)| ((*repo).into(), (*constraint).into()))
+            .collect();
+        lock.add_package(
+            (*name).into(),
+            (*version).into(),
+            None,
+            "hash".into(),
+            Vec::new(),
+            dependencies,
+        );
+    }
+    lock
+}
+
+#[test]
+fn reports_lock_out_of_sync_with_requirements() {
+    let lock = lock_with(&[
+        ("acme/app", "v1.0.0", &[("acme/base", "^1.0")]),
+        ("acme/base", "v2.0.0", &[]),
+        ("acme/old", "v0.1.0", &[]),
+    ]);
+
+    let problems = lock.out_of_sync(vec![
+        Requirement::new("acme/app", "^1.0", None, Requirer::Project),
+        Requirement::new(
+            "acme/extra",
+            "*",
+            Some(PackageTarget::Plugins),
+            Requirer::Project,
+        ),
+    ]);
+
+    assert_eq!(
+        problems,
+        vec![
+            "acme/base ^1.0 (required by acme/app v1.0.0) is locked at v2.0.0",
+            "acme/extra * (required by opencli.toml) is not locked",
+            "acme/old v0.1.0 is locked but no longer required",
+        ]
+    );
+
+    let in_sync = lock_with(&[("acme/base", "v1.2.0", &[])]);
+    assert!(in_sync
+        .out_of_sync(vec![Requirement::new(
+            "acme/base",
+            "^1.0",
+            None,
+            Requirer::Project
+        )])
+        .is_empty());
+}
+
+#[test]
+fn orders_locked_packages_after_their_dependencies() {
+    let lock = lock_with(&[
+        (
+            "acme/app",
+            "v1.0.0",
+            &[("acme/base", "^1.0"), ("acme/util", "*")],
+        ),
+        ("acme/base", "v1.0.0", &[("acme/util", "*")]),
+        ("acme/util", "v1.0.0", &[]),
+    ]);
+
+    assert_eq!(
+        lock.install_order(),
+        vec!["acme/util", "acme/base", "acme/app"]
+    );
+}
+
+#[tokio::test]
+async fn migrates_unversioned_lock_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("opencli.lock");
+    std::fs::write(
+        &path,
+        r#"
+[installed."acme/zeta"]
+version = "v1.0.0"
+hash = "hash"
+installed_at = "2026-01-01T00:00:00+00:00"
+files = ["include/zeta.inc"]
+
+[installed."acme/alpha"]
+version = "v2.0.0"
+hash = "hash"
+installed_at = "2026-01-01T00:00:00+00:00"
+files = ["include/alpha.inc"]
+"#,
+    )
+    .unwrap();
+
+    let lock = PackageLock::load_from_file(&path).await.unwrap();
+    assert_eq!(lock.schema_version, LOCK_SCHEMA_VERSION);
+    assert_eq!(lock.get_installed_version("acme/zeta"), Some("v1.0.0"));
+    assert!(lock.get_package("acme/alpha").unwrap().checksums.is_empty());
+
+    lock.save_to_file(&path).await.unwrap();
+    let saved = std::fs::read_to_string(&path).unwrap();
+    assert!(saved.starts_with(&format!("schema_version = {}", LOCK_SCHEMA_VERSION)));
+    assert!(saved.find("acme/alpha").unwrap() < saved.find("acme/zeta").unwrap());
+
+    std::fs::write(&path, "schema_version = 99\n").unwrap();
+    assert!(PackageLock::load_from_file(&path).await.is_err());
+}
+
+#[tokio::test]
+async fn refuses_artifacts_that_would_escape_their_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("opencli.lock");
+    let digest = "a".repeat(64);
+    let write_lock = |name: &str, sha256: &str| {
+        std::fs::write(
+            &path,
+            format!(
+                r#"
+schema_version = 2
+
+[installed."acme/lib"]
+version = "v1.0.0"
+hash = "hash"
+installed_at = "2026-01-01T00:00:00+00:00"
+files = ["pawno/include/lib.inc"]
+
+[[installed."acme/lib".artifacts]]
+name = '{}'
+url = "https://github.com/acme/lib/releases/download/v1.0.0/lib.zip"
+sha256 = '{}'
+"#,
+                name, sha256
+            ),
+        )
+        .unwrap();
+    };
+
+    write_lock("lib.zip", &digest);
+    assert!(PackageLock::load_from_file(&path).await.is_ok());
+
+    for (name, sha256) in [
+        ("../../x", digest.as_str()),
+        ("nested/lib.zip", digest.as_str()),
+        ("..\\lib.zip", digest.as_str()),
+        ("..", digest.as_str()),
+        ("/tmp/lib.zip", digest.as_str()),
+        ("lib.zip", "../../../tmp"),
+        ("lib.zip", "abc"),
+    ] {
+        write_lock(name, sha256);
+        let error = PackageLock::load_from_file(&path).await.unwrap_err();
+        assert!(error.to_string().contains("acme/lib"), "{}", error);
+    }
+
+    let store = PackageStore::at(dir.path().join("store"));
+    let download = dir.path().join("lib.zip");
+    std::fs::write(&download, "zip").unwrap();
+    assert!(store.add(&download, &digest, "../../x").await.is_err());
+    assert!(store.add(&download, "../..", "lib.zip").await.is_err());
+    assert!(store.find(&digest, "../lib.zip").is_none());
+    assert!(!dir.path().join("x").exists());
+}
+
+#[tokio::test]
+async fn records_installed_files_relative_to_the_project() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("opencli.lock");
+    let binary = dir.path().join("components").join("sscanf.so");
+    std::fs::write(
+        &path,
+        format!(
+            r#"
+[installed."Y-Less/sscanf"]
+version = "v2.13.8"
+hash = "hash"
+installed_at = "2026-01-01T00:00:00+00:00"
+files = ['{}', "pawno/include/sscanf2.inc", "/elsewhere/sscanf.so"]
+"#,
+            binary.display()
+        ),
+    )
+    .unwrap();
+
+    let lock = PackageLock::load_from_file(&path).await.unwrap();
+    let files = &lock.get_package("Y-Less/sscanf").unwrap().files;
+    assert_eq!(
+        files.as_slice(),
+        [
+            Path::new("components").join("sscanf.so").to_str().unwrap(),
+            "pawno/include/sscanf2.inc",
+            "/elsewhere/sscanf.so"
+        ]
+    );
+}
@@ -0,0 +1,96 @@
+use opencli::package::manifest::{parse_sampctl_dependency, PackageManifest};
+use opencli::package::PackageSource;
+
+#[test]
+fn reads_package_section_of_opencli_toml() {
+    let manifest = PackageManifest::from_opencli_toml(
+        r#"
+[build]
+entry_file = "test.pwn"
+output_file = "test.amx"
+compiler_version = "v3.10.11"
+
+[package]
+name = "acme/app"
+
+[package.dependencies]
+"acme/base" = "^1.0"
+"acme/plugin" = { version = "~2.1", target = "plugins" }
+"#,
+    )
+    .unwrap()
+    .unwrap();
+
+    let dependencies = manifest.dependencies();
+    assert_eq!(dependencies.len(), 2);
+    assert_eq!(dependencies[0].0, "acme/base");
+    assert_eq!(dependencies[0].1.version(), "^1.0");
+    assert_eq!(dependencies[1].1.version(), "~2.1");
+    assert!(dependencies[1].1.target().is_some());
+
+    assert!(
+        PackageManifest::from_opencli_toml("[build]\nentry_file = \"a.pwn\"\n")
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[test]
+fn converts_pawn_json_dependencies() {
+    let manifest = PackageManifest::from_pawn_json(
+        r#"{
+            "entry": "test.pwn",
+            "dependencies": [
+                "pawn-lang/samp-stdlib",
+                "pawn-lang/YSI-Includes:v5.10.006",
+                "https://github.com/Y-Less/sscanf",
+                "acme/util@main"
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    let dependencies: Vec<(String, String)> = manifest
+        .dependencies()
+        .into_iter()
+        .map(|(repo, spec)| (repo.to_string(), spec.version().to_string()))
+        .collect();
+
+    assert_eq!(
+        dependencies,
+        vec![
+            ("Y-Less/sscanf".to_string(), "*".to_string()),
+            ("acme/util".to_string(), "*".to_string()),
+            (
+                "pawn-lang/YSI-Includes".to_string(),
+                "=v5.10.006".to_string()
+            ),
+        ]
+    );
+    assert!(matches!(
+        manifest.dependencies()[1].1.source(),
+        Some(PackageSource::Rev { rev }) if rev == "main"
+    ));
+}
+
+#[test]
+fn parses_sampctl_references() {
+    assert!(parse_sampctl_dependency("not a repo").is_err());
+
+    let (repo, spec) = parse_sampctl_dependency("github.com/acme/util.git").unwrap();
+    assert_eq!(repo, "acme/util");
+    assert_eq!(spec.version(), "*");
+
+    let (_, spec) = parse_sampctl_dependency("acme/util:v1.2.0").unwrap();
+    assert_eq!(spec.version(), "=v1.2.0");
+
+    for (dependency, rev) in [("acme/util#0123abc", "0123abc"), ("acme/util@5.x", "5.x")] {
+        let (repo, spec) = parse_sampctl_dependency(dependency).unwrap();
+        assert_eq!(repo, "acme/util");
+        assert!(
+            matches!(spec.source(), Some(PackageSource::Rev { rev: r }) if r == rev),
+            "{:?}",
+            spec
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use opencli::network::{MirrorRule, NetworkConfig, NetworkSettings};
+use opencli::result::OpenCliError;
+
+#[test]
+fn sends_the_token_only_to_the_configured_api_host() {
+    std::env::set_var("GITHUB_TOKEN", "ghp_network_test_token_0123456789abcdef");
+
+    let settings = NetworkSettings::from_config(NetworkConfig {
+        github_api_url: Some("https://ghe.example.com/api/v3".into()),
+        mirrors: Some(vec![MirrorRule {
+            from: "https://ghe.example.com/api/v3/repos/mirrored/".into(),
+            to: "https://artifacts.example.net/".into(),
+        }]),
+        ..NetworkConfig::default()
+    });
+
+    let own = "https://ghe.example.com/api/v3/repos/owner/lib/releases/assets/1";
+    assert!(settings.github_token_for(own).is_some());
+    assert!(settings
+        .github_token_for("https://GHE.example.com/api/v3/repos/owner/lib/tarball/abc")
+        .is_some());
+
+    for foreign in [
+        "https://evil.example.net/api/v3/repos/owner/lib/releases/assets/1",
+        "https://ghe.example.com.evil.example.net/api/v3/repos/owner/lib/releases/assets/1",
+        "http://ghe.example.com/api/v3/repos/owner/lib/releases/assets/1",
+        "https://ghe.example.com:8443/api/v3/repos/owner/lib/releases/assets/1",
+        "https://user@evil.example.net/ghe.example.com/api/v3",
+        "https://ghe.example.com/api/v3/repos/mirrored/lib/releases/assets/1",
+        "not a url",
+    ] {
+        assert!(settings.github_token_for(foreign).is_none(), "{}", foreign);
+    }
+}
+
+fn mirror(from: &str, to: &str) -> MirrorRule {
+    MirrorRule {
+        from: from.into(),
+        to: to.into(),
+    }
+}
+
+#[test]
+fn rewrites_urls_with_the_first_matching_mirror() {
+    let settings = NetworkSettings::from_config(NetworkConfig {
+        mirrors: Some(vec![
+            mirror("https://github.com/pawn-lang/", "https://pawn.example.com/"),
+            mirror("https://github.com/", "https://artifacts.example.com/gh/"),
+        ]),
+        ..NetworkConfig::default()
+    });
+
+    assert_eq!(
+        settings.rewrite_url("https://github.com/pawn-lang/compiler/archive/v3.zip"),
+        "https://pawn.example.com/compiler/archive/v3.zip"
+    );
+    assert_eq!(
+        settings.rewrite_url("https://github.com/owner/lib/archive/v1.zip"),
+        "https://artifacts.example.com/gh/owner/lib/archive/v1.zip"
+    );
+    assert_eq!(
+        settings.rewrite_url("https://gitlab.com/owner/lib.zip"),
+        "https://gitlab.com/owner/lib.zip"
+    );
+    assert_eq!(
+        NetworkSettings::default().rewrite_url("https://github.com/owner/lib.zip"),
+        "https://github.com/owner/lib.zip"
+    );
+}
+
+#[test]
+fn merges_project_settings_over_global_ones() {
+    let project = NetworkConfig {
+        github_api_url: Some("https://ghe.example.com/api/v3".into()),
+        release_cache_ttl: Some(60),
+        mirrors: Some(vec![mirror(
+            "https://github.com/",
+            "https://project.example.com/",
+        )]),
+        ..NetworkConfig::default()
+    };
+    let global = NetworkConfig {
+        github_api_url: Some("https://global.example.com/api/v3".into()),
+        proxy: Some("socks5h://127.0.0.1:1080".into()),
+        release_cache_ttl: Some(3600),
+        mirrors: Some(vec![mirror(
+            "https://github.com/",
+            "https://global.example.com/",
+        )]),
+        ..NetworkConfig::default()
+    };
+
+    let merged = NetworkSettings::merge(project, global);
+    assert_eq!(
+        merged.github_api_url.as_deref(),
+        Some("https://ghe.example.com/api/v3")
+    );
+    assert_eq!(merged.proxy.as_deref(), Some("socks5h://127.0.0.1:1080"));
+    assert_eq!(merged.release_cache_ttl, Some(60));
+
+    let mirrors = merged.mirrors.clone().unwrap();
+    assert_eq!(mirrors.len(), 2);
+    assert_eq!(mirrors[0].to, "https://project.example.com/");
+    assert_eq!(mirrors[1].to, "https://global.example.com/");
+    assert_eq!(
+        NetworkSettings::from_config(merged).rewrite_url("https://github.com/owner/lib.zip"),
+        "https://project.example.com/owner/lib.zip"
+    );
+
+    let empty = NetworkSettings::merge(NetworkConfig::default(), NetworkConfig::default());
+    assert!(empty.mirrors.is_none());
+}
+
+#[test]
+fn reads_the_project_section_from_the_given_config_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let global = dir.path().join("config.toml");
+    std::fs::write(
+        &global,
+        "[network]\ngithub_api_url = \"https://global.example.com/api/v3\"\nrelease_cache_ttl = 3600\n",
+    )
+    .unwrap();
+    let project = dir.path().join("configs").join("ci.toml");
+    std::fs::create_dir_all(project.parent().unwrap()).unwrap();
+    std::fs::write(
+        &project,
+        "[build]\nentry_file = \"gamemodes/main.pwn\"\n\n[network]\ngithub_api_url = \"https://ghe.example.com/api/v3\"\n",
+    )
+    .unwrap();
+
+    let settings = NetworkSettings::from_files(Some(&global), &project);
+    assert_eq!(settings.github_api_url(), "https://ghe.example.com/api/v3");
+    assert_eq!(settings.release_cache_ttl().as_secs(), 3600);
+
+    let settings = NetworkSettings::from_files(None, &dir.path().join("missing.toml"));
+    assert_eq!(settings.github_api_url(), "https://api.github.com");
+}
+
+#[test]
+fn detects_offline_mode_from_the_flag_and_the_environment() {
+    std::env::remove_var("OPENCLI_OFFLINE");
+    NetworkSettings::set_offline(false);
+    assert!(!NetworkSettings::is_offline());
+    assert!(NetworkSettings::ensure_online("lib.zip").is_ok());
+
+    for (value, offline) in [
+        ("1", true),
+        ("true", true),
+        ("yes", true),
+        (" TRUE ", true),
+        ("0", false),
+        ("false", false),
+        ("No", false),
+        ("", false),
+    ] {
+        std::env::set_var("OPENCLI_OFFLINE", value);
+        assert_eq!(NetworkSettings::is_offline(), offline, "{:?}", value);
+    }
+
+    std::env::remove_var("OPENCLI_OFFLINE");
+    NetworkSettings::set_offline(true);
+    assert!(NetworkSettings::is_offline());
+    let error = NetworkSettings::ensure_online("lib.zip").unwrap_err();
+    assert!(error.to_string().contains("lib.zip"));
+
+    NetworkSettings::set_offline(false);
+    assert!(!NetworkSettings::is_offline());
+}
+
+/// A self-signed certificate valid until 2126.
+const TEST_CA: &str = "-----BEGIN CERTIFICATE-----
+MIIBizCCATGgAwIBAgIUWh0Xtk6L+vjaz7KBLSwD5CyzIdkwCgYIKoZIzj0EAwIw
+GjEYMBYGA1UEAwwPb3BlbmNsaSB0ZXN0IENBMCAXDTI2MTAxOTAyMTQxNloYDzIx
+MjYwOTI1MDIxNDE2WjAaMRgwFgYDVQQDDA9vcGVuY2xpIHRlc3QgQ0EwWTATBgcq
+hkjOPQIBBggqhkjOPQMBBwNCAARxZWeM3PAB9iwHzVxj6/10I0luzrGsyJLeCFzf
+aVSggWFdI7b6iuoqBsW515NOG/Z3gJ4nlm1ALwoBY37QGf2Mo1MwUTAdBgNVHQ4E
+FgQUXCThnYGK21a/8ipO4Lp3L4bwmt4wHwYDVR0jBBgwFoAUXCThnYGK21a/8ipO
+4Lp3L4bwmt4wDwYDVR0TAQH/BAUwAwEB/zAKBggqhkjOPQQDAgNIADBFAiEA9vOd
+B40N3+OMj5n1FXKu1sAOdSES/ZghSMGRSR/wMAoCIBPraiWa7jrIExNb1lWxb6SU
+3KzzvQEy0BeHl/2GIGEG
+-----END CERTIFICATE-----
+";
+
+fn client_error(config: NetworkConfig) -> String {
+    match NetworkSettings::from_config(config).http_client() {
+        Ok(_) => panic!("expected the HTTP client to be rejected"),
+        Err(OpenCliError::Config(message)) => message.to_string(),
+        Err(error) => panic!("expected a configuration error, got {}", error),
+    }
+}
+
+#[test]
+fn rejects_an_invalid_proxy() {
+    let error = client_error(NetworkConfig {
+        proxy: Some("http://proxy host:8080".into()),
+        ..NetworkConfig::default()
+    });
+    assert!(
+        error.contains("proxy 'http://proxy host:8080'"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn rejects_a_ca_bundle_that_cannot_be_used() {
+    let dir = tempfile::tempdir().unwrap();
+    let missing = dir.path().join("missing.pem");
+    let garbled = dir.path().join("garbled.pem");
+    std::fs::write(
+        &garbled,
+        "-----BEGIN CERTIFICATE-----\nnot base64!\n-----END CERTIFICATE-----\n",
+    )
+    .unwrap();
+    let empty = dir.path().join("empty.pem");
+    std::fs::write(&empty, "no certificates here\n").unwrap();
+
+    for bundle in [missing, garbled, empty] {
+        let error = client_error(NetworkConfig {
+            ca_bundle: Some(bundle.clone()),
+            ..NetworkConfig::default()
+        });
+        assert!(
+            error.contains(&format!("ca_bundle {}", bundle.display())),
+            "{}",
+            error
+        );
+    }
+}
+
+#[test]
+fn builds_a_client_from_valid_proxy_and_ca_settings() {
+    let dir = tempfile::tempdir().unwrap();
+    let bundle = dir.path().join("ca.pem");
+    std::fs::write(&bundle, TEST_CA).unwrap();
+
+    for proxy in ["http://proxy.example.com:8080", "socks5h://127.0.0.1:1080"] {
+        let settings = NetworkSettings::from_config(NetworkConfig {
+            proxy: Some(proxy.into()),
+            no_proxy: Some("localhost,.internal.example.com".into()),
+            ca_bundle: Some(bundle.clone()),
+            ..NetworkConfig::default()
+        });
+        assert!(settings.http_client().is_ok(), "{}", proxy);
+    }
+}
@@ -0,0 +1,225 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use opencli::package::{PackageLock, PackageManager};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+const CONFIG: &str = r#"
+[build]
+entry_file = "gamemodes/main.pwn"
+output_file = "gamemodes/main.amx"
+compiler_version = "v3.10.11"
+
+[build.includes]
+paths = ["pawno/include"]
+"#;
+
+/// Keeps the package store and caches of every test in a scratch directory.
+fn isolate_user_dirs() {
+    static HOME: OnceLock<tempfile::TempDir> = OnceLock::new();
+    HOME.get_or_init(|| {
+        let home = tempfile::tempdir().unwrap();
+        std::env::set_var("XDG_CONFIG_HOME", home.path().join("config"));
+        std::env::set_var("XDG_DATA_HOME", home.path().join("data"));
+        home
+    });
+}
+
+/// A project directory with `opencli.toml` made of `CONFIG` and `extra`.
+fn project(extra: &str) -> (tempfile::TempDir, PathBuf) {
+    isolate_user_dirs();
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("project");
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(root.join("opencli.toml"), format!("{}{}", CONFIG, extra)).unwrap();
+    (dir, root)
+}
+
+fn manager(root: &Path) -> PackageManager {
+    PackageManager::new(root.to_path_buf(), root.join("opencli.toml")).unwrap()
+}
+
+#[tokio::test]
+async fn refuses_to_remove_files_outside_the_project() {
+    let (dir, root) = project("");
+    let outside = dir.path().join("outside.txt");
+    std::fs::write(&outside, "keep").unwrap();
+    let inside = root.join("pawno").join("include").join("evil.inc");
+    std::fs::create_dir_all(inside.parent().unwrap()).unwrap();
+    std::fs::write(&inside, "keep").unwrap();
+
+    for listed in [
+        "../outside.txt".to_string(),
+        "pawno/../../outside.txt".to_string(),
+        outside.display().to_string(),
+    ] {
+        std::fs::write(
+            root.join("opencli.lock"),
+            format!(
+                r#"
+schema_version = 2
+
+[installed."acme/evil"]
+version = "v1.0.0"
+hash = "hash"
+installed_at = "2026-01-01T00:00:00+00:00"
+files = ["pawno/include/evil.inc", '{}']
+"#,
+                listed
+            ),
+        )
+        .unwrap();
+
+        let error = manager(&root)
+            .remove_package("acme/evil")
+            .await
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("not a path inside the project"),
+            "{}",
+            error
+        );
+        assert!(outside.exists(), "{}", listed);
+        assert!(inside.exists(), "{}", listed);
+    }
+}
+
+#[tokio::test]
+async fn reinstalls_locked_packages_whose_files_were_edited() {
+    let (_dir, root) = project("\n[packages]\n\"acme/mylib\" = { path = \"vendor/mylib\" }\n");
+    let vendor = root.join("vendor").join("mylib");
+    std::fs::create_dir_all(&vendor).unwrap();
+    std::fs::write(vendor.join("mylib.inc"), "#define MYLIB\n").unwrap();
+
+    manager(&root).install_all_packages().await.unwrap();
+    let lock = PackageLock::load_from_file(&root.join("opencli.lock"))
+        .await
+        .unwrap();
+    let files = &lock.get_package("acme/mylib").unwrap().files;
+    assert_eq!(files.len(), 1);
+    assert!(Path::new(files[0].as_str()).is_relative());
+    let installed = root.join(files[0].as_str());
+    assert_eq!(
+        std::fs::read_to_string(&installed).unwrap(),
+        "#define MYLIB\n"
+    );
+
+    std::fs::write(&installed, "#define MYLIB\n#define PATCHED\n").unwrap();
+    manager(&root).install_locked_packages(false).await.unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&installed).unwrap(),
+        "#define MYLIB\n"
+    );
+
+    std::fs::remove_file(&installed).unwrap();
+    manager(&root).install_locked_packages(false).await.unwrap();
+    assert!(installed.exists());
+}
+
+fn tar_gz(files: &[(&str, &str)]) -> Vec<u8> {
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    for (path, data) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, path, data.as_bytes())
+            .unwrap();
+    }
+    builder.into_inner().unwrap().finish().unwrap()
+}
+
+/// Serves each `(name, delay)` as `/<name>.tar.gz`, answering after `delay`
+/// on its own thread. Returns the base URL and the most downloads that were
+/// in flight at once.
+fn serve_archives(archives: &[(&str, u64)]) -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let delays: Vec<(String, u64)> = archives
+        .iter()
+        .map(|(name, delay)| (format!("/{}.tar.gz", name), *delay))
+        .collect();
+    let (in_flight, most) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+    let peak = most.clone();
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let (delays, in_flight, most) = (delays.clone(), in_flight.clone(), most.clone());
+            std::thread::spawn(move || {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request = String::new();
+                reader.read_line(&mut request).unwrap();
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap() == 0 || line == "\r\n" {
+                        break;
+                    }
+                }
+
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                most.fetch_max(now, Ordering::SeqCst);
+                let path = request.split_whitespace().nth(1).unwrap_or_default();
+                let (path, delay) = delays.iter().find(|(served, _)| served == path).unwrap();
+                std::thread::sleep(Duration::from_millis(*delay));
+
+                let name = path.trim_start_matches('/').trim_end_matches(".tar.gz");
+                let include = format!("{}.inc", name);
+                let body = tar_gz(&[(include.as_str(), name), ("shared.inc", name)]);
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                let head = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/gzip\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                );
+                stream.write_all(head.as_bytes()).unwrap();
+                stream.write_all(&body).unwrap();
+            });
+        }
+    });
+
+    (url, peak)
+}
+
+#[tokio::test]
+async fn installs_in_plan_order_within_the_job_limit() {
+    // The first package finishes last and the last one first
+    let (url, most_in_flight) = serve_archives(&[("first", 400), ("second", 200), ("third", 0)]);
+    let (_dir, root) = project(&format!(
+        "\n[packages]\n\"acme/first\" = {{ url = \"{url}/first.tar.gz\" }}\n\"acme/second\" = {{ url = \"{url}/second.tar.gz\" }}\n\"acme/third\" = {{ url = \"{url}/third.tar.gz\" }}\n",
+        url = url
+    ));
+
+    let mut manager = manager(&root);
+    manager.set_jobs(2);
+    manager.install_all_packages().await.unwrap();
+
+    assert_eq!(most_in_flight.load(Ordering::SeqCst), 2);
+
+    // Placed in plan order, so the last package owns the shared file
+    let include = root.join("pawno").join("include");
+    assert_eq!(
+        std::fs::read_to_string(include.join("shared.inc")).unwrap(),
+        "third"
+    );
+    let lock = PackageLock::load_from_file(&root.join("opencli.lock"))
+        .await
+        .unwrap();
+    assert_eq!(
+        lock.installed
+            .keys()
+            .map(|name| name.as_str())
+            .collect::<Vec<_>>(),
+        ["acme/first", "acme/second", "acme/third"]
+    );
+    for name in ["first", "second", "third"] {
+        let package = lock.get_package(&format!("acme/{}", name)).unwrap();
+        assert_eq!(package.artifacts.len(), 1);
+        assert_eq!(package.artifacts[0].name, format!("{}.tar.gz", name));
+        assert!(include.join(format!("{}.inc", name)).exists());
+    }
+}
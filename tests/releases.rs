@@ -0,0 +1,526 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use opencli::package::{
+    DependencyResolver, PackageDownloader, PackageLock, PackageManager, PackageSource, Requirement,
+    Requirer, VersionConstraint,
+};
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// GitHub API and git host stand-in shared by every test of this binary, since the
+/// network settings pointing at it are loaded once per process.
+struct FakeApi {
+    url: String,
+    config_home: tempfile::TempDir,
+    routes: Arc<Mutex<HashMap<String, VecDeque<Vec<u8>>>>>,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl FakeApi {
+    /// Queues `responses` for requests whose path starts with `/repos/<repo>/`.
+    fn script(&self, repo: &str, responses: Vec<String>) {
+        self.script_bytes(
+            repo,
+            responses.into_iter().map(String::into_bytes).collect(),
+        );
+    }
+
+    fn script_bytes(&self, repo: &str, responses: Vec<Vec<u8>>) {
+        self.routes
+            .lock()
+            .unwrap()
+            .insert(format!("/repos/{}/", repo), responses.into());
+    }
+
+    fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    fn requests_for(&self, repo: &str) -> Vec<String> {
+        let prefix = format!("get /repos/{}/", repo);
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|request| request.starts_with(&prefix))
+            .cloned()
+            .collect()
+    }
+
+    fn cache_dir(&self) -> PathBuf {
+        self.config_home
+            .path()
+            .join("opencli")
+            .join("cache")
+            .join("releases")
+    }
+}
+
+fn api() -> &'static FakeApi {
+    static API: OnceLock<FakeApi> = OnceLock::new();
+    API.get_or_init(|| {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let config_home = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(config_home.path().join("opencli")).unwrap();
+        std::fs::write(
+            config_home.path().join("opencli").join("config.toml"),
+            format!(
+                "[network]\ngithub_api_url = \"{}\"\nrelease_cache_ttl = 0\n",
+                url
+            ),
+        )
+        .unwrap();
+        std::env::set_var("XDG_CONFIG_HOME", config_home.path());
+        std::env::set_var("XDG_DATA_HOME", config_home.path().join("data"));
+        std::env::remove_var("GITHUB_TOKEN");
+
+        let routes: Arc<Mutex<HashMap<String, VecDeque<Vec<u8>>>>> = Arc::default();
+        let requests: Arc<Mutex<Vec<String>>> = Arc::default();
+        let (served, recorded) = (routes.clone(), requests.clone());
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut head = String::new();
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap() == 0 || line == "\r\n" {
+                        break;
+                    }
+                    head.push_str(&line.to_ascii_lowercase());
+                }
+
+                let path = head
+                    .split_whitespace()
+                    .nth(1)
+                    .unwrap_or_default()
+                    .to_string();
+                recorded.lock().unwrap().push(head);
+                let response = served
+                    .lock()
+                    .unwrap()
+                    .iter_mut()
+                    .find(|(prefix, _)| path.starts_with(prefix.as_str()))
+                    .and_then(|(_, responses)| responses.pop_front())
+                    .unwrap_or_else(|| {
+                        response("500 Internal Server Error", "", "[]").into_bytes()
+                    });
+                stream.write_all(&response).unwrap();
+            }
+        });
+
+        FakeApi {
+            url,
+            config_home,
+            routes,
+            requests,
+        }
+    })
+}
+
+fn response(status: &str, headers: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {}\r\n{}Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        headers,
+        body.len(),
+        body
+    )
+}
+
+fn releases(tags: &[&str]) -> String {
+    let releases: Vec<String> = tags
+        .iter()
+        .enumerate()
+        .map(|(id, tag)| {
+            format!(
+                r#"{{"url":"https://api.example.com/r/{id}","html_url":"https://example.com/r/{id}","assets_url":"https://api.example.com/r/{id}/assets","upload_url":"https://uploads.example.com/r/{id}","tarball_url":null,"zipball_url":null,"id":{id},"node_id":"R{id}","tag_name":"{tag}","target_commitish":"main","name":null,"body":null,"draft":false,"prerelease":false,"created_at":null,"published_at":null,"author":null,"assets":[]}}"#,
+                id = id + 1,
+                tag = tag
+            )
+        })
+        .collect();
+    format!("[{}]", releases.join(","))
+}
+
+fn tags(releases: &[opencli::package::downloader::GitHubRelease]) -> Vec<&str> {
+    releases
+        .iter()
+        .map(|release| release.tag_name.as_str())
+        .collect()
+}
+
+#[tokio::test]
+async fn revalidates_cached_listings_with_the_etag() {
+    let api = api();
+    api.script(
+        "acme/cached",
+        vec![
+            response(
+                "200 OK",
+                "ETag: \"r1\"\r\n",
+                &releases(&["v1.1.0", "v1.0.0"]),
+            ),
+            response("304 Not Modified", "ETag: \"r1\"\r\n", ""),
+            response(
+                "403 Forbidden",
+                "X-RateLimit-Remaining: 0\r\nX-RateLimit-Reset: 1700000000\r\n",
+                r#"{"message":"API rate limit exceeded"}"#,
+            ),
+        ],
+    );
+    let downloader = PackageDownloader::new().unwrap();
+
+    for _ in 0..3 {
+        let page = downloader.get_release_page("acme/cached", 1).await.unwrap();
+        assert_eq!(tags(&page), ["v1.1.0", "v1.0.0"]);
+    }
+
+    let requests = api.requests_for("acme/cached");
+    assert_eq!(requests.len(), 3);
+    assert!(!requests[0].contains("if-none-match"));
+    assert!(requests[1].contains("if-none-match: \"r1\""));
+    assert!(requests[2].contains("if-none-match: \"r1\""));
+}
+
+#[tokio::test]
+async fn scopes_cached_listings_to_the_api_host() {
+    let api = api();
+    api.script(
+        "acme/scoped",
+        vec![response("200 OK", "", &releases(&["v2.0.0"]))],
+    );
+    let downloader = PackageDownloader::new().unwrap();
+    downloader.get_release_page("acme/scoped", 1).await.unwrap();
+
+    let entries: Vec<String> = std::fs::read_dir(api.cache_dir())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name.contains("acme_scoped"))
+        .collect();
+    assert_eq!(entries.len(), 1);
+    assert!(entries[0].starts_with("127.0.0.1_"), "{}", entries[0]);
+}
+
+/// A full page of releases `v<major>.<minor>.0`, newest first.
+fn full_page(major: u32, minors: std::ops::Range<u32>) -> String {
+    let tags: Vec<String> = minors
+        .rev()
+        .map(|minor| format!("v{}.{}.0", major, minor))
+        .collect();
+    assert_eq!(tags.len(), 100);
+    releases(&tags.iter().map(String::as_str).collect::<Vec<_>>())
+}
+
+fn constraints(inputs: &[&str]) -> Vec<VersionConstraint> {
+    inputs
+        .iter()
+        .map(|input| VersionConstraint::parse(input).unwrap())
+        .collect()
+}
+
+fn pages_requested(api: &FakeApi, repo: &str) -> Vec<String> {
+    api.requests_for(repo)
+        .iter()
+        .filter_map(|request| {
+            let path = request.split_whitespace().nth(1)?;
+            Some(path.rsplit("page=").next()?.to_string())
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn keeps_listing_pages_until_a_release_matches() {
+    let api = api();
+    api.script(
+        "acme/paged",
+        vec![
+            response("200 OK", "", &full_page(3, 0..100)),
+            response("200 OK", "", &full_page(1, 0..100)),
+            response("200 OK", "", &full_page(0, 0..100)),
+        ],
+    );
+    let downloader = PackageDownloader::new().unwrap();
+
+    let release = downloader
+        .find_release_matching_all("acme/paged", &constraints(&["^1.0.0", ">=1.4.0"]))
+        .await
+        .unwrap();
+    assert_eq!(release.tag_name, "v1.99.0");
+    assert_eq!(pages_requested(api, "acme/paged"), ["1", "2"]);
+
+    // The pages fetched so far answer later resolutions without the API
+    let release = downloader
+        .find_matching_version("acme/paged", &VersionConstraint::parse("~1.50.0").unwrap())
+        .await
+        .unwrap();
+    assert_eq!(release.tag_name, "v1.50.0");
+    assert_eq!(pages_requested(api, "acme/paged"), ["1", "2"]);
+}
+
+#[tokio::test]
+async fn stops_listing_once_a_page_is_below_every_lower_bound() {
+    let api = api();
+    api.script(
+        "acme/outdated",
+        vec![
+            response("200 OK", "", &full_page(2, 0..100)),
+            response("200 OK", "", &full_page(1, 0..100)),
+            response("200 OK", "", &full_page(0, 0..100)),
+        ],
+    );
+    let downloader = PackageDownloader::new().unwrap();
+
+    // The highest lower bound (2.150.0) applies, so page 1 already ends the search
+    let error = downloader
+        .find_release_matching_all("acme/outdated", &constraints(&[">=1.0.0", ">=2.150.0"]))
+        .await
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("100 releases checked"),
+        "{}",
+        error
+    );
+    assert_eq!(pages_requested(api, "acme/outdated"), ["1"]);
+}
+
+#[tokio::test]
+async fn explains_conflicting_requirements_with_their_requirers() {
+    let api = api();
+    api.script(
+        "acme/shared",
+        vec![response(
+            "200 OK",
+            "",
+            &releases(&["v2.1.0", "v2.0.0", "v1.4.0", "v1.0.0"]),
+        )],
+    );
+    let downloader = PackageDownloader::new().unwrap();
+    let lock = PackageLock::default();
+
+    let error = DependencyResolver::new(&downloader, &lock)
+        .resolve(
+            vec![Requirement::new(
+                "acme/shared",
+                "^2.0.0",
+                None,
+                Requirer::Project,
+            )],
+            vec![Requirement::new(
+                "acme/shared",
+                "^1.0.0",
+                None,
+                Requirer::Package {
+                    repo: "acme/app".into(),
+                    version: "v1.2.0".into(),
+                },
+            )],
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+
+    assert!(
+        error.contains("Conflicting version requirements for acme/shared"),
+        "{}",
+        error
+    );
+    assert!(
+        error.contains("^2.0.0 required by opencli.toml (newest match v2.1.0)"),
+        "{}",
+        error
+    );
+    assert!(
+        error.contains("^1.0.0 required by acme/app v1.2.0 (newest match v1.4.0)"),
+        "{}",
+        error
+    );
+    assert_eq!(api.requests_for("acme/shared").len(), 1);
+}
+
+#[tokio::test]
+async fn keeps_the_installed_version_when_an_update_fails_to_download() {
+    let api = api();
+    // The new release has no assets and its source archive is unavailable
+    api.script(
+        "acme/upd",
+        vec![response("200 OK", "", &releases(&["v2.0.0"]))],
+    );
+
+    let project = tempfile::tempdir().unwrap();
+    let root = project.path();
+    std::fs::write(
+        root.join("opencli.toml"),
+        r#"
+[build]
+entry_file = "gamemodes/main.pwn"
+output_file = "gamemodes/main.amx"
+compiler_version = "v3.10.11"
+
+[build.includes]
+paths = ["pawno/include"]
+
+[packages]
+"acme/upd" = "*"
+"#,
+    )
+    .unwrap();
+    let lock = r#"
+schema_version = 2
+
+[installed."acme/upd"]
+version = "v1.0.0"
+hash = "hash"
+installed_at = "2026-01-01T00:00:00+00:00"
+files = ["pawno/include/upd.inc"]
+"#;
+    std::fs::write(root.join("opencli.lock"), lock).unwrap();
+    let include = root.join("pawno").join("include").join("upd.inc");
+    std::fs::create_dir_all(include.parent().unwrap()).unwrap();
+    std::fs::write(&include, "#define UPD_V1\n").unwrap();
+
+    let mut manager = PackageManager::new(root.to_path_buf(), root.join("opencli.toml")).unwrap();
+    assert!(manager.update_package("acme/upd").await.is_err());
+
+    assert_eq!(
+        std::fs::read_to_string(&include).unwrap(),
+        "#define UPD_V1\n"
+    );
+    let lock = PackageLock::load_from_file(&root.join("opencli.lock"))
+        .await
+        .unwrap();
+    assert_eq!(lock.get_package("acme/upd").unwrap().version, "v1.0.0");
+}
+
+const MAIN_COMMIT: &str = "1f0c3f6a5be0c2b1de7d2a4b7e0f96a3c8d51e42";
+
+fn pkt_line(line: &str) -> String {
+    format!("{:04x}{}", line.len() + 4, line)
+}
+
+fn tar_gz(path: &str, data: &[u8]) -> Vec<u8> {
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(&mut header, path, data).unwrap();
+    builder.into_inner().unwrap().finish().unwrap()
+}
+
+#[tokio::test]
+async fn locks_git_sources_at_the_commit_of_their_branch() {
+    let api = api();
+    let advertisement = format!(
+        "{}0000{}{}0000",
+        pkt_line("# service=git-upload-pack\n"),
+        pkt_line(&format!(
+            "{} HEAD\0multi_ack side-band-64k symref=HEAD:refs/heads/main\n",
+            MAIN_COMMIT
+        )),
+        pkt_line(&format!("{} refs/heads/main\n", MAIN_COMMIT)),
+    );
+    api.script(
+        "acme/gitsrc.git",
+        vec![response("200 OK", "", &advertisement)],
+    );
+    let archive = tar_gz(
+        &format!("gitsrc-{}/gitsrc.inc", MAIN_COMMIT),
+        b"#define GITSRC\n",
+    );
+    let mut archive_response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/gzip\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        archive.len()
+    )
+    .into_bytes();
+    archive_response.extend(archive);
+    api.script_bytes("acme/gitsrc", vec![archive_response]);
+
+    let source = PackageSource::Git {
+        git: format!("{}/repos/acme/gitsrc", api.url()).into(),
+        rev: Some("main".into()),
+        forge: None,
+    };
+    let project = tempfile::tempdir().unwrap();
+    let downloader = PackageDownloader::new().unwrap();
+    let package_files = downloader
+        .download_source(
+            "acme/gitsrc",
+            &source,
+            project.path(),
+            &project.path().join("tmp"),
+            None,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(package_files.commit.as_deref(), Some(MAIN_COMMIT));
+    assert_eq!(package_files.includes.len(), 1);
+    assert_eq!(package_files.artifacts.len(), 1);
+    assert!(package_files.artifacts[0].url.ends_with(&format!(
+        "/repos/acme/gitsrc/archive/{}.tar.gz",
+        MAIN_COMMIT
+    )));
+
+    let refs = api.requests_for("acme/gitsrc.git");
+    assert_eq!(refs.len(), 1);
+    assert!(refs[0].contains("/info/refs?service=git-upload-pack"));
+}
+
+#[test]
+fn reads_the_rate_limit_reset_time() {
+    let build = |status: u16, headers: &[(&str, &str)]| {
+        let mut builder = http::Response::builder().status(status);
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body(()).unwrap()
+    };
+    let reset = |response| PackageDownloader::rate_limit_reset(&response);
+
+    let expected = chrono::DateTime::from_timestamp(1_700_000_000, 0)
+        .unwrap()
+        .with_timezone(&chrono::Local)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string();
+    assert_eq!(
+        reset(build(
+            403,
+            &[
+                ("x-ratelimit-remaining", "0"),
+                ("x-ratelimit-reset", "1700000000")
+            ]
+        )),
+        Some(expected)
+    );
+    assert!(reset(build(429, &[("retry-after", "60")])).is_some());
+    assert!(reset(build(403, &[("retry-after", "60")])).is_some());
+
+    assert_eq!(reset(build(403, &[])), None);
+    assert_eq!(
+        reset(build(
+            403,
+            &[
+                ("x-ratelimit-remaining", "12"),
+                ("x-ratelimit-reset", "1700000000")
+            ]
+        )),
+        None
+    );
+    assert_eq!(
+        reset(build(
+            200,
+            &[
+                ("x-ratelimit-remaining", "0"),
+                ("x-ratelimit-reset", "1700000000")
+            ]
+        )),
+        None
+    );
+    assert_eq!(reset(build(403, &[("x-ratelimit-remaining", "0")])), None);
+}
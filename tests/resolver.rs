@@ -0,0 +1,91 @@
+use opencli::package::resolver::install_order;
+use smol_str::SmolStr;
+use std::collections::BTreeMap;
+
+fn graph(edges: &[(&str, &[&str])]) -> BTreeMap<SmolStr, Vec<SmolStr>> {
+    edges
+        .iter()
+        .map(|(repo, dependencies)| {
+            (
+                SmolStr::from(*repo),
+                dependencies.iter().map(|d| SmolStr::from(*d)).collect(),
+            )
+        })
+        .collect()
+}
+
+fn repos(names: &[&str]) -> Vec<SmolStr> {
+    names.iter().map(|name| SmolStr::from(*name)).collect()
+}
+
+fn graph_without(repo: &str) -> BTreeMap<SmolStr, Vec<SmolStr>> {
+    let mut graph = graph(&[
+        ("acme/app", &["acme/http", "acme/util"]),
+        ("acme/http", &["acme/util"]),
+        ("acme/util", &["acme/strings"]),
+        ("acme/strings", &[]),
+    ]);
+    graph.remove(repo);
+    graph
+}
+
+#[test]
+fn installs_dependencies_before_their_dependents() {
+    let graph = graph(&[
+        ("acme/app", &["acme/http", "acme/util"]),
+        ("acme/http", &["acme/util", "acme/strings"]),
+        ("acme/util", &["acme/strings"]),
+        ("acme/strings", &[]),
+        ("acme/tool", &["acme/util"]),
+        ("acme/unused", &[]),
+    ]);
+
+    let (order, cycles) = install_order(&repos(&["acme/app", "acme/tool"]), &graph);
+    assert_eq!(
+        order,
+        repos(&[
+            "acme/strings",
+            "acme/util",
+            "acme/http",
+            "acme/app",
+            "acme/tool"
+        ])
+    );
+    assert!(cycles.is_empty());
+
+    // Dependencies that were not resolved are left out
+    let (order, _) = install_order(&repos(&["acme/app"]), &graph_without("acme/http"));
+    assert_eq!(order, repos(&["acme/strings", "acme/util", "acme/app"]));
+}
+
+#[test]
+fn breaks_dependency_cycles_and_reports_them() {
+    let graph = graph(&[
+        ("acme/app", &["acme/a"]),
+        ("acme/a", &["acme/b"]),
+        ("acme/b", &["acme/c", "acme/leaf"]),
+        ("acme/c", &["acme/a"]),
+        ("acme/leaf", &[]),
+        ("acme/self", &["acme/self"]),
+    ]);
+
+    let (order, cycles) = install_order(&repos(&["acme/app", "acme/self"]), &graph);
+    assert_eq!(
+        order,
+        repos(&[
+            "acme/c",
+            "acme/leaf",
+            "acme/b",
+            "acme/a",
+            "acme/app",
+            "acme/self"
+        ])
+    );
+    assert_eq!(
+        cycles,
+        vec![
+            repos(&["acme/a", "acme/b", "acme/c", "acme/a"]),
+            repos(&["acme/self", "acme/self"])
+        ]
+    );
+}
@@ -0,0 +1,133 @@
+use opencli::build::sampctl::SampctlProject;
+use opencli::build::PackageTarget;
+use opencli::package::PackageSource;
+
+#[test]
+fn converts_pawn_json_project() {
+    let project = SampctlProject::from_json(
+        r#"{
+            "user": "acme",
+            "repo": "gamemode",
+            "entry": "gamemodes/main.pwn",
+            "output": "gamemodes/main.amx",
+            "dependencies": [
+                "sampctl/samp-stdlib",
+                "pawn-lang/YSI-Includes:v5.10.006",
+                "Y-Less/sscanf",
+                "Zeex/amx_assembly@master",
+                "oscar-broman/strlib#70a4b1f"
+            ],
+            "builds": [
+                {
+                    "name": "main",
+                    "version": "3.10.10",
+                    "args": ["-d3", "-Z+"],
+                    "includes": ["legacy"],
+                    "constants": { "MAX_PLAYERS": "100" }
+                },
+                { "name": "old", "version": "3.10.8" }
+            ],
+            "runtime": {
+                "plugins": ["pBlueG/SA-MP-MySQL:R41-4", "crashdetect"],
+                "rcon_password": "changeme"
+            },
+            "local": true
+        }"#,
+    )
+    .unwrap();
+
+    let import = project.to_opencli();
+    let config = &import.config;
+
+    assert_eq!(config.build.entry_file.to_str(), Some("gamemodes/main.pwn"));
+    assert_eq!(
+        config.build.output_file.to_str(),
+        Some("gamemodes/main.amx")
+    );
+    assert_eq!(config.build.compiler_version, "v3.10.10");
+    assert_eq!(config.compiler_args(), ["-d3", "-Z+", "MAX_PLAYERS=100"]);
+    assert!(config
+        .get_include_paths()
+        .contains(&std::path::PathBuf::from("legacy")));
+    assert_eq!(
+        config.matrix_compiler_versions(),
+        ["v3.10.10".to_string(), "v3.10.8".to_string()]
+    );
+
+    let packages = config.get_packages().unwrap();
+    let names: Vec<&str> = packages.keys().map(|name| name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "Y-Less/sscanf",
+            "Zeex/amx_assembly",
+            "oscar-broman/strlib",
+            "pBlueG/SA-MP-MySQL",
+            "pawn-lang/YSI-Includes"
+        ]
+    );
+    for (repo, rev) in [
+        ("Zeex/amx_assembly", "master"),
+        ("oscar-broman/strlib", "70a4b1f"),
+    ] {
+        assert!(
+            matches!(packages[repo].source(), Some(PackageSource::Rev { rev: r }) if r == rev),
+            "{:?}",
+            packages[repo]
+        );
+    }
+    assert_eq!(packages["pawn-lang/YSI-Includes"].version(), "v5.10.006");
+    assert_eq!(packages["Y-Less/sscanf"].version(), "*");
+    assert!(matches!(
+        packages["pBlueG/SA-MP-MySQL"].target(),
+        Some(PackageTarget::Plugins)
+    ));
+    assert_eq!(
+        config.package.as_ref().and_then(|p| p.name.as_deref()),
+        Some("acme/gamemode")
+    );
+
+    for expected in [
+        "sampctl/samp-stdlib",
+        "build \"old\"",
+        "crashdetect",
+        "rcon_password",
+        "local",
+    ] {
+        assert!(
+            import.unmapped.iter().any(|item| item.contains(expected)),
+            "{} not reported in {:?}",
+            expected,
+            import.unmapped
+        );
+    }
+}
+
+#[test]
+fn converts_pawn_yaml_project() {
+    let project = SampctlProject::from_yaml(
+        r#"
+entry: test.pwn
+output: test.amx
+dependencies:
+  - pawn-lang/samp-stdlib
+  - oscar-broman/strlib
+build:
+  version: 3.10
+"#,
+    )
+    .unwrap();
+
+    let import = project.to_opencli();
+    assert_eq!(import.config.build.entry_file.to_str(), Some("test.pwn"));
+    assert_eq!(import.config.build.compiler_version, "v3.1");
+    assert!(import
+        .unmapped
+        .iter()
+        .any(|item| item.contains("written as a number")));
+    assert!(import
+        .config
+        .get_packages()
+        .unwrap()
+        .contains_key("oscar-broman/strlib"));
+}
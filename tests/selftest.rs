@@ -0,0 +1,102 @@
+use opencli::compiler::{detect_version, CompilerSelfTest, SelfTestReport};
+
+const PAWN_LANG_BANNER: &str = "Pawn compiler 3.10.10\t \t \tCopyright (c) 1997-2006, ITB CompuPhase\n\n\nHeader size:            100 bytes\nCode size:               36 bytes\nTotal requirements:   16488 bytes\n";
+const LEGACY_BANNER: &str =
+    "Pawn compiler 3.2.3664\t \t \tCopyright (c) 1997-2006, ITB CompuPhase\n";
+const LOADER_ERROR: &str = "pawncc: error while loading shared libraries: libpawnc.so: cannot open shared object file: No such file or directory\n";
+
+#[test]
+fn detects_the_version_in_compiler_banners() {
+    assert_eq!(detect_version(PAWN_LANG_BANNER).as_deref(), Some("3.10.10"));
+    assert_eq!(detect_version(LEGACY_BANNER).as_deref(), Some("3.2.3664"));
+    assert_eq!(
+        detect_version("PAWN COMPILER 3.10.11-rc1\n").as_deref(),
+        Some("3.10.11-rc1")
+    );
+
+    assert_eq!(detect_version(LOADER_ERROR), None);
+    assert_eq!(
+        detect_version("Usage:   pawncc <filename> [filename...] [options]\n"),
+        None
+    );
+    assert_eq!(detect_version("Pawn compiler\n"), None);
+}
+
+#[tokio::test]
+async fn round_trips_selftest_reports() {
+    let dir = tempfile::tempdir().unwrap();
+    assert!(SelfTestReport::load_from_dir(dir.path())
+        .await
+        .unwrap()
+        .is_none());
+
+    for version in [Some("3.10.10".to_string()), None] {
+        let report = SelfTestReport {
+            version: version.clone(),
+            tested_at: "2024-05-01T12:00:00+00:00".to_string(),
+        };
+        report.save_to_dir(dir.path()).await.unwrap();
+
+        let loaded = SelfTestReport::load_from_dir(dir.path())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded.version, version);
+        assert_eq!(loaded.tested_at, report.tested_at);
+    }
+}
+
+/// Runs fake compilers, one after another so no script is executed while
+/// another test still holds it open for writing.
+#[cfg(unix)]
+#[tokio::test]
+async fn records_the_version_a_compiler_reports() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let cases = [
+        (PAWN_LANG_BANNER, true, Some("3.10.10")),
+        ("Compiling selftest.pwn\n", true, None),
+        (LOADER_ERROR, false, None),
+    ];
+
+    for (index, (banner, compiles, expected)) in cases.into_iter().enumerate() {
+        let compiler_dir = dir.path().join(format!("compiler{}", index));
+        std::fs::create_dir_all(&compiler_dir).unwrap();
+        let compiler = compiler_dir.join("pawncc");
+
+        let output = if compiles {
+            "for arg in \"$@\"; do case \"$arg\" in -o*) : > \"${arg#-o}\";; esac; done\nexit 0"
+        } else {
+            "exit 127"
+        };
+        std::fs::write(
+            &compiler,
+            format!("#!/bin/sh\nprintf '%s' '{}'\n{}\n", banner, output),
+        )
+        .unwrap();
+        std::fs::set_permissions(&compiler, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let result = CompilerSelfTest::new(&dir.path().join("work"), &[])
+            .run(&compiler)
+            .await;
+
+        if !compiles {
+            let error = result.unwrap_err().to_string();
+            assert!(error.contains("libpawnc.so"), "{}", error);
+            assert!(SelfTestReport::load_from_dir(&compiler_dir)
+                .await
+                .unwrap()
+                .is_none());
+            continue;
+        }
+
+        let report = result.unwrap();
+        assert_eq!(report.version.as_deref(), expected);
+        let saved = SelfTestReport::load_from_dir(&compiler_dir)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(saved.version.as_deref(), expected);
+    }
+}
@@ -0,0 +1,177 @@
+use opencli::build::{BuildConfig, PackageSpec};
+use opencli::package::source::{find_advertised_commit, git_refs_url};
+use opencli::package::PackageSource;
+
+#[test]
+fn reads_non_github_package_sources() {
+    let config: BuildConfig = toml::from_str(
+        r#"
+[build]
+entry_file = "test.pwn"
+output_file = "test.amx"
+compiler_version = "v3.10.11"
+
+[packages]
+"acme/base" = "^1.0"
+"fork" = { git = "https://gitlab.com/me/fork.git", rev = "v1.2.0", target = "plugins" }
+"shared" = { path = "../includes" }
+"tool" = { url = "https://example.com/dl/tool.zip?raw=1", sha256 = "abc" }
+"#,
+    )
+    .unwrap();
+    let packages = config.get_packages().unwrap();
+
+    assert!(packages["acme/base"].source().is_none());
+    assert!(matches!(
+        packages["shared"].source(),
+        Some(PackageSource::Path { .. })
+    ));
+    assert!(packages["fork"].target().is_some());
+    assert_eq!(packages["fork"].version(), "*");
+
+    let fork = packages["fork"]
+        .source()
+        .unwrap()
+        .artifact()
+        .unwrap()
+        .unwrap();
+    assert_eq!(fork.name, "fork-v1.2.0.tar.gz");
+    assert_eq!(
+        fork.url,
+        "https://gitlab.com/me/fork/-/archive/v1.2.0/fork-v1.2.0.tar.gz"
+    );
+
+    let tool = packages["tool"]
+        .source()
+        .unwrap()
+        .artifact()
+        .unwrap()
+        .unwrap();
+    assert_eq!(tool.name, "tool.zip");
+    assert_eq!(tool.sha256.as_deref(), Some("abc"));
+
+    assert!(matches!(packages["shared"], PackageSpec::Source { .. }));
+    assert!(packages["shared"]
+        .source()
+        .unwrap()
+        .artifact()
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn builds_snapshot_urls_for_git_hosts() {
+    let archive_url = |git: &str, forge: Option<&str>| {
+        let forge = forge.map_or(String::new(), |forge| format!("\nforge = \"{}\"", forge));
+        let source: PackageSource =
+            toml::from_str(&format!("git = \"{}\"\nrev = \"feature/x\"{}", git, forge)).unwrap();
+        source.artifact().unwrap().unwrap().url.to_string()
+    };
+
+    assert_eq!(
+        archive_url("https://github.com/acme/lib", None),
+        "https://github.com/acme/lib/archive/feature/x.tar.gz"
+    );
+    assert_eq!(
+        archive_url("https://codeberg.org/acme/lib/", None),
+        "https://codeberg.org/acme/lib/archive/feature/x.tar.gz"
+    );
+    assert_eq!(
+        archive_url("https://git.example.com/acme/lib", Some("gitlab")),
+        "https://git.example.com/acme/lib/-/archive/feature/x/lib-feature-x.tar.gz"
+    );
+
+    let ssh: PackageSource =
+        toml::from_str("git = \"git@github.com:acme/lib.git\"\nrev = \"main\"").unwrap();
+    assert!(ssh.artifact().is_err());
+    let no_rev: PackageSource = toml::from_str("git = \"https://github.com/acme/lib\"").unwrap();
+    assert!(no_rev.artifact().is_err());
+}
+
+#[test]
+fn reads_github_refs_without_releases() {
+    let config: BuildConfig = toml::from_str(
+        r#"
+[build]
+entry_file = "test.pwn"
+output_file = "test.amx"
+compiler_version = "v3.10.11"
+
+[packages]
+"pawn-lang/YSI-Includes" = { rev = "5.x" }
+"acme/fork" = { git = "https://github.com/acme/fork", rev = "main" }
+"#,
+    )
+    .unwrap();
+    let packages = config.get_packages().unwrap();
+
+    let ysi = packages["pawn-lang/YSI-Includes"].source().unwrap();
+    assert_eq!(ysi, &PackageSource::Rev { rev: "5.x".into() });
+    assert_eq!(ysi.version_label(), "5.x");
+    assert!(ysi.artifact().unwrap().is_none());
+
+    assert!(matches!(
+        packages["acme/fork"].source(),
+        Some(PackageSource::Git { .. })
+    ));
+}
+
+fn pkt_line(line: &str) -> String {
+    format!("{:04x}{}", line.len() + 4, line)
+}
+
+#[test]
+fn resolves_revs_from_ref_advertisements() {
+    let head = "1f0c3f6a5be0c2b1de7d2a4b7e0f96a3c8d51e42";
+    let branch = "8a61b0c2d3e4f5a6b7c8d9e0f1a2b3c4d5e6f7a8";
+    let tag_object = "0b9d1f6e3c2a7b8d9e0f1a2b3c4d5e6f7a8b9c0d";
+    let tagged = "E3C2A7B8D9E0F1A2B3C4D5E6F7A8B9C0D0B9D1F6";
+    let advertisement = [
+        pkt_line("# service=git-upload-pack\n"),
+        "0000".to_string(),
+        pkt_line(&format!(
+            "{} HEAD\0multi_ack thin-pack side-band-64k symref=HEAD:refs/heads/main\n",
+            head
+        )),
+        pkt_line(&format!("{} refs/heads/main\n", head)),
+        pkt_line(&format!("{} refs/heads/v2.0\n", branch)),
+        pkt_line(&format!("{} refs/heads/feature/x\n", branch)),
+        pkt_line(&format!("{} refs/tags/v2.0\n", tag_object)),
+        pkt_line(&format!("{} refs/tags/v2.0^{{}}\n", tagged)),
+        pkt_line(&format!("{} refs/tags/light\n", branch)),
+        "0000".to_string(),
+    ]
+    .concat();
+    let resolve = |rev| find_advertised_commit(advertisement.as_bytes(), rev);
+
+    assert_eq!(resolve("main").as_deref(), Some(head));
+    assert_eq!(resolve("HEAD").as_deref(), Some(head));
+    assert_eq!(resolve("feature/x").as_deref(), Some(branch));
+    assert_eq!(resolve("light").as_deref(), Some(branch));
+    // Tags win over branches and annotated tags resolve to their commit
+    assert_eq!(
+        resolve("v2.0").as_deref(),
+        Some(tagged.to_ascii_lowercase().as_str())
+    );
+    assert_eq!(resolve("refs/heads/v2.0").as_deref(), Some(branch));
+
+    assert_eq!(resolve("missing"), None);
+    assert_eq!(resolve("1f0c3f6"), None);
+    assert_eq!(
+        find_advertised_commit(b"<html>Sign in</html>", "main"),
+        None
+    );
+}
+
+#[test]
+fn builds_ref_advertisement_urls() {
+    assert_eq!(
+        git_refs_url("https://gitlab.com/me/my-fork.git").unwrap(),
+        "https://gitlab.com/me/my-fork.git/info/refs?service=git-upload-pack"
+    );
+    assert_eq!(
+        git_refs_url("https://codeberg.org/acme/lib/").unwrap(),
+        "https://codeberg.org/acme/lib.git/info/refs?service=git-upload-pack"
+    );
+    assert!(git_refs_url("git@github.com:acme/lib.git").is_err());
+}
@@ -0,0 +1,137 @@
+use opencli::package::lock::LockedArtifact;
+use opencli::package::store::{place_file, LinkMode};
+use opencli::package::{PackageLock, PackageStore};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+
+const KEPT: &str = "1111111111111111111111111111111111111111111111111111111111111111";
+const UNUSED: &str = "2222222222222222222222222222222222222222222222222222222222222222";
+
+#[tokio::test]
+async fn stores_downloads_and_collects_unreferenced_entries() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = PackageStore::at(dir.path().join("store"));
+
+    for sha256 in [KEPT, UNUSED] {
+        let download = dir.path().join("download.zip");
+        std::fs::write(&download, sha256).unwrap();
+        let stored = store.add(&download, sha256, "pkg.zip").await.unwrap();
+        assert!(!download.exists());
+        assert_eq!(store.find(sha256, "pkg.zip"), Some(stored));
+    }
+    assert!(store.find(KEPT, "other.zip").is_none());
+
+    let lock_path = dir.path().join("project").join("opencli.lock");
+    let mut lock = PackageLock::default();
+    lock.add_package(
+        "acme/pkg".into(),
+        "v1.0.0".into(),
+        None,
+        "hash".into(),
+        Vec::new(),
+        BTreeMap::new(),
+    )
+    .artifacts = vec![LockedArtifact {
+        name: "pkg.zip".into(),
+        url: "https://example.com/pkg.zip".into(),
+        api_url: None,
+        size: None,
+        sha256: Some(KEPT.to_uppercase().into()),
+    }];
+    lock.save_to_file(&lock_path).await.unwrap();
+    store.remember_lock(&lock_path).await.unwrap();
+
+    let dry_run = store.gc(true).await.unwrap();
+    assert_eq!(dry_run.removed, vec![UNUSED]);
+    assert!(store.find(UNUSED, "pkg.zip").is_some());
+
+    let report = store.gc(false).await.unwrap();
+    assert_eq!(report.removed, vec![UNUSED]);
+    assert_eq!((report.kept, report.lock_files), (1, 1));
+    assert!(report.freed_bytes > 0);
+    assert!(store.find(UNUSED, "pkg.zip").is_none());
+    assert!(store.find(KEPT, "pkg.zip").is_some());
+
+    // A deleted project no longer keeps its packages
+    std::fs::remove_file(&lock_path).unwrap();
+    let report = store.gc(false).await.unwrap();
+    assert_eq!(report.removed, vec![KEPT]);
+    assert_eq!(report.lock_files, 0);
+}
+
+#[tokio::test]
+async fn replaces_installed_files_without_writing_through_links() {
+    let dir = tempfile::tempdir().unwrap();
+    let stored = dir.path().join("stored.inc");
+    let installed = dir.path().join("installed.inc");
+    std::fs::write(&stored, "stored").unwrap();
+
+    place_file(&stored, &installed, LinkMode::Hardlink)
+        .await
+        .unwrap();
+    assert_eq!(std::fs::read_to_string(&installed).unwrap(), "stored");
+
+    let other = dir.path().join("other.inc");
+    std::fs::write(&other, "other").unwrap();
+    place_file(&other, &installed, LinkMode::Copy)
+        .await
+        .unwrap();
+
+    assert_eq!(std::fs::read_to_string(&installed).unwrap(), "other");
+    assert_eq!(std::fs::read_to_string(&stored).unwrap(), "stored");
+}
+
+#[tokio::test]
+async fn shares_in_place_edits_of_hardlinked_files_with_the_store() {
+    let dir = tempfile::tempdir().unwrap();
+    let stored = dir.path().join("stored.inc");
+    let installed = dir.path().join("installed.inc");
+    std::fs::write(&stored, "stored").unwrap();
+
+    place_file(&stored, &installed, LinkMode::Hardlink)
+        .await
+        .unwrap();
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(&installed)
+        .unwrap()
+        .write_all(b" edited")
+        .unwrap();
+    assert_eq!(std::fs::read_to_string(&stored).unwrap(), "stored edited");
+
+    let copied = dir.path().join("copied.inc");
+    place_file(&stored, &copied, LinkMode::Copy).await.unwrap();
+    std::fs::write(&copied, "copied").unwrap();
+    assert_eq!(std::fs::read_to_string(&stored).unwrap(), "stored edited");
+}
+
+#[tokio::test]
+async fn remembers_lock_files_registered_concurrently() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = PackageStore::at(dir.path().join("store"));
+
+    let lock_paths: Vec<_> = (0..16)
+        .map(|index| {
+            let project = dir.path().join(format!("project{}", index));
+            std::fs::create_dir_all(&project).unwrap();
+            let lock_path = project.join("opencli.lock");
+            std::fs::write(&lock_path, "").unwrap();
+            lock_path
+        })
+        .collect();
+
+    let results =
+        futures_util::future::join_all(lock_paths.iter().map(|path| store.remember_lock(path)))
+            .await;
+    assert!(results.iter().all(Result::is_ok));
+
+    let remembered =
+        std::fs::read_to_string(dir.path().join("store").join("lockfiles.txt")).unwrap();
+    assert_eq!(remembered.lines().count(), lock_paths.len());
+    for path in &lock_paths {
+        let path = std::fs::canonicalize(path).unwrap();
+        assert!(remembered.lines().any(|line| Path::new(line) == path));
+    }
+    assert!(!dir.path().join("store").join("lockfiles.txt.lock").exists());
+}
@@ -0,0 +1,122 @@
+use opencli::build::ToolchainLocation;
+use opencli::compiler::{
+    compiler_command, CompilerManager, Invocation, PawnccToolchain, Toolchain,
+};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// A compiler manager reading an empty `compilers.toml` from a scratch
+/// config directory, so nothing is downloaded.
+async fn manager() -> CompilerManager {
+    static HOME: OnceLock<tempfile::TempDir> = OnceLock::new();
+    HOME.get_or_init(|| {
+        let home = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(home.path().join("opencli")).unwrap();
+        std::fs::write(home.path().join("opencli").join("compilers.toml"), "").unwrap();
+        std::env::set_var("XDG_CONFIG_HOME", home.path());
+        home
+    });
+    CompilerManager::new(ToolchainLocation::User).await.unwrap()
+}
+
+fn strings(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+/// The program and arguments `toolchain` runs to compile with `args`.
+fn invoke(toolchain: &PawnccToolchain, args: &[String]) -> (String, Vec<String>, Option<PathBuf>) {
+    let invocation = Invocation {
+        current_dir: Path::new("/project"),
+        entry_file: Path::new("gamemodes/main.pwn"),
+        output_file: Path::new("/project/gamemodes/main.amx"),
+        include_paths: vec![
+            PathBuf::from("/project/pawno/include"),
+            PathBuf::from("/project/dependencies"),
+        ],
+        args,
+    };
+    let cmd = toolchain.invoke(Path::new("/opt/pawncc"), &invocation);
+    let cmd = cmd.as_std();
+    (
+        cmd.get_program().to_string_lossy().into_owned(),
+        cmd.get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect(),
+        cmd.get_current_dir().map(Path::to_path_buf),
+    )
+}
+
+#[tokio::test]
+async fn passes_output_includes_and_arguments_before_the_entry_file() {
+    let toolchain = PawnccToolchain::new(manager().await);
+
+    let (program, args, current_dir) = invoke(&toolchain, &strings(&["-d1", "-O1", "-;+"]));
+
+    assert_eq!(program, "/opt/pawncc");
+    assert_eq!(
+        args,
+        strings(&[
+            "-o/project/gamemodes/main.amx",
+            "-i/project/pawno/include",
+            "-i/project/dependencies",
+            "-d1",
+            "-O1",
+            "-;+",
+            "gamemodes/main.pwn",
+        ])
+    );
+    assert_eq!(current_dir, Some(PathBuf::from("/project")));
+}
+
+#[tokio::test]
+async fn drops_optimisation_flags_under_full_debug_info() {
+    let toolchain = PawnccToolchain::new(manager().await);
+
+    for debug in ["-d2", "-d3"] {
+        let (_, args, _) = invoke(&toolchain, &strings(&[debug, "-O1", "-;+", "-O2"]));
+        assert_eq!(
+            &args[3..],
+            strings(&[debug, "-;+", "gamemodes/main.pwn"]),
+            "{}",
+            debug
+        );
+    }
+}
+
+#[tokio::test]
+async fn runs_pawncc_through_the_configured_wrapper() {
+    let toolchain = PawnccToolchain::new(manager().await.with_launcher(strings(&[
+        "qemu-i386",
+        "-L",
+        "/usr/i386-linux-gnu",
+    ])));
+
+    let (program, args, _) = invoke(&toolchain, &[]);
+
+    assert_eq!(program, "qemu-i386");
+    assert_eq!(
+        &args[..4],
+        strings(&[
+            "-L",
+            "/usr/i386-linux-gnu",
+            "/opt/pawncc",
+            "-o/project/gamemodes/main.amx"
+        ])
+    );
+    assert_eq!(args.last().map(String::as_str), Some("gamemodes/main.pwn"));
+}
+
+#[test]
+fn runs_the_compiler_directly_without_a_launcher() {
+    let direct = compiler_command(Path::new("/opt/pawncc"), &[]);
+    assert_eq!(direct.as_std().get_program(), OsStr::new("/opt/pawncc"));
+    assert_eq!(direct.as_std().get_args().count(), 0);
+
+    let wrapped = compiler_command(Path::new("/opt/pawncc"), &strings(&["box86"]));
+    assert_eq!(wrapped.as_std().get_program(), OsStr::new("box86"));
+    assert_eq!(
+        wrapped.as_std().get_args().collect::<Vec<_>>(),
+        vec![OsStr::new("/opt/pawncc")]
+    );
+}
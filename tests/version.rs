@@ -0,0 +1,19 @@
+use opencli::package::version::Version;
+use opencli::package::VersionConstraint;
+
+#[test]
+fn judges_breaking_updates_by_constraint_kind() {
+    let v = |input: &str| Version::parse(input).unwrap();
+    let caret = VersionConstraint::parse("^1.2.0").unwrap();
+    let tilde = VersionConstraint::parse("~1.2.0").unwrap();
+
+    assert!(!caret.is_breaking_update(&v("1.2.0"), &v("1.4.1")));
+    assert!(caret.is_breaking_update(&v("1.2.0"), &v("2.0.0")));
+    assert!(!tilde.is_breaking_update(&v("1.2.0"), &v("1.2.5")));
+    assert!(tilde.is_breaking_update(&v("1.2.0"), &v("1.3.0")));
+
+    // Below 1.0.0 a minor release is breaking under `^` semantics
+    let any = VersionConstraint::parse("*").unwrap();
+    assert!(any.is_breaking_update(&v("0.3.1"), &v("0.4.0")));
+    assert!(!any.is_breaking_update(&v("0.3.1"), &v("0.3.2")));
+}